@@ -36,9 +36,7 @@ impl ModelResponseParser for ClaudeParser {
                         match block_type {
                             "text" => {
                                 if let Some(text) = block.get("text").and_then(|t| t.as_str()) {
-                                    blocks.push(ContentBlock::Text {
-                                        text: text.to_string(),
-                                    });
+                                    blocks.push(ContentBlock::text(text));
                                 }
                             }
                             "tool_use" => {
@@ -63,7 +61,7 @@ impl ModelResponseParser for ClaudeParser {
             }
         }
 
-        Ok(ContentFrame { id, model, blocks })
+        Ok(ContentFrame::new(id, model, blocks))
     }
 }
 
@@ -89,7 +87,7 @@ fn main() {
             
             for (i, block) in frame.blocks.iter().enumerate() {
                 match block {
-                    ContentBlock::Text { text } => {
+                    ContentBlock::Text { text, .. } => {
                         println!("  Block {}: Text - {}", i, text);
                     }
                     _ => println!("  Block {}: Other block type", i),
@@ -49,9 +49,7 @@ impl ModelResponseParser for QwenParser {
                         let text_content = content.split("</think>").last().unwrap_or("").trim();
 
                         if !text_content.is_empty() {
-                            blocks.push(ContentBlock::Text {
-                                text: text_content.to_string(),
-                            });
+                            blocks.push(ContentBlock::text(text_content));
                         }
                     }
 
@@ -84,7 +82,7 @@ impl ModelResponseParser for QwenParser {
             }
         }
 
-        Ok(ContentFrame { id, model, blocks })
+        Ok(ContentFrame::new(id, model, blocks))
     }
 }
 
@@ -130,7 +128,7 @@ fn main() {
             
             for (i, block) in frame.blocks.iter().enumerate() {
                 match block {
-                    ContentBlock::Text { text } => {
+                    ContentBlock::Text { text, .. } => {
                         println!("  Block {}: Text - {}", i, text);
                     }
                     _ => println!("  Block {}: Other block type", i),
@@ -150,7 +148,7 @@ fn main() {
             
             for (i, block) in frame.blocks.iter().enumerate() {
                 match block {
-                    ContentBlock::Text { text } => {
+                    ContentBlock::Text { text, .. } => {
                         println!("  Block {}: Text - {}", i, text);
                     }
                     ContentBlock::Thinking { thinking, .. } => {
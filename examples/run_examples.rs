@@ -1,26 +1,10 @@
-use std::sync::Arc;
-use adaptogen::registry::ParserRegistry;
 use adaptogen::normalized::ContentFrame;
 use adaptogen::parser::ParseError;
 
-mod claude_parser;
-mod qwen_parser;
-
-use claude_parser::ClaudeParser;
-use qwen_parser::QwenParser;
-
-// Register your parsers 
-fn create_default_registry() -> ParserRegistry {
-    let mut registry = ParserRegistry::new();
-    registry.register_parser(Arc::new(QwenParser));
-    registry.register_parser(Arc::new(ClaudeParser));
-    registry
-}
-
-// simple convenience method to contruct the registy and call parse
+// The built-in parsers cover both responses below; `adaptogen::parse` uses a
+// registry created with `ParserRegistry::with_defaults()`
 fn parse(raw_response: &str) -> Result<ContentFrame, ParseError> {
-    let registry = create_default_registry();
-    registry.parse(raw_response)
+    adaptogen::parse(raw_response)
 }
 
 // Example usage
@@ -0,0 +1,351 @@
+//! Batch normalization of JSON Lines
+//!
+//! Logs of provider responses are commonly stored as JSON Lines, one raw
+//! response per line. [`ParserRegistry::parse_jsonl`] reads such a stream
+//! lazily and yields each line's result with its line number, so one bad line
+//! does not stop a backfill. With the `parallel` feature,
+//! [`ParserRegistry::parse_jsonl_parallel`] parses batches of lines on the
+//! rayon thread pool, still yielding results in line order.
+//!
+//! Both keep [`BatchStats`] of the results yielded so far.
+//!
+//! # Examples
+//!
+//! ```
+//! use adaptogen::registry::ParserRegistry;
+//!
+//! let log = "{\"id\": \"msg_1\", \"model\": \"claude-3-5-haiku-latest\", \"content\": []}\n\nnot json\n";
+//! let registry = ParserRegistry::with_defaults();
+//!
+//! let mut frames = registry.parse_jsonl(log.as_bytes());
+//! for (line, result) in &mut frames {
+//!     if let Err(error) = result {
+//!         eprintln!("line {line}: {error}");
+//!     }
+//! }
+//!
+//! let stats = frames.stats();
+//! assert_eq!(stats.total, 2);
+//! assert_eq!(stats.by_error["invalid json"], 1);
+//! ```
+
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{BufRead, ErrorKind};
+
+use crate::normalized::ContentFrame;
+use crate::parser::ParseError;
+use crate::registry::ParserRegistry;
+
+/// Lines parsed together by the parallel variant
+///
+/// Large enough to keep the thread pool busy, small enough that results
+/// arrive steadily and memory stays bounded.
+const PARALLEL_BATCH: usize = 256;
+
+/// Counts of the results of a batch
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchStats {
+    /// Responses read, not counting blank lines
+    pub total: usize,
+    /// Responses that parsed
+    pub parsed: usize,
+    /// Responses that failed to parse, or lines that could not be read
+    pub failed: usize,
+    /// Parsed responses by the model of their frame
+    pub by_model: BTreeMap<String, usize>,
+    /// Failures by kind of error
+    ///
+    /// The kinds are `invalid json`, `missing field`, `unsupported model`,
+    /// `other`, and for provider errors `provider error (<kind>)`, such as
+    /// `provider error (rate limit)`.
+    pub by_error: BTreeMap<String, usize>,
+}
+
+impl BatchStats {
+    /// Count one result
+    pub fn record(&mut self, result: &Result<ContentFrame, ParseError>) {
+        self.total += 1;
+        match result {
+            Ok(frame) => {
+                self.parsed += 1;
+                *self.by_model.entry(frame.model.clone()).or_default() += 1;
+            }
+            Err(error) => {
+                self.failed += 1;
+                *self.by_error.entry(error_kind(error)).or_default() += 1;
+            }
+        }
+    }
+}
+
+fn error_kind(error: &ParseError) -> String {
+    match error {
+        ParseError::InvalidJson(_) => "invalid json".to_string(),
+        ParseError::MissingField(_) => "missing field".to_string(),
+        ParseError::UnsupportedModel(_) => "unsupported model".to_string(),
+        ParseError::Other(_) => "other".to_string(),
+        ParseError::ProviderError { kind, .. } => format!("provider error ({kind})"),
+    }
+}
+
+/// Iterator over the results of a JSON Lines stream
+///
+/// Created by [`ParserRegistry::parse_jsonl`] and
+/// [`ParserRegistry::parse_jsonl_parallel`]. Yields `(line number, result)`
+/// pairs, numbered from 1, and skips blank lines. A line that is not valid
+/// UTF-8 yields a [`ParseError::Other`]; so does a read error, which also ends
+/// the iteration.
+pub struct JsonlFrames<'a, R> {
+    registry: &'a ParserRegistry,
+    reader: R,
+    line: usize,
+    done: bool,
+    parallel: bool,
+    pending: VecDeque<(usize, Result<ContentFrame, ParseError>)>,
+    stats: BatchStats,
+}
+
+impl<'a, R: BufRead> JsonlFrames<'a, R> {
+    pub(crate) fn new(registry: &'a ParserRegistry, reader: R, parallel: bool) -> Self {
+        JsonlFrames {
+            registry,
+            reader,
+            line: 0,
+            done: false,
+            parallel,
+            pending: VecDeque::new(),
+            stats: BatchStats::default(),
+        }
+    }
+
+    /// Counts of the results yielded so far
+    pub fn stats(&self) -> &BatchStats {
+        &self.stats
+    }
+
+    /// Read and parse the next line, or the next batch of lines when parallel
+    fn fill(&mut self) {
+        let size = if self.parallel { PARALLEL_BATCH } else { 1 };
+        let mut lines = Vec::with_capacity(size);
+        while lines.len() < size {
+            match self.read_line() {
+                Some(line) => lines.push(line),
+                None => break,
+            }
+        }
+
+        let registry = self.registry;
+        let parse = |(line, raw): (usize, Result<String, ParseError>)| {
+            (line, raw.and_then(|raw| registry.parse(&raw)))
+        };
+
+        #[cfg(feature = "parallel")]
+        if self.parallel {
+            use rayon::prelude::*;
+            let results: Vec<_> = lines.into_par_iter().map(parse).collect();
+            self.pending.extend(results);
+            return;
+        }
+        self.pending.extend(lines.into_iter().map(parse));
+    }
+
+    /// The next non-blank line and its number
+    fn read_line(&mut self) -> Option<(usize, Result<String, ParseError>)> {
+        let mut buffer = Vec::new();
+        while !self.done {
+            buffer.clear();
+            match self.reader.read_until(b'\n', &mut buffer) {
+                Ok(0) => self.done = true,
+                Ok(_) => {
+                    self.line += 1;
+                    let line = match String::from_utf8(std::mem::take(&mut buffer)) {
+                        Ok(line) => line,
+                        Err(error) => {
+                            return Some((
+                                self.line,
+                                Err(ParseError::Other(format!("Invalid UTF-8: {error}"))),
+                            ));
+                        }
+                    };
+                    if !line.trim().is_empty() {
+                        let line = line.trim_end_matches(['\n', '\r']).to_string();
+                        return Some((self.line, Ok(line)));
+                    }
+                }
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(error) => {
+                    self.done = true;
+                    return Some((
+                        self.line + 1,
+                        Err(ParseError::Other(format!("Failed to read line: {error}"))),
+                    ));
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<R: BufRead> Iterator for JsonlFrames<'_, R> {
+    type Item = (usize, Result<ContentFrame, ParseError>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pending.is_empty() {
+            self.fill();
+        }
+        let (line, result) = self.pending.pop_front()?;
+        self.stats.record(&result);
+        Some((line, result))
+    }
+}
+
+impl<R> std::fmt::Debug for JsonlFrames<'_, R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JsonlFrames")
+            .field("line", &self.line)
+            .field("done", &self.done)
+            .field("parallel", &self.parallel)
+            .field("stats", &self.stats)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::normalized::ContentBlock;
+    use crate::parser::{ModelResponseParser, ProviderErrorKind};
+    use serde_json::Value;
+    use std::io::{BufReader, Read};
+    use std::sync::Arc;
+
+    struct EchoParser;
+
+    impl ModelResponseParser for EchoParser {
+        fn supported_models(&self) -> Vec<String> {
+            vec!["echo-a".to_string(), "echo-b".to_string()]
+        }
+
+        fn parse(&self, raw_response: &str) -> Result<ContentFrame, ParseError> {
+            let json: Value = serde_json::from_str(raw_response)?;
+            let id = json["id"]
+                .as_str()
+                .ok_or_else(|| ParseError::MissingField("id".to_string()))?;
+            let model = json["model"].as_str().unwrap_or_default();
+            Ok(ContentFrame::new(
+                id,
+                model,
+                vec![ContentBlock::text("echo")],
+            ))
+        }
+    }
+
+    fn registry() -> ParserRegistry {
+        let registry = ParserRegistry::new();
+        registry.register_parser(Arc::new(EchoParser));
+        registry
+    }
+
+    const LOG: &str = concat!(
+        "{\"id\": \"1\", \"model\": \"echo-a\"}\n",
+        "\n",
+        "{\"id\": \"2\", \"model\": \"echo-b\"}\r\n",
+        "{\"model\": \"echo-a\"}\n",
+        "{\"id\": \"4\", \"model\": \"other\"}\n",
+        "{\"type\": \"error\", \"error\": {\"type\": \"rate_limit_error\", \"message\": \"Slow down\"}}\n",
+        "   \n",
+        "{\"id\": \"7\", \"model\": \"echo-a\"",
+    );
+
+    #[test]
+    fn test_parse_jsonl() {
+        let registry = registry();
+        let mut frames = registry.parse_jsonl(LOG.as_bytes());
+        let results: Vec<(usize, Result<ContentFrame, ParseError>)> = (&mut frames).collect();
+
+        let lines: Vec<usize> = results.iter().map(|(line, _)| *line).collect();
+        assert_eq!(lines, [1, 3, 4, 5, 6, 8]);
+        assert_eq!(results[1].1.as_ref().unwrap().id, "2");
+        assert!(matches!(results[2].1, Err(ParseError::MissingField(_))));
+        assert!(matches!(results[3].1, Err(ParseError::UnsupportedModel(_))));
+        assert!(matches!(
+            results[4].1,
+            Err(ParseError::ProviderError {
+                kind: ProviderErrorKind::RateLimit,
+                ..
+            })
+        ));
+        assert!(matches!(results[5].1, Err(ParseError::InvalidJson(_))));
+
+        let stats = frames.stats();
+        assert_eq!((stats.total, stats.parsed, stats.failed), (6, 2, 4));
+        assert_eq!(
+            stats.by_model,
+            BTreeMap::from([("echo-a".to_string(), 1), ("echo-b".to_string(), 1)])
+        );
+        assert_eq!(
+            stats.by_error,
+            BTreeMap::from([
+                ("invalid json".to_string(), 1),
+                ("missing field".to_string(), 1),
+                ("provider error (rate limit)".to_string(), 1),
+                ("unsupported model".to_string(), 1),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_read_errors() {
+        let registry = registry();
+        let input: &[u8] = b"{\"id\": \"1\", \"model\": \"echo-a\"}\n\xff\xfe\n{\"id\": \"3\", \"model\": \"echo-a\"}\n";
+        let results: Vec<_> = registry.parse_jsonl(input).collect();
+        assert_eq!(results.len(), 3);
+        assert!(
+            matches!(&results[1], (2, Err(ParseError::Other(message))) if message.contains("UTF-8"))
+        );
+        assert!(results[2].1.is_ok());
+
+        struct FailingReader(bool);
+        impl Read for FailingReader {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                if std::mem::replace(&mut self.0, true) {
+                    return Err(std::io::Error::other("disk on fire"));
+                }
+                let line = b"{\"id\": \"1\", \"model\": \"echo-a\"}\n";
+                buf[..line.len()].copy_from_slice(line);
+                Ok(line.len())
+            }
+        }
+        let results: Vec<_> = registry
+            .parse_jsonl(BufReader::new(FailingReader(false)))
+            .collect();
+        assert_eq!(results.len(), 2);
+        assert!(
+            matches!(&results[1], (2, Err(ParseError::Other(message))) if message.contains("disk on fire"))
+        );
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parse_jsonl_parallel_keeps_order() {
+        let log: String = (0..1000)
+            .map(|i| match i % 7 {
+                0 => "garbage\n".to_string(),
+                _ => format!("{{\"id\": \"{i}\", \"model\": \"echo-a\"}}\n"),
+            })
+            .collect();
+        let registry = registry();
+
+        let sequential: Vec<_> = registry
+            .parse_jsonl(log.as_bytes())
+            .map(|(line, r)| (line, r.map(|f| f.id).map_err(|e| e.to_string())))
+            .collect();
+        let mut frames = registry.parse_jsonl_parallel(log.as_bytes());
+        let parallel: Vec<_> = (&mut frames)
+            .map(|(line, r)| (line, r.map(|f| f.id).map_err(|e| e.to_string())))
+            .collect();
+
+        assert_eq!(parallel, sequential);
+        assert_eq!((frames.stats().parsed, frames.stats().failed), (857, 143));
+    }
+}
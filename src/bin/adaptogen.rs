@@ -0,0 +1,328 @@
+//! Command-line tool that normalizes provider responses into content frames
+//!
+//! Reads responses from files or standard input as single JSON documents,
+//! JSON Lines, or captured server-sent event streams, and writes the
+//! normalized [`ContentFrame`]s as JSON Lines or a JSON array.
+//!
+//! ```text
+//! adaptogen responses.jsonl -o frames.jsonl
+//! curl -N ... | adaptogen --format sse
+//! adaptogen --provider bedrock-converse --model amazon.nova-pro-v1:0 response.json
+//! ```
+
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+
+use clap::{Parser, ValueEnum};
+use serde_json::Value;
+
+use adaptogen::normalized::ContentFrame;
+use adaptogen::parser::{ModelResponseParser, ParseError};
+use adaptogen::providers;
+use adaptogen::registry::ParserRegistry;
+use adaptogen::stream::{self, SseDecoder};
+
+/// Normalize LLM provider responses into content frames
+#[derive(Debug, Parser)]
+#[command(name = "adaptogen", version)]
+struct Args {
+    /// Files to read; standard input is read if none are given, or for `-`
+    files: Vec<PathBuf>,
+
+    /// Input format; `auto` goes by the file extension, then the content
+    #[arg(short, long, value_enum, default_value_t = Format::Auto)]
+    format: Format,
+
+    /// Model the requests were sent to, for formats that do not include it
+    #[arg(short, long)]
+    model: Option<String>,
+
+    /// Parse with this provider's parser instead of picking one by model
+    #[arg(short, long, value_enum)]
+    provider: Option<Provider>,
+
+    /// File to write to instead of standard output
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Write one frame per line, or a single JSON array
+    #[arg(long, value_enum, default_value_t = OutputFormat::Jsonl)]
+    output_format: OutputFormat,
+
+    /// Stop at the first response that fails, exiting with status 1
+    #[arg(long)]
+    strict: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// Detect the format of each input
+    Auto,
+    /// One response per input
+    Json,
+    /// One response per line
+    Jsonl,
+    /// A captured stream of server-sent events
+    Sse,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// JSON Lines, one frame per line
+    Jsonl,
+    /// A pretty-printed JSON array of frames
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Provider {
+    Anthropic,
+    Openai,
+    OpenaiResponses,
+    Gemini,
+    BedrockConverse,
+    BedrockInvoke,
+    Cohere,
+    Mistral,
+    Ollama,
+}
+
+impl Provider {
+    /// The provider's parser, if its feature is enabled in this build
+    fn parser(self) -> Option<Arc<dyn ModelResponseParser>> {
+        match self {
+            #[cfg(feature = "anthropic")]
+            Provider::Anthropic => Some(Arc::new(providers::AnthropicParser)),
+            #[cfg(feature = "openai")]
+            Provider::Openai => Some(Arc::new(providers::OpenAiParser)),
+            #[cfg(feature = "openai")]
+            Provider::OpenaiResponses => Some(Arc::new(providers::OpenAiResponsesParser)),
+            #[cfg(feature = "gemini")]
+            Provider::Gemini => Some(Arc::new(providers::GeminiParser)),
+            #[cfg(feature = "bedrock")]
+            Provider::BedrockConverse => Some(Arc::new(providers::BedrockConverseParser)),
+            #[cfg(feature = "bedrock")]
+            Provider::BedrockInvoke => Some(Arc::new(providers::BedrockInvokeParser)),
+            #[cfg(feature = "cohere")]
+            Provider::Cohere => Some(Arc::new(providers::CohereParser)),
+            #[cfg(feature = "mistral")]
+            Provider::Mistral => Some(Arc::new(providers::MistralParser)),
+            #[cfg(feature = "ollama")]
+            Provider::Ollama => Some(Arc::new(providers::OllamaParser)),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+}
+
+/// A response that could not be read or normalized
+struct Failure {
+    source: String,
+    line: usize,
+    error: String,
+}
+
+/// Parses responses with either a fixed parser or the default registry
+struct Normalizer {
+    parser: Option<Arc<dyn ModelResponseParser>>,
+    registry: ParserRegistry,
+    model: Option<String>,
+}
+
+impl Normalizer {
+    fn parse(&self, body: &str) -> Result<ContentFrame, ParseError> {
+        let Some(parser) = &self.parser else {
+            return match &self.model {
+                Some(model) => self.registry.parse_with_model(body, model),
+                None => self.registry.parse(body),
+            };
+        };
+
+        // The registry reports provider errors; a parser on its own does not
+        let json: Value = serde_json::from_str(body)?;
+        if let Some(error) = providers::detect_provider_error(&json) {
+            return Err(error);
+        }
+        match &self.model {
+            Some(model) => parser.parse_with_model(body, model),
+            None => parser.parse(body),
+        }
+    }
+}
+
+/// Writes frames as they are parsed, or collects them for a JSON array
+struct Output {
+    writer: Box<dyn Write>,
+    format: OutputFormat,
+    frames: Vec<ContentFrame>,
+}
+
+impl Output {
+    fn write(&mut self, frame: ContentFrame) -> io::Result<()> {
+        match self.format {
+            OutputFormat::Jsonl => {
+                serde_json::to_writer(&mut self.writer, &frame)?;
+                self.writer.write_all(b"\n")
+            }
+            OutputFormat::Json => {
+                self.frames.push(frame);
+                Ok(())
+            }
+        }
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        if self.format == OutputFormat::Json {
+            serde_json::to_writer_pretty(&mut self.writer, &self.frames)?;
+            self.writer.write_all(b"\n")?;
+        }
+        self.writer.flush()
+    }
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    match run(&args) {
+        Ok(code) => code,
+        Err(error) => {
+            eprintln!("adaptogen: {error}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run(args: &Args) -> io::Result<ExitCode> {
+    let parser = match args.provider {
+        Some(provider) => match provider.parser() {
+            Some(parser) => Some(parser),
+            None => {
+                let name = provider.to_possible_value().map(|v| v.get_name().to_string()).unwrap_or_default();
+                eprintln!("adaptogen: the {name} parser is not enabled in this build");
+                return Ok(ExitCode::from(2));
+            }
+        },
+        None => None,
+    };
+    let normalizer = Normalizer {
+        parser,
+        registry: ParserRegistry::with_defaults(),
+        model: args.model.clone(),
+    };
+
+    let writer: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+    let mut output = Output {
+        writer,
+        format: args.output_format,
+        frames: Vec::new(),
+    };
+
+    let stdin = [PathBuf::from("-")];
+    let files = if args.files.is_empty() { &stdin[..] } else { &args.files[..] };
+
+    let mut parsed = 0;
+    let mut failures = Vec::new();
+    'files: for path in files {
+        let (source, raw) = match read(path) {
+            Ok(input) => input,
+            Err(error) => {
+                failures.push(Failure {
+                    source: path.display().to_string(),
+                    line: 0,
+                    error: error.to_string(),
+                });
+                if args.strict {
+                    break;
+                }
+                continue;
+            }
+        };
+
+        for (line, body) in responses(&raw, detect_format(args.format, path, &raw)) {
+            match body.and_then(|body| normalizer.parse(&body)) {
+                Ok(frame) => {
+                    output.write(frame)?;
+                    parsed += 1;
+                }
+                Err(error) => {
+                    failures.push(Failure {
+                        source: source.clone(),
+                        line,
+                        error: error.to_string(),
+                    });
+                    if args.strict {
+                        break 'files;
+                    }
+                }
+            }
+        }
+    }
+    output.finish()?;
+
+    if !failures.is_empty() {
+        eprintln!("adaptogen: {parsed} parsed, {} failed", failures.len());
+        for failure in &failures {
+            match failure.line {
+                0 => eprintln!("  {}: {}", failure.source, failure.error),
+                line => eprintln!("  {}:{line}: {}", failure.source, failure.error),
+            }
+        }
+    }
+
+    Ok(if args.strict && !failures.is_empty() { ExitCode::FAILURE } else { ExitCode::SUCCESS })
+}
+
+/// Read a file, or standard input for `-`, returning its display name and contents
+fn read(path: &Path) -> io::Result<(String, String)> {
+    if path == Path::new("-") {
+        let mut raw = String::new();
+        io::stdin().read_to_string(&mut raw)?;
+        Ok(("<stdin>".to_string(), raw))
+    } else {
+        Ok((path.display().to_string(), std::fs::read_to_string(path)?))
+    }
+}
+
+/// Resolve `auto` by the file extension, then by sniffing the content
+fn detect_format(format: Format, path: &Path, raw: &str) -> Format {
+    if format != Format::Auto {
+        return format;
+    }
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("jsonl" | "ndjson") => return Format::Jsonl,
+        Some("sse") => return Format::Sse,
+        Some("json") => return Format::Json,
+        _ => {}
+    }
+
+    let start = raw.trim_start();
+    if start.starts_with("data:") || start.starts_with("event:") || start.starts_with(':') {
+        Format::Sse
+    } else if serde_json::from_str::<serde::de::IgnoredAny>(raw).is_ok() {
+        Format::Json
+    } else {
+        Format::Jsonl
+    }
+}
+
+/// Split an input into response bodies, numbered by the line they start on
+fn responses(raw: &str, format: Format) -> Vec<(usize, Result<String, ParseError>)> {
+    match format {
+        Format::Jsonl => raw
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| (index + 1, Ok(line.to_string())))
+            .collect(),
+        Format::Sse => {
+            let body = stream::assemble(&SseDecoder::decode(raw)).map(|body| body.to_string());
+            vec![(1, body)]
+        }
+        Format::Json | Format::Auto => vec![(1, Ok(raw.to_string()))],
+    }
+}
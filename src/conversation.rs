@@ -0,0 +1,635 @@
+//! Conversation-level utilities over a sequence of content frames
+//!
+//! Providers reject a request whose history contains a tool call without a
+//! matching result, or a result without a preceding call. [`pair_tool_calls`]
+//! matches every [`ContentBlock::ToolUse`] with its [`ContentBlock::ToolResult`]
+//! so such problems can be found, and repaired, before the next request is sent.
+//!
+//! Providers also disagree on what a tool call id looks like. [`ToolIdMapper`]
+//! rewrites the ids of a conversation into the format a target provider
+//! accepts, and back.
+//!
+//! # Examples
+//!
+//! ```
+//! use adaptogen::conversation::pair_tool_calls;
+//! use adaptogen::normalized::{ContentBlock, ContentFrame};
+//! use serde_json::json;
+//!
+//! let conversation = vec![ContentFrame::new("msg_1", "claude-sonnet-4-5", vec![
+//!     ContentBlock::ToolUse { id: "toolu_1".to_string(), name: "get_weather".to_string(), input: json!({}) },
+//! ])];
+//!
+//! let report = pair_tool_calls(&conversation);
+//! assert!(!report.is_complete());
+//! assert_eq!(report.orphaned_calls[0].id, "toolu_1");
+//!
+//! // Answer the dangling call before continuing the conversation
+//! let results = report.placeholder_results();
+//! assert_eq!(results.len(), 1);
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::normalized::{ContentBlock, ContentFrame, ContentResultBlock};
+
+/// Content of the results created by [`ToolCallReport::placeholder_results`]
+pub const PLACEHOLDER_RESULT: &str = "Tool call was not completed";
+
+/// Location of a block within a conversation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlockPosition {
+    /// Index of the frame in the conversation
+    pub frame: usize,
+    /// Index of the block within the frame
+    pub block: usize,
+}
+
+/// A tool call together with its result
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolCallPair {
+    /// The shared tool use id
+    pub id: String,
+    /// Name of the tool that was called
+    pub name: String,
+    /// Position of the `ToolUse` block
+    pub call: BlockPosition,
+    /// Position of the `ToolResult` block
+    pub result: BlockPosition,
+}
+
+/// A tool call that no later result answers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrphanedCall {
+    /// The tool use id
+    pub id: String,
+    /// Name of the tool that was called
+    pub name: String,
+    /// Position of the `ToolUse` block
+    pub position: BlockPosition,
+}
+
+/// A tool result that answers no earlier call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrphanedResult {
+    /// The tool use id the result refers to
+    pub tool_use_id: String,
+    /// Position of the `ToolResult` block
+    pub position: BlockPosition,
+}
+
+/// A tool call reusing the id of a call still awaiting its result, or a
+/// result for a call that was already answered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateId {
+    /// The repeated id
+    pub id: String,
+    /// Position of the earlier block of the same kind with this id
+    pub first: BlockPosition,
+    /// Position of the repeated block, which is otherwise ignored
+    pub duplicate: BlockPosition,
+}
+
+/// Outcome of matching tool calls with their results across a conversation
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolCallReport {
+    /// Calls with a matching result, in the order the results appear
+    pub pairs: Vec<ToolCallPair>,
+    /// Calls without a result, in conversation order
+    pub orphaned_calls: Vec<OrphanedCall>,
+    /// Results without a preceding call, in conversation order
+    pub orphaned_results: Vec<OrphanedResult>,
+    /// Calls whose id is still awaiting a result, or results for calls already answered
+    pub duplicate_ids: Vec<DuplicateId>,
+}
+
+impl ToolCallReport {
+    /// Whether every call is answered exactly once and every result answers a call
+    pub fn is_complete(&self) -> bool {
+        self.orphaned_calls.is_empty()
+            && self.orphaned_results.is_empty()
+            && self.duplicate_ids.is_empty()
+    }
+
+    /// Error results answering each orphaned call
+    ///
+    /// Each result has `is_error: true` and [`PLACEHOLDER_RESULT`] as content,
+    /// ready to be sent in the next user message so the provider accepts the
+    /// conversation.
+    pub fn placeholder_results(&self) -> Vec<ContentBlock> {
+        self.orphaned_calls
+            .iter()
+            .map(|call| ContentBlock::ToolResult {
+                tool_use_id: call.id.clone(),
+                content: vec![ContentResultBlock {
+                    content: PLACEHOLDER_RESULT.to_string(),
+                }],
+                is_error: true,
+            })
+            .collect()
+    }
+}
+
+/// Match tool calls with their results across a sequence of frames
+///
+/// A result answers the unanswered call with the same id appearing before it.
+/// Once answered, an id may be used again by a later call, as the built-in
+/// parsers do when they number the calls of each response from `call_0`. A
+/// result placed before its call is reported as orphaned, as are the calls
+/// and results left unmatched. Server tool blocks are not considered, since
+/// the provider pairs them itself.
+pub fn pair_tool_calls(frames: &[ContentFrame]) -> ToolCallReport {
+    let mut report = ToolCallReport::default();
+
+    // Calls awaiting a result, by id, as indexes into `pending`
+    let mut pending: Vec<Option<OrphanedCall>> = Vec::new();
+    let mut open_calls: HashMap<&str, usize> = HashMap::new();
+    // Position of the latest result for each answered id
+    let mut answered: HashMap<&str, BlockPosition> = HashMap::new();
+
+    for (frame_index, frame) in frames.iter().enumerate() {
+        for (block_index, block) in frame.blocks.iter().enumerate() {
+            let position = BlockPosition {
+                frame: frame_index,
+                block: block_index,
+            };
+
+            match block {
+                ContentBlock::ToolUse { id, name, .. } => {
+                    let open = open_calls.get(id.as_str()).and_then(|&i| pending[i].as_ref());
+                    if let Some(first) = open {
+                        report.duplicate_ids.push(DuplicateId {
+                            id: id.clone(),
+                            first: first.position,
+                            duplicate: position,
+                        });
+                        continue;
+                    }
+                    open_calls.insert(id, pending.len());
+                    pending.push(Some(OrphanedCall {
+                        id: id.clone(),
+                        name: name.clone(),
+                        position,
+                    }));
+                }
+                ContentBlock::ToolResult { tool_use_id, .. } => {
+                    let open = open_calls.remove(tool_use_id.as_str());
+                    match open.and_then(|i| pending[i].take()) {
+                        Some(call) => {
+                            answered.insert(tool_use_id, position);
+                            report.pairs.push(ToolCallPair {
+                                id: call.id,
+                                name: call.name,
+                                call: call.position,
+                                result: position,
+                            });
+                        }
+                        None => match answered.get(tool_use_id.as_str()) {
+                            Some(first) => report.duplicate_ids.push(DuplicateId {
+                                id: tool_use_id.clone(),
+                                first: *first,
+                                duplicate: position,
+                            }),
+                            None => report.orphaned_results.push(OrphanedResult {
+                                tool_use_id: tool_use_id.clone(),
+                                position,
+                            }),
+                        },
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    report.orphaned_calls = pending.into_iter().flatten().collect();
+    report
+}
+
+/// Tool call id format expected by a provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ToolIdFormat {
+    /// `toolu_` followed by letters, digits, `_` or `-`
+    Anthropic,
+    /// `call_` followed by letters, digits, `_` or `-`
+    OpenAi,
+    /// Exactly 9 letters or digits
+    Mistral,
+    /// `call_<n>`, as filled in by the Gemini parser for calls without an id
+    ///
+    /// Gemini matches function responses to calls by name and order. The
+    /// Gemini parser numbers the calls of each response from `call_0`, so ids
+    /// are only unique within one response and repeat across turns.
+    Gemini,
+}
+
+impl ToolIdFormat {
+    /// Whether `id` is acceptable to the provider as is
+    pub fn is_valid(&self, id: &str) -> bool {
+        let is_id_char = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
+        let suffix_matches = |prefix: &str, valid: &dyn Fn(char) -> bool| {
+            id.strip_prefix(prefix)
+                .is_some_and(|rest| !rest.is_empty() && rest.chars().all(valid))
+        };
+
+        match self {
+            ToolIdFormat::Anthropic => suffix_matches("toolu_", &is_id_char),
+            ToolIdFormat::OpenAi => suffix_matches("call_", &is_id_char),
+            ToolIdFormat::Mistral => id.len() == 9 && id.chars().all(|c| c.is_ascii_alphanumeric()),
+            ToolIdFormat::Gemini => suffix_matches("call_", &|c| c.is_ascii_digit()),
+        }
+    }
+
+    /// The `n`th generated id in this format
+    fn generate(&self, n: usize) -> String {
+        match self {
+            ToolIdFormat::Anthropic => format!("toolu_{n:024}"),
+            ToolIdFormat::OpenAi => format!("call_{n:024}"),
+            ToolIdFormat::Mistral => format!("{n:09}"),
+            ToolIdFormat::Gemini => format!("call_{n}"),
+        }
+    }
+}
+
+impl fmt::Display for ToolIdFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ToolIdFormat::Anthropic => "anthropic",
+            ToolIdFormat::OpenAi => "openai",
+            ToolIdFormat::Mistral => "mistral",
+            ToolIdFormat::Gemini => "gemini",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Reversible mapping of tool call ids into a provider's [`ToolIdFormat`]
+///
+/// Ids already valid for the target provider are kept; others are replaced
+/// with generated ids. The mapping is remembered, so a conversation can be
+/// rewritten incrementally as it grows, and ids in the provider's responses
+/// can be restored to the originals with [`restore`](ToolIdMapper::restore).
+///
+/// An id that is used again by a later call once its first call was answered,
+/// as with the per-response ids of the Gemini and Ollama parsers, is given a
+/// new target id for each use.
+///
+/// # Examples
+///
+/// ```
+/// use adaptogen::conversation::{ToolIdFormat, ToolIdMapper};
+/// use adaptogen::normalized::{ContentBlock, ContentFrame};
+/// use serde_json::json;
+///
+/// let mut conversation = vec![ContentFrame::new("msg_1", "claude-sonnet-4-5", vec![
+///     ContentBlock::ToolUse { id: "toolu_01A09q90qw90lq917835lq9".to_string(), name: "get_weather".to_string(), input: json!({}) },
+/// ])];
+///
+/// let mut mapper = ToolIdMapper::new(ToolIdFormat::Mistral);
+/// mapper.rewrite(&mut conversation);
+/// match &conversation[0].blocks[0] {
+///     ContentBlock::ToolUse { id, .. } => assert!(ToolIdFormat::Mistral.is_valid(id)),
+///     _ => unreachable!(),
+/// }
+///
+/// mapper.restore(&mut conversation);
+/// match &conversation[0].blocks[0] {
+///     ContentBlock::ToolUse { id, .. } => assert_eq!(id, "toolu_01A09q90qw90lq917835lq9"),
+///     _ => unreachable!(),
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ToolIdMapper {
+    format: ToolIdFormat,
+    /// Target ids by original id and the number of earlier uses of that id
+    to_target: HashMap<(String, usize), String>,
+    to_original: HashMap<String, String>,
+    generated: usize,
+}
+
+impl ToolIdMapper {
+    /// Create an empty mapping into `format`
+    pub fn new(format: ToolIdFormat) -> Self {
+        Self {
+            format,
+            to_target: HashMap::new(),
+            to_original: HashMap::new(),
+            generated: 0,
+        }
+    }
+
+    /// The format ids are mapped into
+    pub fn format(&self) -> ToolIdFormat {
+        self.format
+    }
+
+    /// The target id for `id`, assigning one if `id` has not been seen before
+    ///
+    /// This is the target of the first use of `id`; see
+    /// [`rewrite`](ToolIdMapper::rewrite) for ids used again in later turns.
+    pub fn map_id(&mut self, id: &str) -> String {
+        self.map_use(id, 0)
+    }
+
+    /// The target id for the `reuse`th reuse of `id`
+    fn map_use(&mut self, id: &str, reuse: usize) -> String {
+        let key = (id.to_string(), reuse);
+        if let Some(target) = self.to_target.get(&key) {
+            return target.clone();
+        }
+
+        // Keep valid ids unless a generated id already took them
+        let target = if self.format.is_valid(id) && !self.to_original.contains_key(id) {
+            id.to_string()
+        } else {
+            loop {
+                let candidate = self.format.generate(self.generated);
+                self.generated += 1;
+                let is_original = self.to_target.contains_key(&(candidate.clone(), 0));
+                if !self.to_original.contains_key(&candidate) && !is_original {
+                    break candidate;
+                }
+            }
+        };
+
+        self.to_target.insert(key, target.clone());
+        self.to_original.insert(target.clone(), id.to_string());
+        target
+    }
+
+    /// The id `target` was mapped from, if it was assigned by this mapper
+    pub fn original_id(&self, target: &str) -> Option<&str> {
+        self.to_original.get(target).map(String::as_str)
+    }
+
+    /// Rewrite `ToolUse` ids and `ToolResult` tool use ids into the target format
+    ///
+    /// Calls and their results are given the same target id, so pairing is
+    /// preserved. A call reusing the id of an answered call gets a new target
+    /// id, so `frames` should hold the conversation from its start. Server tool
+    /// blocks are left untouched.
+    pub fn rewrite(&mut self, frames: &mut [ContentFrame]) {
+        // Number of reuses of each id, and whether its latest call is unanswered
+        let mut uses: HashMap<String, (usize, bool)> = HashMap::new();
+
+        for block in frames.iter_mut().flat_map(|frame| frame.blocks.iter_mut()) {
+            match block {
+                ContentBlock::ToolUse { id, .. } => {
+                    let reuse = match uses.get_mut(id.as_str()) {
+                        Some((reuse, open)) => {
+                            if !*open {
+                                *reuse += 1;
+                            }
+                            *open = true;
+                            *reuse
+                        }
+                        None => {
+                            uses.insert(id.clone(), (0, true));
+                            0
+                        }
+                    };
+                    *id = self.map_use(id, reuse);
+                }
+                ContentBlock::ToolResult { tool_use_id, .. } => {
+                    let reuse = match uses.get_mut(tool_use_id.as_str()) {
+                        Some((reuse, open)) => {
+                            *open = false;
+                            *reuse
+                        }
+                        None => 0,
+                    };
+                    *tool_use_id = self.map_use(tool_use_id, reuse);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Restore the original ids of blocks rewritten by [`rewrite`](ToolIdMapper::rewrite)
+    ///
+    /// Ids this mapper did not assign, such as those of new calls made by the
+    /// target provider, are left as they are.
+    pub fn restore(&self, frames: &mut [ContentFrame]) {
+        for id in tool_ids_mut(frames) {
+            if let Some(original) = self.to_original.get(id.as_str()) {
+                *id = original.clone();
+            }
+        }
+    }
+}
+
+/// Ids of the client tool calls and results in `frames`
+fn tool_ids_mut(frames: &mut [ContentFrame]) -> impl Iterator<Item = &mut String> {
+    frames
+        .iter_mut()
+        .flat_map(|frame| frame.blocks.iter_mut())
+        .filter_map(|block| match block {
+            ContentBlock::ToolUse { id, .. } => Some(id),
+            ContentBlock::ToolResult { tool_use_id, .. } => Some(tool_use_id),
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn call(id: &str) -> ContentBlock {
+        ContentBlock::ToolUse {
+            id: id.to_string(),
+            name: "lookup".to_string(),
+            input: json!({}),
+        }
+    }
+
+    fn result(id: &str) -> ContentBlock {
+        ContentBlock::ToolResult {
+            tool_use_id: id.to_string(),
+            content: vec![ContentResultBlock {
+                content: "ok".to_string(),
+            }],
+            is_error: false,
+        }
+    }
+
+    fn frame(blocks: Vec<ContentBlock>) -> ContentFrame {
+        ContentFrame::new("msg", "model", blocks)
+    }
+
+    #[test]
+    fn test_pairs_calls_with_results() {
+        let frames = vec![
+            frame(vec![ContentBlock::text("Looking up"), call("a"), call("b")]),
+            frame(vec![result("b"), result("a")]),
+        ];
+
+        let report = pair_tool_calls(&frames);
+        assert!(report.is_complete());
+        assert_eq!(report.pairs.len(), 2);
+        assert_eq!(report.pairs[0].id, "b");
+        assert_eq!(report.pairs[0].call, BlockPosition { frame: 0, block: 2 });
+        assert_eq!(report.pairs[0].result, BlockPosition { frame: 1, block: 0 });
+        assert!(report.placeholder_results().is_empty());
+    }
+
+    #[test]
+    fn test_reports_orphans_and_duplicates() {
+        let frames = vec![
+            frame(vec![result("early"), call("early"), call("a"), call("a")]),
+            frame(vec![result("a"), result("a"), result("unknown")]),
+        ];
+
+        let report = pair_tool_calls(&frames);
+        assert!(!report.is_complete());
+        assert_eq!(report.pairs.len(), 1);
+
+        // The result came before its call, so neither is matched
+        let orphaned_results: Vec<_> = report.orphaned_results.iter().map(|r| r.tool_use_id.as_str()).collect();
+        assert_eq!(orphaned_results, ["early", "unknown"]);
+        assert_eq!(report.orphaned_calls.len(), 1);
+        assert_eq!(report.orphaned_calls[0].id, "early");
+
+        assert_eq!(report.duplicate_ids.len(), 2);
+        assert_eq!(report.duplicate_ids[0].first, BlockPosition { frame: 0, block: 2 });
+        assert_eq!(report.duplicate_ids[0].duplicate, BlockPosition { frame: 0, block: 3 });
+        assert_eq!(report.duplicate_ids[1].duplicate, BlockPosition { frame: 1, block: 1 });
+    }
+
+    #[cfg(feature = "gemini")]
+    #[test]
+    fn test_reused_ids_across_gemini_turns() {
+        use crate::parser::ModelResponseParser;
+        use crate::providers::GeminiParser;
+
+        let response = r#"{"responseId": "r1", "modelVersion": "gemini-2.5-flash", "candidates": [{"content": {"role": "model",
+            "parts": [{"functionCall": {"name": "lookup", "args": {}}}]}}]}"#;
+        let turn = || GeminiParser.parse(response).unwrap();
+
+        // Every response numbers its calls from call_0
+        let frames = vec![
+            turn(),
+            frame(vec![result("call_0")]),
+            turn(),
+            frame(vec![result("call_0")]),
+        ];
+        let report = pair_tool_calls(&frames);
+        assert!(report.is_complete());
+        assert_eq!(report.pairs.len(), 2);
+        assert_eq!(report.pairs[1].call, BlockPosition { frame: 2, block: 0 });
+        assert_eq!(report.pairs[1].result, BlockPosition { frame: 3, block: 0 });
+    }
+
+    #[test]
+    fn test_placeholder_results() {
+        let frames = vec![frame(vec![call("a"), call("b")]), frame(vec![result("a")])];
+
+        let placeholders = pair_tool_calls(&frames).placeholder_results();
+        assert_eq!(placeholders.len(), 1);
+        match &placeholders[0] {
+            ContentBlock::ToolResult { tool_use_id, content, is_error } => {
+                assert_eq!(tool_use_id, "b");
+                assert_eq!(content[0].content, PLACEHOLDER_RESULT);
+                assert!(is_error);
+            }
+            _ => panic!("Expected ToolResult block"),
+        }
+
+        // Once answered, the conversation is complete
+        let mut frames = frames;
+        frames.push(frame(placeholders));
+        assert!(pair_tool_calls(&frames).is_complete());
+    }
+
+    #[test]
+    fn test_ignores_server_tools() {
+        let frames = vec![frame(vec![ContentBlock::ServerToolUse {
+            id: "srvtoolu_1".to_string(),
+            name: "web_search".to_string(),
+            input: json!({"query": "rust"}),
+        }])];
+
+        assert_eq!(pair_tool_calls(&frames), ToolCallReport::default());
+    }
+
+    #[test]
+    fn test_tool_id_formats() {
+        assert!(ToolIdFormat::Anthropic.is_valid("toolu_01A09q90qw90lq917835lq9"));
+        assert!(!ToolIdFormat::Anthropic.is_valid("call_abc"));
+        assert!(ToolIdFormat::OpenAi.is_valid("call_abc-123"));
+        assert!(!ToolIdFormat::OpenAi.is_valid("call_"));
+        assert!(ToolIdFormat::Mistral.is_valid("D681PevKs"));
+        assert!(!ToolIdFormat::Mistral.is_valid("call_D681P"));
+        assert!(ToolIdFormat::Gemini.is_valid("call_0"));
+        assert!(!ToolIdFormat::Gemini.is_valid("call_abc"));
+
+        for format in [ToolIdFormat::Anthropic, ToolIdFormat::OpenAi, ToolIdFormat::Mistral, ToolIdFormat::Gemini] {
+            assert!(format.is_valid(&format.generate(42)), "{format}");
+        }
+    }
+
+    #[test]
+    fn test_rewrite_and_restore_ids() {
+        let original = vec![
+            frame(vec![call("toolu_abc"), call("D681PevKs")]),
+            frame(vec![result("toolu_abc"), result("D681PevKs")]),
+        ];
+
+        let mut frames = original.clone();
+        let mut mapper = ToolIdMapper::new(ToolIdFormat::Mistral);
+        mapper.rewrite(&mut frames);
+
+        // Valid ids are kept and pairing survives the rewrite
+        let report = pair_tool_calls(&frames);
+        assert!(report.is_complete());
+        assert_eq!(report.pairs[1].id, "D681PevKs");
+        assert!(ToolIdFormat::Mistral.is_valid(&report.pairs[0].id));
+        assert_eq!(mapper.original_id(&report.pairs[0].id), Some("toolu_abc"));
+
+        // A new call from the target provider is left alone
+        frames.push(frame(vec![call("Xy12345ab")]));
+        mapper.restore(&mut frames);
+        let restored: Vec<_> = tool_ids_mut(&mut frames).map(|id| id.clone()).collect();
+        assert_eq!(restored, ["toolu_abc", "D681PevKs", "toolu_abc", "D681PevKs", "Xy12345ab"]);
+    }
+
+    #[test]
+    fn test_rewrite_reused_ids() {
+        // Gemini numbers the calls of every response from call_0
+        let original = vec![
+            frame(vec![call("call_0")]),
+            frame(vec![result("call_0")]),
+            frame(vec![call("call_0")]),
+            frame(vec![result("call_0")]),
+        ];
+
+        let mut frames = original.clone();
+        let mut mapper = ToolIdMapper::new(ToolIdFormat::Anthropic);
+        mapper.rewrite(&mut frames);
+
+        let report = pair_tool_calls(&frames);
+        assert!(report.is_complete());
+        assert_eq!(report.pairs.len(), 2);
+        assert_ne!(report.pairs[0].id, report.pairs[1].id);
+
+        // Rewriting the grown conversation again gives the same ids
+        let mut again = original.clone();
+        mapper.rewrite(&mut again);
+        assert_eq!(again, frames);
+
+        mapper.restore(&mut frames);
+        assert_eq!(frames, original);
+    }
+
+    #[test]
+    fn test_generated_ids_do_not_collide() {
+        let mut mapper = ToolIdMapper::new(ToolIdFormat::Gemini);
+        assert_eq!(mapper.map_id("toolu_1"), "call_0");
+        // A valid id already handed out is replaced rather than reused
+        assert_eq!(mapper.map_id("call_0"), "call_1");
+        assert_eq!(mapper.map_id("call_2"), "call_2");
+        assert_eq!(mapper.map_id("toolu_2"), "call_3");
+        assert_eq!(mapper.map_id("toolu_1"), "call_0");
+    }
+}
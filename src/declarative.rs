@@ -0,0 +1,640 @@
+//! Parsers defined by a mapping configuration instead of Rust code
+//!
+//! Many providers expose responses that only differ from each other in where
+//! fields live. A [`DeclarativeParser`] is configured with a [`ParserMapping`]
+//! that lists the JSON paths of each field, so a new provider can be supported
+//! by loading a JSON (or, with the `toml` feature, TOML) file.
+//!
+//! # Mapping format
+//!
+//! ```toml
+//! name = "acme"
+//! # Exact model names, or prefixes ending in `*`
+//! models = ["acme-chat", "acme-large-*"]
+//!
+//! id = "id"
+//! model = "model"
+//! text = "choices[0].message.content"
+//! thinking = "choices[0].message.reasoning"
+//! stop_reason = "choices[0].finish_reason"
+//!
+//! # Optional: thinking embedded in the text between tags
+//! [thinking_tags]
+//! open = "<think>"
+//! close = "</think>"
+//!
+//! # Optional: paths inside each tool call are relative to the tool call
+//! [tool_calls]
+//! path = "choices[0].message.tool_calls"
+//! id = "id"
+//! name = "function.name"
+//! arguments = "function.arguments"
+//!
+//! [usage]
+//! input_tokens = "usage.prompt_tokens"
+//! output_tokens = "usage.completion_tokens"
+//! ```
+//!
+//! Paths are dot-separated object keys with `[n]` array indices.
+
+use std::fmt;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::normalized::{ContentBlock, ContentFrame, Usage};
+use crate::parser::{ModelResponseParser, ParseError};
+use crate::providers::{decode_arguments, split_thinking};
+
+/// Error type for loading parser mappings
+#[derive(Debug, thiserror::Error)]
+pub enum MappingError {
+    /// The mapping file could not be read
+    #[error("Failed to read mapping: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// The mapping is not valid JSON or does not match the expected structure
+    #[error("Invalid JSON mapping: {0}")]
+    InvalidJson(#[from] serde_json::Error),
+
+    /// The mapping is not valid TOML or does not match the expected structure
+    #[cfg(feature = "toml")]
+    #[error("Invalid TOML mapping: {0}")]
+    InvalidToml(#[from] toml::de::Error),
+
+    /// The mapping file has an extension that is not supported
+    #[error("Unsupported mapping format: {0}")]
+    UnsupportedFormat(String),
+}
+
+/// A path to a value inside a JSON document, such as `choices[0].message.content`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct FieldPath {
+    raw: String,
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+impl FieldPath {
+    /// Resolve the path against a JSON value
+    pub fn resolve<'a>(&self, value: &'a Value) -> Option<&'a Value> {
+        self.segments
+            .iter()
+            .try_fold(value, |current, segment| match segment {
+                Segment::Key(key) => current.get(key),
+                Segment::Index(index) => current.get(index),
+            })
+    }
+
+    fn resolve_str<'a>(&self, value: &'a Value) -> Option<&'a str> {
+        self.resolve(value).and_then(|v| v.as_str())
+    }
+}
+
+impl TryFrom<String> for FieldPath {
+    type Error = String;
+
+    fn try_from(raw: String) -> Result<Self, Self::Error> {
+        let mut segments = Vec::new();
+
+        for part in raw.split('.') {
+            let (key, mut indices) = match part.find('[') {
+                Some(bracket) => (&part[..bracket], &part[bracket..]),
+                None => (part, ""),
+            };
+            if key.is_empty() && (segments.is_empty() || indices.is_empty()) {
+                return Err(format!("invalid path `{raw}`: empty key"));
+            }
+            if !key.is_empty() {
+                segments.push(Segment::Key(key.to_string()));
+            }
+
+            while !indices.is_empty() {
+                let index = indices
+                    .strip_prefix('[')
+                    .and_then(|rest| rest.split_once(']'))
+                    .and_then(|(index, rest)| Some((index.parse().ok()?, rest)));
+                let Some((index, rest)) = index else {
+                    return Err(format!("invalid path `{raw}`: malformed index in `{part}`"));
+                };
+                segments.push(Segment::Index(index));
+                indices = rest;
+            }
+        }
+
+        Ok(Self { raw, segments })
+    }
+}
+
+impl std::str::FromStr for FieldPath {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(s.to_string())
+    }
+}
+
+impl fmt::Display for FieldPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+fn default_model_path() -> FieldPath {
+    FieldPath {
+        raw: "model".to_string(),
+        segments: vec![Segment::Key("model".to_string())],
+    }
+}
+
+/// Delimiters of thinking embedded in the response text
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ThinkingTags {
+    /// Opening tag, such as `<think>`
+    pub open: String,
+    /// Closing tag, such as `</think>`
+    pub close: String,
+}
+
+/// Location of tool calls in a response
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ToolCallMapping {
+    /// Path to the array of tool calls
+    pub path: FieldPath,
+    /// Path to the tool call id, relative to a tool call
+    pub id: FieldPath,
+    /// Path to the tool name, relative to a tool call
+    pub name: FieldPath,
+    /// Path to the arguments, relative to a tool call; either an object or a
+    /// JSON-encoded string
+    pub arguments: FieldPath,
+}
+
+/// Location of token counts in a response
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UsageMapping {
+    /// Path to the number of prompt tokens
+    pub input_tokens: Option<FieldPath>,
+    /// Path to the number of generated tokens
+    pub output_tokens: Option<FieldPath>,
+}
+
+/// Configuration of a [`DeclarativeParser`]
+///
+/// See the [module documentation](self) for the file format.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParserMapping {
+    /// Human readable name of the provider
+    pub name: String,
+    /// Supported model names; entries ending in `*` match by prefix
+    pub models: Vec<String>,
+    /// Path to the response id
+    pub id: FieldPath,
+    /// Path to the model name, `model` by default
+    ///
+    /// The registry also resolves this path to route responses to the parser.
+    #[serde(default = "default_model_path")]
+    pub model: FieldPath,
+    /// Path to the response text
+    ///
+    /// The value may be a string or an array of strings or `{"text": ...}` parts.
+    pub text: Option<FieldPath>,
+    /// Path to reasoning returned in a separate field
+    pub thinking: Option<FieldPath>,
+    /// Tags delimiting reasoning embedded in the response text
+    pub thinking_tags: Option<ThinkingTags>,
+    /// Location of tool calls
+    pub tool_calls: Option<ToolCallMapping>,
+    /// Path to the stop reason
+    pub stop_reason: Option<FieldPath>,
+    /// Location of token counts
+    pub usage: Option<UsageMapping>,
+}
+
+/// A [`ModelResponseParser`] driven by a [`ParserMapping`]
+///
+/// Blocks are emitted in a fixed order: thinking from the `thinking` path,
+/// thinking from `thinking_tags`, text, then tool calls.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use adaptogen::declarative::DeclarativeParser;
+/// use adaptogen::registry::ParserRegistry;
+///
+/// let parser = DeclarativeParser::from_json_str(r#"{
+///     "name": "acme",
+///     "models": ["acme-*"],
+///     "id": "response.id",
+///     "model": "response.model",
+///     "text": "response.output[0].text"
+/// }"#).unwrap();
+///
+/// let registry = ParserRegistry::new();
+/// registry.register_parser(Arc::new(parser));
+///
+/// // The registry finds the model at the mapping's `model` path
+/// let frame = registry.parse(r#"{
+///     "response": {"id": "r1", "model": "acme-1", "output": [{"text": "Hi"}]}
+/// }"#).unwrap();
+/// assert_eq!(frame.id, "r1");
+/// ```
+#[derive(Debug, Clone)]
+pub struct DeclarativeParser {
+    mapping: ParserMapping,
+}
+
+impl DeclarativeParser {
+    /// Create a parser from a mapping
+    pub fn new(mapping: ParserMapping) -> Self {
+        Self { mapping }
+    }
+
+    /// Load a parser from a JSON mapping
+    pub fn from_json_str(config: &str) -> Result<Self, MappingError> {
+        Ok(Self::new(serde_json::from_str(config)?))
+    }
+
+    /// Load a parser from a TOML mapping
+    #[cfg(feature = "toml")]
+    pub fn from_toml_str(config: &str) -> Result<Self, MappingError> {
+        Ok(Self::new(toml::from_str(config)?))
+    }
+
+    /// Load a parser from a mapping file
+    ///
+    /// The format is chosen by file extension: `.json`, or `.toml` when the
+    /// `toml` feature is enabled.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, MappingError> {
+        let path = path.as_ref();
+        let config = std::fs::read_to_string(path)?;
+
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Self::from_json_str(&config),
+            #[cfg(feature = "toml")]
+            Some("toml") => Self::from_toml_str(&config),
+            _ => Err(MappingError::UnsupportedFormat(path.display().to_string())),
+        }
+    }
+
+    /// Load a parser from every supported mapping file in a directory
+    ///
+    /// Files with other extensions are ignored. Parsers are returned sorted by
+    /// file name so registration order is deterministic.
+    pub fn load_dir(dir: impl AsRef<Path>) -> Result<Vec<Self>, MappingError> {
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let extension = path.extension().and_then(|e| e.to_str());
+            let supported =
+                extension == Some("json") || (extension == Some("toml") && cfg!(feature = "toml"));
+            if supported && path.is_file() {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        paths.iter().map(Self::from_file).collect()
+    }
+
+    /// The name of the provider from the mapping
+    pub fn name(&self) -> &str {
+        &self.mapping.name
+    }
+
+    /// The mapping this parser was created from
+    pub fn mapping(&self) -> &ParserMapping {
+        &self.mapping
+    }
+
+    fn parse_blocks(&self, json: &Value) -> Vec<ContentBlock> {
+        let mapping = &self.mapping;
+        let mut blocks = Vec::new();
+
+        let thinking = mapping
+            .thinking
+            .as_ref()
+            .and_then(|path| path.resolve_str(json))
+            .map(str::trim)
+            .filter(|thinking| !thinking.is_empty());
+        if let Some(thinking) = thinking {
+            blocks.push(ContentBlock::Thinking {
+                thinking: Some(thinking.to_string()),
+                signature: None,
+            });
+        }
+
+        let text = mapping
+            .text
+            .as_ref()
+            .and_then(|path| path.resolve(json))
+            .and_then(text_value);
+        if let Some(text) = text {
+            let (thinking, text) = match &mapping.thinking_tags {
+                Some(tags) => split_thinking(&text, &tags.open, &tags.close),
+                None => (None, text),
+            };
+            if let Some(thinking) = thinking {
+                blocks.push(ContentBlock::Thinking {
+                    thinking: Some(thinking),
+                    signature: None,
+                });
+            }
+            if !text.is_empty() {
+                blocks.push(ContentBlock::text(text));
+            }
+        }
+
+        if let Some(tool_calls) = &mapping.tool_calls {
+            let calls = tool_calls
+                .path
+                .resolve(json)
+                .and_then(|calls| calls.as_array());
+            for call in calls.into_iter().flatten() {
+                let (Some(id), Some(name)) = (
+                    tool_calls.id.resolve_str(call),
+                    tool_calls.name.resolve_str(call),
+                ) else {
+                    continue;
+                };
+                blocks.push(ContentBlock::ToolUse {
+                    id: id.to_string(),
+                    name: name.to_string(),
+                    input: decode_arguments(tool_calls.arguments.resolve(call)),
+                });
+            }
+        }
+
+        blocks
+    }
+
+    fn parse_usage(&self, json: &Value) -> Option<Usage> {
+        let usage = self.mapping.usage.as_ref()?;
+        let count = |path: &Option<FieldPath>| {
+            path.as_ref()
+                .and_then(|path| path.resolve(json))
+                .and_then(|v| v.as_u64())
+        };
+
+        let (input_tokens, output_tokens) = (count(&usage.input_tokens), count(&usage.output_tokens));
+        if input_tokens.is_none() && output_tokens.is_none() {
+            return None;
+        }
+
+        Some(Usage {
+            input_tokens: input_tokens.unwrap_or(0),
+            output_tokens: output_tokens.unwrap_or(0),
+        })
+    }
+}
+
+/// Text is either a string or a list of strings or `{"text": ...}` parts
+fn text_value(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Array(parts) => Some(
+            parts
+                .iter()
+                .filter_map(|part| part.as_str().or_else(|| part.get("text")?.as_str()))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+impl ModelResponseParser for DeclarativeParser {
+    fn supported_models(&self) -> Vec<String> {
+        self.mapping.models.clone()
+    }
+
+    fn parse(&self, raw_response: &str) -> Result<ContentFrame, ParseError> {
+        let json: Value = serde_json::from_str(raw_response)?;
+        let mapping = &self.mapping;
+
+        let required = |path: &FieldPath| {
+            path.resolve_str(&json)
+                .map(|s| s.to_string())
+                .ok_or_else(|| ParseError::MissingField(path.to_string()))
+        };
+        let id = required(&mapping.id)?;
+        let model = required(&mapping.model)?;
+
+        Ok(ContentFrame {
+            id,
+            model,
+            blocks: self.parse_blocks(&json),
+            stop_reason: mapping
+                .stop_reason
+                .as_ref()
+                .and_then(|path| path.resolve_str(&json))
+                .map(|s| s.to_string()),
+            usage: self.parse_usage(&json),
+            safety: None,
+            ..Default::default()
+        })
+    }
+
+    fn can_handle(&self, model: &str) -> bool {
+        self.mapping
+            .models
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => model.starts_with(prefix),
+                None => model == pattern,
+            })
+    }
+
+    /// Recognizes responses whose model, at the mapping's `model` path, is supported
+    fn can_handle_response(&self, response: &Value) -> bool {
+        self.mapping.model.resolve_str(response).is_some_and(|model| self.can_handle(model))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const OPENAI_LIKE: &str = r#"{
+        "name": "openai-like",
+        "models": ["acme-chat", "acme-large-*"],
+        "id": "id",
+        "text": "choices[0].message.content",
+        "thinking": "choices[0].message.reasoning",
+        "thinking_tags": {"open": "<think>", "close": "</think>"},
+        "tool_calls": {
+            "path": "choices[0].message.tool_calls",
+            "id": "id",
+            "name": "function.name",
+            "arguments": "function.arguments"
+        },
+        "stop_reason": "choices[0].finish_reason",
+        "usage": {
+            "input_tokens": "usage.prompt_tokens",
+            "output_tokens": "usage.completion_tokens"
+        }
+    }"#;
+
+    #[test]
+    fn test_field_path() {
+        let path: FieldPath = "choices[0].message.content".parse().unwrap();
+        let value = json!({"choices": [{"message": {"content": "Hi"}}]});
+        assert_eq!(path.resolve(&value), Some(&json!("Hi")));
+        assert_eq!(path.to_string(), "choices[0].message.content");
+
+        let nested: FieldPath = "matrix[1][0]".parse().unwrap();
+        assert_eq!(nested.resolve(&json!({"matrix": [[1], [2]]})), Some(&json!(2)));
+
+        assert!("a..b".parse::<FieldPath>().is_err());
+        assert!("a[x]".parse::<FieldPath>().is_err());
+        assert!("a[0".parse::<FieldPath>().is_err());
+    }
+
+    #[test]
+    fn test_can_handle_patterns() {
+        let parser = DeclarativeParser::from_json_str(OPENAI_LIKE).unwrap();
+        assert!(parser.can_handle("acme-chat"));
+        assert!(parser.can_handle("acme-large-2"));
+        assert!(!parser.can_handle("acme-chat-2"));
+    }
+
+    #[test]
+    fn test_parse_with_mapping() {
+        let parser = DeclarativeParser::from_json_str(OPENAI_LIKE).unwrap();
+        let response = r#"{
+            "id": "r1",
+            "model": "acme-chat",
+            "choices": [{
+                "message": {
+                    "content": "<think>Looking it up</think>Let me check.",
+                    "tool_calls": [
+                        {"id": "c1", "function": {"name": "lookup", "arguments": "{\"q\": \"x\"}"}},
+                        {"function": {"name": "missing_id"}}
+                    ]
+                },
+                "finish_reason": "tool_calls"
+            }],
+            "usage": {"prompt_tokens": 3, "completion_tokens": 4}
+        }"#;
+
+        let frame = parser.parse(response).unwrap();
+        assert_eq!(frame.id, "r1");
+        assert_eq!(frame.model, "acme-chat");
+        assert_eq!(frame.stop_reason.as_deref(), Some("tool_calls"));
+        assert_eq!(frame.usage.unwrap().total_tokens(), 7);
+        assert_eq!(frame.blocks.len(), 3);
+
+        match &frame.blocks[0] {
+            ContentBlock::Thinking { thinking, .. } => {
+                assert_eq!(thinking.as_deref(), Some("Looking it up"))
+            }
+            _ => panic!("Expected Thinking block"),
+        }
+        match &frame.blocks[1] {
+            ContentBlock::Text { text, .. } => assert_eq!(text, "Let me check."),
+            _ => panic!("Expected Text block"),
+        }
+        match &frame.blocks[2] {
+            ContentBlock::ToolUse { id, name, input } => {
+                assert_eq!(id, "c1");
+                assert_eq!(name, "lookup");
+                assert_eq!(input["q"], "x");
+            }
+            _ => panic!("Expected ToolUse block"),
+        }
+    }
+
+    #[test]
+    fn test_missing_id_reports_path() {
+        let parser = DeclarativeParser::from_json_str(
+            r#"{"name": "n", "models": ["m"], "id": "meta.id"}"#,
+        )
+        .unwrap();
+
+        match parser.parse(r#"{"model": "m"}"#).unwrap_err() {
+            ParseError::MissingField(field) => assert_eq!(field, "meta.id"),
+            _ => panic!("Expected MissingField error"),
+        }
+    }
+
+    #[test]
+    fn test_invalid_mapping() {
+        let unknown_field = r#"{"name": "n", "models": [], "id": "id", "txt": "x"}"#;
+        assert!(matches!(
+            DeclarativeParser::from_json_str(unknown_field),
+            Err(MappingError::InvalidJson(_))
+        ));
+
+        let bad_path = r#"{"name": "n", "models": [], "id": "id[oops]"}"#;
+        assert!(DeclarativeParser::from_json_str(bad_path).is_err());
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_from_toml_str() {
+        let parser = DeclarativeParser::from_toml_str(
+            r#"
+            name = "acme"
+            models = ["acme-*"]
+            id = "id"
+            text = "output"
+            "#,
+        )
+        .unwrap();
+
+        let frame = parser
+            .parse(r#"{"id": "1", "model": "acme-1", "output": ["Hello, ", "world"]}"#)
+            .unwrap();
+        match &frame.blocks[0] {
+            ContentBlock::Text { text, .. } => assert_eq!(text, "Hello, world"),
+            _ => panic!("Expected Text block"),
+        }
+    }
+
+    #[test]
+    fn test_registry_routes_by_nested_model() {
+        let parser = DeclarativeParser::from_json_str(
+            r#"{"name": "nested", "models": ["acme-*"], "id": "meta.id", "model": "meta.model", "text": "output"}"#,
+        )
+        .unwrap();
+        let registry = crate::registry::ParserRegistry::new();
+        registry.register_parser(std::sync::Arc::new(parser));
+
+        let frame = registry
+            .parse(r#"{"meta": {"id": "r1", "model": "acme-2"}, "output": "Hi"}"#)
+            .unwrap();
+        assert_eq!((frame.id.as_str(), frame.model.as_str(), frame.text().as_str()), ("r1", "acme-2", "Hi"));
+
+        assert!(matches!(
+            registry.parse(r#"{"meta": {"id": "r1", "model": "other"}, "output": "Hi"}"#),
+            Err(ParseError::MissingField(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_dir() {
+        let dir = std::env::temp_dir().join(format!("adaptogen-mappings-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("b.json"), r#"{"name": "b", "models": ["b"], "id": "id"}"#).unwrap();
+        std::fs::write(dir.join("a.json"), r#"{"name": "a", "models": ["a"], "id": "id"}"#).unwrap();
+        std::fs::write(dir.join("notes.txt"), "not a mapping").unwrap();
+
+        let parsers = DeclarativeParser::load_dir(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let names: Vec<&str> = parsers.iter().map(|p| p.name()).collect();
+        assert_eq!(names, vec!["a", "b"]);
+    }
+}
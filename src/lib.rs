@@ -8,8 +8,29 @@
 //!
 //! - **Model-agnostic parsing**: Parse responses from any LLM provider to a consistent format
 //! - **Extensible architecture**: Easily implement custom parsers for new models
-//! - **Registry system**: Simple registration and lookup of appropriate parsers for any given model
+//! - **Registry system**: Thread-safe registration, replacement and lookup of appropriate parsers for any given model
 //! - **Normalized content blocks**: Consistent representation of text, tool calls, tool results, and thinking blocks
+//! - **Batch parsing**: Normalize JSON Lines logs line by line, optionally in parallel (`parallel` feature), with per-line errors and stats
+//! - **Streaming**: Reassemble server-sent event streams into regular responses, follow them as normalized delta events, also over async bodies (`async` feature), and re-encode them as OpenAI or Anthropic streams
+//! - **Typed tools**: Describe tools as Rust types and decode tool calls into them
+//! - **Tool call pairing**: Match tool calls with their results across a conversation and answer dangling calls
+//! - **JSON Schema**: A versioned JSON Schema of the normalized format for consumers in other languages (`schema` feature)
+//! - **Test kit**: Snapshot tests, conformance checks and fuzz-style inputs for custom parsers (`testkit` feature)
+//! - **Built-in parsers**: Anthropic, OpenAI-compatible, Gemini, AWS Bedrock, Cohere, Mistral and Ollama parsers, each behind a cargo feature
+//! - **Command-line tool**: Normalize response files, JSON Lines and captured streams from the shell (`cli` feature)
+//!
+//! ## Built-in Parsers
+//!
+//! For the providers that ship with the crate, no setup is needed:
+//!
+//! ```
+//! # #[cfg(feature = "anthropic")]
+//! # {
+//! let response = r#"{"id": "msg_1", "model": "claude-3-5-haiku-latest", "content": [{"type": "text", "text": "Hi"}]}"#;
+//! let frame = adaptogen::parse(response).unwrap();
+//! assert_eq!(frame.blocks.len(), 1);
+//! # }
+//! ```
 //!
 //! ## Basic Usage
 //!
@@ -32,13 +53,14 @@
 //!         Ok(ContentFrame {
 //!             id: "msg_123".to_string(),
 //!             model: "model_name".to_string(),
-//!             blocks: vec![]
+//!             blocks: vec![],
+//!             ..Default::default()
 //!         })
 //!     }
 //! }
 //!
 //! // Create a registry and register parsers
-//! let mut registry = ParserRegistry::new();
+//! let registry = ParserRegistry::new();
 //!
 //! // Register your parser
 //! registry.register_parser(Arc::new(ExampleParser));
@@ -53,6 +75,81 @@
 //! }
 //! ```
 
+use std::sync::OnceLock;
+
+use normalized::{ContentFrame, ParsedResponse};
+use parser::ParseError;
+use registry::ParserRegistry;
+
+pub mod batch;
+pub mod conversation;
+pub mod declarative;
 pub mod normalized;
 pub mod parser;
+pub mod providers;
 pub mod registry;
+#[cfg(feature = "schema")]
+pub mod schema;
+pub mod stream;
+#[cfg(any(test, feature = "testkit"))]
+pub mod testkit;
+pub mod tools;
+
+/// The process-wide registry used by [`parse`]
+///
+/// Created on first use with [`ParserRegistry::with_defaults`]. Since the
+/// registry can be modified through a shared reference, additional parsers
+/// registered here are also picked up by [`parse`].
+pub fn default_registry() -> &'static ParserRegistry {
+    static DEFAULT_REGISTRY: OnceLock<ParserRegistry> = OnceLock::new();
+    DEFAULT_REGISTRY.get_or_init(ParserRegistry::with_defaults)
+}
+
+/// Parse a raw LLM response with the built-in parsers
+///
+/// Convenience wrapper around [`default_registry`]`().parse(raw_response)`.
+///
+/// # Examples
+///
+/// ```
+/// let response = r#"{
+///     "id": "chatcmpl-123",
+///     "model": "gpt-4o",
+///     "choices": [{"message": {"role": "assistant", "content": "Hi!"}}]
+/// }"#;
+///
+/// # #[cfg(feature = "openai")]
+/// # {
+/// let frame = adaptogen::parse(response).unwrap();
+/// assert_eq!(frame.model, "gpt-4o");
+/// # }
+/// ```
+pub fn parse(raw_response: &str) -> Result<ContentFrame, ParseError> {
+    default_registry().parse(raw_response)
+}
+
+/// Parse every candidate completion of a raw LLM response with the built-in parsers
+///
+/// Convenience wrapper around [`default_registry`]`().parse_all(raw_response)`.
+///
+/// # Examples
+///
+/// ```
+/// let response = r#"{
+///     "id": "chatcmpl-123",
+///     "model": "gpt-4o",
+///     "choices": [
+///         {"index": 0, "message": {"role": "assistant", "content": "Hi!"}},
+///         {"index": 1, "message": {"role": "assistant", "content": "Hello!"}}
+///     ]
+/// }"#;
+///
+/// # #[cfg(feature = "openai")]
+/// # {
+/// let response = adaptogen::parse_all(response).unwrap();
+/// assert_eq!(response.len(), 2);
+/// # }
+/// ```
+pub fn parse_all(raw_response: &str) -> Result<ParsedResponse, ParseError> {
+    default_registry().parse_all(raw_response)
+}
@@ -1,5 +1,30 @@
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+use crate::parser::ParseError;
+use crate::tools::{ToolError, ToolSet};
+
+/// Version of the normalized format, bumped on changes that old consumers cannot read
+///
+/// Version 1 frames predate the `schema_version` field; version 2 added it,
+/// along with [`ContentBlock::Unknown`].
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Migrations between schema versions, where `MIGRATIONS[i]` upgrades a frame
+/// from version `i + 1` to version `i + 2`
+const MIGRATIONS: &[fn(&mut Map<String, Value>)] = &[migrate_v1_to_v2];
+
+/// The `type` tags of the known [`ContentBlock`] variants
+pub(crate) const BLOCK_TYPES: &[&str] = &[
+    "text",
+    "tool_use",
+    "tool_result",
+    "server_tool_use",
+    "server_tool_result",
+    "refusal",
+    "thinking",
+];
 
 /// Core content block representation for normalized LLM responses.
 /// 
@@ -10,19 +35,32 @@ use serde_json::Value;
 /// 
 /// ```
 /// use adaptogen::normalized::ContentBlock;
-/// 
-/// let text_block = ContentBlock::Text {
-///     text: "Hello, world!".to_string(),
-/// };
+///
+/// let text_block = ContentBlock::text("Hello, world!");
 /// ```
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Blocks with a `type` this version of the crate does not know, such as
+/// those written by a newer version, deserialize to
+/// [`Unknown`](ContentBlock::Unknown) instead of failing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(remote = "Self")]
 #[serde(tag = "type")]
 pub enum ContentBlock {
     /// Simple text content from the model
     #[serde(rename = "text")]
-    Text { text: String },
+    Text {
+        /// The text content
+        text: String,
+        /// Citations supporting parts of the text
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        citations: Vec<Citation>,
+        /// Log probabilities of the tokens making up the text, when requested
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        logprobs: Vec<TokenLogprob>,
+    },
 
-    /// A tool/function call made by the model
+    /// A tool/function call made by the model, to be executed by the client
     #[serde(rename = "tool_use")]
     ToolUse {
         /// Unique identifier for this tool use
@@ -44,6 +82,38 @@ pub enum ContentBlock {
         is_error: bool,
     },
 
+    /// A tool call executed by the provider itself, such as web search or code execution
+    ///
+    /// Unlike [`ToolUse`](ContentBlock::ToolUse), these must not be executed by
+    /// the client; the provider has already run them.
+    #[serde(rename = "server_tool_use")]
+    ServerToolUse {
+        /// Unique identifier for this tool use
+        id: String,
+        /// Name of the tool, such as `web_search` or `code_execution`
+        name: String,
+        /// Input parameters for the tool call, such as a search query or code
+        input: Value,
+    },
+
+    /// Results of a tool executed by the provider
+    #[serde(rename = "server_tool_result")]
+    ServerToolResult {
+        /// ID of the corresponding server tool use
+        tool_use_id: String,
+        /// The result as reported by the provider, such as search results or program output
+        content: Value,
+        /// Whether the tool execution resulted in an error
+        is_error: bool,
+    },
+
+    /// A refusal by the model to fulfil the request, with its explanation
+    #[serde(rename = "refusal")]
+    Refusal {
+        /// The refusal message from the model
+        refusal: String,
+    },
+
     /// Internal reasoning/thinking from the model
     #[serde(rename = "thinking")]
     Thinking {
@@ -53,42 +123,671 @@ pub enum ContentBlock {
         #[serde(skip_serializing_if = "Option::is_none")]
         signature: Option<String>,
     },
+
+    /// A block of a type not known to this version of the crate
+    ///
+    /// Serializes back to `raw` unchanged.
+    #[serde(skip)]
+    Unknown {
+        /// The block as it was deserialized, including its `type`
+        raw: Value,
+    },
+}
+
+impl Serialize for ContentBlock {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ContentBlock::Unknown { raw } => raw.serialize(serializer),
+            _ => ContentBlock::serialize(self, serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ContentBlock {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = Value::deserialize(deserializer)?;
+        match raw.get("type").and_then(|t| t.as_str()) {
+            Some(block_type) if !BLOCK_TYPES.contains(&block_type) => Ok(ContentBlock::Unknown { raw }),
+            _ => ContentBlock::deserialize(raw).map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+impl ContentBlock {
+    /// Create a text block without citations or logprobs
+    pub fn text(text: impl Into<String>) -> Self {
+        ContentBlock::Text {
+            text: text.into(),
+            citations: Vec::new(),
+            logprobs: Vec::new(),
+        }
+    }
+
+    /// Whether this block is a tool call or result handled by the provider
+    ///
+    /// Server tool blocks record what the provider did; only
+    /// [`ToolUse`](ContentBlock::ToolUse) blocks need to be executed by the client.
+    pub fn is_server_tool(&self) -> bool {
+        matches!(
+            self,
+            ContentBlock::ServerToolUse { .. } | ContentBlock::ServerToolResult { .. }
+        )
+    }
+
+    /// View this block as a client tool call, if it is one
+    pub fn as_tool_use(&self) -> Option<ToolUse<'_>> {
+        match self {
+            ContentBlock::ToolUse { id, name, input } => Some(ToolUse { id, name, input }),
+            _ => None,
+        }
+    }
+}
+
+/// Borrowed view of a [`ContentBlock::ToolUse`] block
+///
+/// # Examples
+///
+/// ```
+/// use adaptogen::normalized::{ContentBlock, ContentFrame};
+/// use serde::Deserialize;
+/// use serde_json::json;
+///
+/// #[derive(Deserialize)]
+/// struct Weather {
+///     city: String,
+/// }
+///
+/// let frame = ContentFrame::new("msg_123", "my-model", vec![ContentBlock::ToolUse {
+///     id: "call_1".to_string(),
+///     name: "get_weather".to_string(),
+///     input: json!({"city": "Paris"}),
+/// }]);
+///
+/// let call = frame.first_tool_use_named("get_weather").unwrap();
+/// let weather: Weather = call.input_as().unwrap();
+/// assert_eq!(weather.city, "Paris");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToolUse<'a> {
+    /// Unique identifier for this tool use
+    pub id: &'a str,
+    /// Name of the tool being used
+    pub name: &'a str,
+    /// Input parameters for the tool call
+    pub input: &'a Value,
+}
+
+impl ToolUse<'_> {
+    /// Deserialize the input parameters into `T`
+    pub fn input_as<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        T::deserialize(self.input)
+    }
+
+    /// Decode the call into one of the tools of a [`ToolSet`]
+    pub fn decode<S: ToolSet>(&self) -> Result<S, ToolError> {
+        S::decode(*self)
+    }
+}
+
+/// Provider-independent description of a tool the model may call
+///
+/// See [`tools`](crate::tools) for rendering definitions into each provider's
+/// request format.
+///
+/// # Examples
+///
+/// ```
+/// use adaptogen::normalized::ToolDefinition;
+/// use serde_json::json;
+///
+/// let tool = ToolDefinition::new(
+///     "get_weather",
+///     "Get the current weather in a city",
+///     json!({"type": "object", "properties": {"city": {"type": "string"}}, "required": ["city"]}),
+/// );
+/// assert_eq!(tool.name, "get_weather");
+/// ```
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ToolDefinition {
+    /// Name the model uses to call the tool
+    pub name: String,
+    /// Description of what the tool does, shown to the model
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    /// JSON Schema of the tool's input
+    pub input_schema: Value,
+}
+
+impl ToolDefinition {
+    /// Create a tool definition
+    pub fn new(name: impl Into<String>, description: impl Into<String>, input_schema: Value) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            input_schema,
+        }
+    }
+}
+
+/// Log probability of a generated token
+///
+/// Tokens do not always fall on character boundaries, so positions are given
+/// as byte offsets into the UTF-8 text of the block.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TokenLogprob {
+    /// The token as reported by the provider
+    pub token: String,
+    /// Natural log probability of the token
+    pub logprob: f64,
+    /// Byte offset in the block text where the token starts
+    ///
+    /// A token that begins before the block (for example one spanning the end
+    /// of a thinking section) is given offset 0.
+    pub offset: usize,
+    /// Raw UTF-8 bytes of the token, when the provider reports them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<Vec<u8>>,
+    /// The most likely alternatives at this position, most likely first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub top_logprobs: Vec<TopLogprob>,
+}
+
+impl TokenLogprob {
+    /// Probability of the token, between 0 and 1
+    pub fn probability(&self) -> f64 {
+        self.logprob.exp()
+    }
+}
+
+/// An alternative token considered at a position
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TopLogprob {
+    /// The alternative token
+    pub token: String,
+    /// Natural log probability of the alternative
+    pub logprob: f64,
+    /// Raw UTF-8 bytes of the token, when the provider reports them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<Vec<u8>>,
+}
+
+/// A citation supporting part of a text block
+///
+/// Providers differ in what they report, so every field is optional.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Citation {
+    /// The text the citation refers to, as reported by the provider
+    ///
+    /// For Anthropic this is the quoted passage of the source; for Cohere it is
+    /// the span of the response that the sources support.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cited_text: Option<String>,
+    /// Character offset in the block text where the supported span starts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<usize>,
+    /// Character offset in the block text where the supported span ends (exclusive)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<usize>,
+    /// The sources backing the citation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<CitationSource>,
+}
+
+/// A source referenced by a [`Citation`]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CitationSource {
+    /// Provider-assigned identifier of the source, such as a document id or index
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Title of the source
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// URL of the source, for web results
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
 }
 
 /// Content result block for tool results
 ///
 /// Represents a single block of content within a tool result
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ContentResultBlock {
     /// The content of the result block
     pub content: String,
 }
 
+/// Token usage reported by the provider for a single response
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Usage {
+    /// Number of tokens in the prompt
+    pub input_tokens: u64,
+    /// Number of tokens generated by the model, including reasoning tokens
+    pub output_tokens: u64,
+}
+
+impl Usage {
+    /// Total number of tokens billed for the response, saturating at `u64::MAX`
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens.saturating_add(self.output_tokens)
+    }
+}
+
+/// Safety and moderation outcome of a response
+///
+/// Collects what providers report about refusals and content filtering in one
+/// place: OpenAI refusals and Azure content filter results, Gemini safety
+/// ratings and blocked prompts, and Anthropic and Bedrock refusal stop reasons.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SafetyReport {
+    /// Whether the response was withheld, cut short or refused, either by the
+    /// model or by a provider content filter
+    pub blocked: bool,
+    /// Why the response was blocked, as reported by the provider
+    /// (for example `refusal`, `content_filter`, `SAFETY` or a Gemini `blockReason`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Per-category ratings of the prompt and response
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ratings: Vec<SafetyRating>,
+}
+
+/// A provider's assessment of one harm category
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SafetyRating {
+    /// Harm category as named by the provider, such as `hate` or `HARM_CATEGORY_HARASSMENT`
+    pub category: String,
+    /// Severity or probability level as reported by the provider, such as `safe`, `low` or `NEGLIGIBLE`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<String>,
+    /// Whether this category caused content to be filtered
+    #[serde(default)]
+    pub filtered: bool,
+    /// Whether the rating applies to the prompt rather than the response
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub prompt: bool,
+}
+
 /// A ContentFrame represents a complete message from an LLM
 ///
 /// This structure contains metadata about the message and a collection
 /// of ContentBlock instances representing the actual content.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// # Examples
+///
+/// ```
+/// use adaptogen::normalized::{ContentBlock, ContentFrame};
+///
+/// let frame = ContentFrame::new("msg_123", "my-model", vec![ContentBlock::text("Hello")]);
+/// assert!(frame.stop_reason.is_none());
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ContentFrame {
+    /// Version of the normalized format the frame was written in
+    ///
+    /// Frames written before the field existed are read as version 1.
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32,
     /// Unique identifier for this content frame
     pub id: String,
     /// The model that generated this content
     pub model: String,
     /// The normalized content blocks that make up the message
     pub blocks: Vec<ContentBlock>,
+    /// Why the model stopped generating, as reported by the provider
+    /// (for example `end_turn`, `stop` or `tool_calls`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_reason: Option<String>,
+    /// Token usage reported by the provider
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+    /// Safety and moderation information reported by the provider
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub safety: Option<SafetyReport>,
+}
+
+impl Default for ContentFrame {
+    fn default() -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            id: String::new(),
+            model: String::new(),
+            blocks: Vec::new(),
+            stop_reason: None,
+            usage: None,
+            safety: None,
+        }
+    }
+}
+
+fn legacy_schema_version() -> u32 {
+    1
+}
+
+impl ContentFrame {
+    /// Read a frame written by this or an earlier version of the crate
+    ///
+    /// Frame JSON from older schema versions is migrated to the current
+    /// [`SCHEMA_VERSION`] before it is deserialized. Frames from newer versions
+    /// are read as they are, with unrecognized blocks kept as
+    /// [`ContentBlock::Unknown`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use adaptogen::normalized::{ContentFrame, SCHEMA_VERSION};
+    ///
+    /// let stored = r#"{"id": "msg_1", "model": "claude-3-opus", "blocks": [{"type": "text", "text": "Hi"}]}"#;
+    /// let frame = ContentFrame::from_json(stored).unwrap();
+    /// assert_eq!(frame.schema_version, SCHEMA_VERSION);
+    /// ```
+    pub fn from_json(json: &str) -> Result<Self, ParseError> {
+        Self::from_value(serde_json::from_str(json)?)
+    }
+
+    /// Read a frame from a JSON value, migrating it to the current version as needed
+    ///
+    /// See [`from_json`](ContentFrame::from_json).
+    pub fn from_value(mut value: Value) -> Result<Self, ParseError> {
+        migrate(&mut value)?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Create a frame without stop reason or usage information
+    pub fn new(id: impl Into<String>, model: impl Into<String>, blocks: Vec<ContentBlock>) -> Self {
+        Self {
+            id: id.into(),
+            model: model.into(),
+            blocks,
+            ..Default::default()
+        }
+    }
+
+    /// The text of all text blocks, concatenated in order
+    ///
+    /// Providers split text around tool calls and citations, so the blocks are
+    /// joined without a separator.
+    pub fn text(&self) -> String {
+        self.blocks
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Text { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The model's visible reasoning, with separate thinking blocks joined by blank lines
+    ///
+    /// Returns `None` when the frame has no thinking text, including when all
+    /// thinking blocks are redacted.
+    pub fn thinking(&self) -> Option<String> {
+        let thinking: Vec<&str> = self
+            .blocks
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Thinking { thinking, .. } => thinking.as_deref(),
+                _ => None,
+            })
+            .collect();
+        (!thinking.is_empty()).then(|| thinking.join("\n\n"))
+    }
+
+    /// Iterate over the tool calls the client is expected to execute
+    pub fn tool_uses(&self) -> impl Iterator<Item = ToolUse<'_>> {
+        self.blocks.iter().filter_map(ContentBlock::as_tool_use)
+    }
+
+    /// Whether the frame contains tool calls for the client to execute
+    ///
+    /// Server tool calls, already run by the provider, are not counted.
+    pub fn has_tool_calls(&self) -> bool {
+        self.tool_uses().next().is_some()
+    }
+
+    /// The first tool call to the tool named `name`
+    pub fn first_tool_use_named(&self, name: &str) -> Option<ToolUse<'_>> {
+        self.tool_uses().find(|tool_use| tool_use.name == name)
+    }
+}
+
+/// Upgrade serialized frame JSON in place to the current [`SCHEMA_VERSION`]
+///
+/// Frames without a `schema_version` are treated as version 1. Frames already
+/// at the current version, or written by a newer version, are left unchanged.
+pub fn migrate(frame: &mut Value) -> Result<(), ParseError> {
+    let frame = frame
+        .as_object_mut()
+        .ok_or_else(|| ParseError::Other("Content frame must be a JSON object".to_string()))?;
+
+    let version = match frame.get("schema_version") {
+        None => legacy_schema_version(),
+        Some(version) => version
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .filter(|v| *v >= 1)
+            .ok_or_else(|| ParseError::Other(format!("Invalid schema version: {version}")))?,
+    };
+
+    for migration in MIGRATIONS.iter().skip(version as usize - 1) {
+        migration(frame);
+    }
+    Ok(())
+}
+
+/// Version 2 only adds the `schema_version` field and unknown blocks
+fn migrate_v1_to_v2(frame: &mut Map<String, Value>) {
+    frame.insert("schema_version".to_string(), Value::from(2));
+}
+
+/// One of several alternative completions returned for a single request
+///
+/// Providers return several candidates when asked for more than one sample,
+/// such as OpenAI's `n` parameter or Gemini's `candidateCount`.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Candidate {
+    /// Position of the candidate as reported by the provider
+    pub index: usize,
+    /// The candidate message, including its own stop reason
+    pub frame: ContentFrame,
+    /// Log probabilities for the candidate, in the provider's own format
+    ///
+    /// Parsers that understand the format also attach normalized per-token
+    /// logprobs to the candidate's text blocks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<Value>,
+}
+
+/// All candidate completions of a response
+///
+/// Every candidate frame shares the response id and model. Providers report
+/// token usage for the response as a whole, so each frame carries the same
+/// usage, covering all candidates.
+///
+/// # Examples
+///
+/// ```
+/// use adaptogen::normalized::{ContentBlock, ContentFrame, ParsedResponse};
+///
+/// let frame = ContentFrame::new("msg_123", "my-model", vec![ContentBlock::text("Hello")]);
+/// let response = ParsedResponse::from(frame);
+/// assert_eq!(response.len(), 1);
+/// assert_eq!(response.first().unwrap().id, "msg_123");
+/// ```
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ParsedResponse {
+    /// The candidates, in the order the provider returned them
+    pub candidates: Vec<Candidate>,
+}
+
+impl ParsedResponse {
+    /// The frame of the first candidate
+    pub fn first(&self) -> Option<&ContentFrame> {
+        self.candidates.first().map(|candidate| &candidate.frame)
+    }
+
+    /// Consume the response, keeping only the frame of the first candidate
+    pub fn into_first(self) -> Option<ContentFrame> {
+        self.candidates.into_iter().next().map(|candidate| candidate.frame)
+    }
+
+    /// Iterate over the frames of all candidates
+    pub fn frames(&self) -> impl Iterator<Item = &ContentFrame> {
+        self.candidates.iter().map(|candidate| &candidate.frame)
+    }
+
+    /// Number of candidates
+    pub fn len(&self) -> usize {
+        self.candidates.len()
+    }
+
+    /// Whether the response has no candidates
+    pub fn is_empty(&self) -> bool {
+        self.candidates.is_empty()
+    }
+}
+
+impl From<ContentFrame> for ParsedResponse {
+    /// Wrap a single frame as the only candidate
+    fn from(frame: ContentFrame) -> Self {
+        Self {
+            candidates: vec![Candidate {
+                index: 0,
+                frame,
+                logprobs: None,
+            }],
+        }
+    }
+}
+
+/// A provider-independent event of a streamed response
+///
+/// A stream starts with `MessageStart` and ends with `MessageStop`, unless it
+/// fails with `Error`. In between, each content block is opened with
+/// `BlockStart`, receives deltas, and is closed with `BlockStop` before the
+/// next block starts. Block indexes count the blocks of the stream in the
+/// order they start.
+///
+/// Only text, thinking, refusal and tool use blocks are streamed as events;
+/// other content, such as server tool results and citations, is only part of
+/// the final frame.
+///
+/// Events serialize with a `type` tag, such as
+/// `{"type": "text_delta", "index": 0, "text": "Hel"}`, so they can be
+/// forwarded to clients in the same format whichever provider streams them.
+///
+/// # Examples
+///
+/// ```
+/// use adaptogen::normalized::{BlockKind, StreamEvent};
+///
+/// let event = StreamEvent::BlockStart { index: 1, kind: BlockKind::ToolUse { id: "call_1".to_string(), name: "search".to_string() } };
+/// let json = serde_json::to_string(&event).unwrap();
+/// assert_eq!(json, r#"{"type":"block_start","index":1,"kind":{"type":"tool_use","id":"call_1","name":"search"}}"#);
+/// assert_eq!(serde_json::from_str::<StreamEvent>(&json).unwrap(), event);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamEvent {
+    /// The response started
+    MessageStart {
+        /// Provider-assigned response id, empty if the stream has none
+        id: String,
+        /// The model generating the response, empty if the stream does not say
+        model: String,
+    },
+    /// A content block started
+    BlockStart {
+        /// Position of the block in the stream
+        index: usize,
+        /// What the block holds
+        kind: BlockKind,
+    },
+    /// Text was added to a text or refusal block
+    TextDelta {
+        /// The block the text belongs to
+        index: usize,
+        /// The added text
+        text: String,
+    },
+    /// Reasoning was added to a thinking block
+    ThinkingDelta {
+        /// The block the reasoning belongs to
+        index: usize,
+        /// The added reasoning
+        thinking: String,
+    },
+    /// A piece of a tool call's JSON input arrived
+    ToolInputDelta {
+        /// The tool use block the input belongs to
+        index: usize,
+        /// The next piece of the input's JSON text; pieces are not valid JSON on their own
+        partial_json: String,
+    },
+    /// A content block is complete
+    BlockStop {
+        /// The completed block
+        index: usize,
+    },
+    /// The token counts so far, replacing any earlier update
+    UsageUpdate {
+        /// Token counts of the response so far
+        usage: Usage,
+    },
+    /// The response is complete
+    MessageStop {
+        /// The provider's reason for stopping, if the stream gave one
+        stop_reason: Option<String>,
+    },
+    /// The provider reported an error in the stream
+    Error {
+        /// Description of the error
+        message: String,
+    },
+}
+
+/// Kind of a streamed content block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BlockKind {
+    /// Text from the model
+    Text,
+    /// Visible reasoning
+    Thinking,
+    /// A refusal message
+    Refusal,
+    /// A call of a client-side tool
+    ToolUse {
+        /// The call's id
+        id: String,
+        /// Name of the called tool
+        name: String,
+    },
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use serde_json::json;
+    use std::collections::HashMap;
 
     #[test]
     fn test_content_block_text_serialization() {
-        let text_block = ContentBlock::Text {
-            text: "Hello, world!".to_string(),
-        };
-        
+        let text_block = ContentBlock::text("Hello, world!");
+
         let serialized = serde_json::to_string(&text_block).unwrap();
         let expected = r#"{"type":"text","text":"Hello, world!"}"#;
         
@@ -96,7 +795,7 @@ mod tests {
         
         let deserialized: ContentBlock = serde_json::from_str(expected).unwrap();
         match deserialized {
-            ContentBlock::Text { text } => assert_eq!(text, "Hello, world!"),
+            ContentBlock::Text { text, .. } => assert_eq!(text, "Hello, world!"),
             _ => panic!("Deserialized to wrong variant"),
         }
     }
@@ -128,12 +827,13 @@ mod tests {
             id: "msg_123".to_string(),
             model: "test-model".to_string(),
             blocks: vec![
-                ContentBlock::Text { text: "Hello".to_string() },
-                ContentBlock::Thinking { 
+                ContentBlock::text("Hello"),
+                ContentBlock::Thinking {
                     thinking: Some("Some thinking".to_string()),
                     signature: None,
                 },
             ],
+            ..Default::default()
         };
         
         assert_eq!(frame.id, "msg_123");
@@ -147,4 +847,197 @@ mod tests {
         assert_eq!(deserialized.model, frame.model);
         assert_eq!(deserialized.blocks.len(), frame.blocks.len());
     }
+
+    #[test]
+    fn test_content_frame_optional_metadata() {
+        // Frames serialized before stop_reason and usage existed still deserialize
+        let frame: ContentFrame =
+            serde_json::from_str(r#"{"id":"msg_1","model":"m","blocks":[]}"#).unwrap();
+        assert!(frame.stop_reason.is_none());
+        assert!(frame.usage.is_none());
+
+        let frame = ContentFrame {
+            stop_reason: Some("end_turn".to_string()),
+            usage: Some(Usage {
+                input_tokens: 10,
+                output_tokens: 5,
+            }),
+            ..ContentFrame::new("msg_1", "m", vec![])
+        };
+        let value = serde_json::to_value(&frame).unwrap();
+        assert_eq!(value["stop_reason"], "end_turn");
+        assert_eq!(value["usage"]["output_tokens"], 5);
+        assert_eq!(frame.usage.unwrap().total_tokens(), 15);
+    }
+
+    #[test]
+    fn test_usage_total_tokens() {
+        let usage = Usage {
+            input_tokens: 12,
+            output_tokens: 30,
+        };
+        assert_eq!(usage.total_tokens(), 42);
+
+        let usage = Usage {
+            input_tokens: 12,
+            output_tokens: u64::MAX,
+        };
+        assert_eq!(usage.total_tokens(), u64::MAX);
+    }
+
+    #[test]
+    fn test_parsed_response() {
+        let response = ParsedResponse {
+            candidates: vec![
+                Candidate {
+                    index: 0,
+                    frame: ContentFrame::new("msg_1", "m", vec![ContentBlock::text("A")]),
+                    logprobs: None,
+                },
+                Candidate {
+                    index: 1,
+                    frame: ContentFrame::new("msg_1", "m", vec![ContentBlock::text("B")]),
+                    logprobs: Some(json!({"content": []})),
+                },
+            ],
+        };
+        assert_eq!(response.len(), 2);
+        assert_eq!(response.frames().count(), 2);
+
+        let value = serde_json::to_value(&response).unwrap();
+        assert!(value["candidates"][0].get("logprobs").is_none());
+        assert_eq!(value["candidates"][1]["index"], 1);
+
+        let first = response.into_first().unwrap();
+        match &first.blocks[0] {
+            ContentBlock::Text { text, .. } => assert_eq!(text, "A"),
+            _ => panic!("Expected Text block"),
+        }
+    }
+
+    #[test]
+    fn test_content_frame_accessors() {
+        let frame = ContentFrame::new(
+            "msg_1",
+            "m",
+            vec![
+                ContentBlock::Thinking { thinking: Some("Check the weather.".to_string()), signature: None },
+                ContentBlock::text("Let me check. "),
+                ContentBlock::ServerToolUse { id: "srv_1".to_string(), name: "web_search".to_string(), input: json!({}) },
+                ContentBlock::ToolUse { id: "call_1".to_string(), name: "get_weather".to_string(), input: json!({"city": "Paris"}) },
+                ContentBlock::ToolUse { id: "call_2".to_string(), name: "get_time".to_string(), input: json!({"zone": 1}) },
+                ContentBlock::Thinking { thinking: None, signature: Some("redacted".to_string()) },
+                ContentBlock::text("One moment."),
+            ],
+        );
+
+        assert_eq!(frame.text(), "Let me check. One moment.");
+        assert_eq!(frame.thinking().as_deref(), Some("Check the weather."));
+        assert!(frame.has_tool_calls());
+        assert_eq!(frame.tool_uses().map(|t| t.id).collect::<Vec<_>>(), ["call_1", "call_2"]);
+
+        let call = frame.first_tool_use_named("get_time").unwrap();
+        assert_eq!(call.id, "call_2");
+        assert_eq!(call.input_as::<HashMap<String, u32>>().unwrap()["zone"], 1);
+        assert!(call.input_as::<HashMap<String, String>>().is_err());
+        assert!(frame.first_tool_use_named("web_search").is_none());
+
+        let empty = ContentFrame::default();
+        assert_eq!(empty.text(), "");
+        assert!(empty.thinking().is_none());
+        assert!(!empty.has_tool_calls());
+    }
+
+    #[test]
+    fn test_unknown_blocks() {
+        let raw = json!({"type": "image", "source": {"url": "https://example.com/cat.png"}});
+        let block: ContentBlock = serde_json::from_value(raw.clone()).unwrap();
+        match &block {
+            ContentBlock::Unknown { raw: kept } => assert_eq!(kept, &raw),
+            _ => panic!("Expected Unknown block"),
+        }
+        assert_eq!(serde_json::to_value(&block).unwrap(), raw);
+
+        // Known types still report their errors
+        let error = serde_json::from_value::<ContentBlock>(json!({"type": "tool_use", "id": "1"})).unwrap_err();
+        assert!(error.to_string().contains("missing field `name`"), "{error}");
+        assert!(serde_json::from_value::<ContentBlock>(json!({"text": "no type"})).is_err());
+
+        // Every known variant round-trips as itself
+        let blocks = [
+            ContentBlock::text("a"),
+            ContentBlock::ToolUse { id: "1".to_string(), name: "f".to_string(), input: json!({}) },
+            ContentBlock::ToolResult { tool_use_id: "1".to_string(), content: Vec::new(), is_error: false },
+            ContentBlock::ServerToolUse { id: "2".to_string(), name: "web_search".to_string(), input: json!({}) },
+            ContentBlock::ServerToolResult { tool_use_id: "2".to_string(), content: json!([]), is_error: false },
+            ContentBlock::Refusal { refusal: "No".to_string() },
+            ContentBlock::Thinking { thinking: None, signature: None },
+        ];
+        assert_eq!(blocks.len(), BLOCK_TYPES.len());
+        for block in blocks {
+            let value = serde_json::to_value(&block).unwrap();
+            assert!(BLOCK_TYPES.contains(&value["type"].as_str().unwrap()));
+            let block: ContentBlock = serde_json::from_value(value).unwrap();
+            assert!(!matches!(block, ContentBlock::Unknown { .. }));
+        }
+    }
+
+    #[test]
+    fn test_schema_versions() {
+        // Frames from before versioning are read as version 1
+        let legacy = r#"{"id": "msg_1", "model": "m", "blocks": [{"type": "text", "text": "Hi"}]}"#;
+        let frame: ContentFrame = serde_json::from_str(legacy).unwrap();
+        assert_eq!(frame.schema_version, 1);
+
+        let frame = ContentFrame::from_json(legacy).unwrap();
+        assert_eq!(frame.schema_version, SCHEMA_VERSION);
+        assert_eq!(frame.text(), "Hi");
+
+        let mut value = serde_json::to_value(ContentFrame::default()).unwrap();
+        assert_eq!(value["schema_version"], SCHEMA_VERSION);
+        migrate(&mut value).unwrap();
+        assert_eq!(value["schema_version"], SCHEMA_VERSION);
+
+        // Newer frames are read as they are
+        let newer = json!({"schema_version": 99, "id": "1", "model": "m", "blocks": [{"type": "video"}], "mood": "happy"});
+        let frame = ContentFrame::from_value(newer).unwrap();
+        assert_eq!(frame.schema_version, 99);
+        assert!(matches!(frame.blocks[0], ContentBlock::Unknown { .. }));
+
+        assert!(ContentFrame::from_value(json!({"schema_version": 0, "id": "1", "model": "m", "blocks": []})).is_err());
+        assert!(ContentFrame::from_value(json!([])).is_err());
+    }
+
+    #[test]
+    fn test_stream_event_serialization() {
+        let events = vec![
+            StreamEvent::MessageStart { id: "msg_1".to_string(), model: "m".to_string() },
+            StreamEvent::BlockStart { index: 0, kind: BlockKind::Thinking },
+            StreamEvent::ThinkingDelta { index: 0, thinking: "Hmm".to_string() },
+            StreamEvent::BlockStop { index: 0 },
+            StreamEvent::BlockStart { index: 1, kind: BlockKind::ToolUse { id: "call_1".to_string(), name: "f".to_string() } },
+            StreamEvent::ToolInputDelta { index: 1, partial_json: "{\"a\":".to_string() },
+            StreamEvent::BlockStop { index: 1 },
+            StreamEvent::UsageUpdate { usage: Usage { input_tokens: 3, output_tokens: 5 } },
+            StreamEvent::MessageStop { stop_reason: None },
+            StreamEvent::Error { message: "Overloaded".to_string() },
+        ];
+        let value = serde_json::to_value(&events).unwrap();
+        assert_eq!(
+            value,
+            json!([
+                {"type": "message_start", "id": "msg_1", "model": "m"},
+                {"type": "block_start", "index": 0, "kind": {"type": "thinking"}},
+                {"type": "thinking_delta", "index": 0, "thinking": "Hmm"},
+                {"type": "block_stop", "index": 0},
+                {"type": "block_start", "index": 1, "kind": {"type": "tool_use", "id": "call_1", "name": "f"}},
+                {"type": "tool_input_delta", "index": 1, "partial_json": "{\"a\":"},
+                {"type": "block_stop", "index": 1},
+                {"type": "usage_update", "usage": {"input_tokens": 3, "output_tokens": 5}},
+                {"type": "message_stop", "stop_reason": null},
+                {"type": "error", "message": "Overloaded"},
+            ])
+        );
+        assert_eq!(serde_json::from_value::<Vec<StreamEvent>>(value).unwrap(), events);
+    }
 }
@@ -1,4 +1,6 @@
-use crate::normalized::ContentFrame;
+use serde_json::Value;
+
+use crate::normalized::{ContentFrame, ParsedResponse};
 
 /// Trait for parsing LLM model responses into ContentFrames
 ///
@@ -25,7 +27,8 @@ use crate::normalized::ContentFrame;
 ///         # Ok(ContentFrame { 
 ///         #    id: "example".into(), 
 ///         #    model: "my-model".into(), 
-///         #    blocks: vec![] 
+///         #    blocks: vec![],
+///         #    ..Default::default()
 ///         # })
 ///     }
 /// }
@@ -50,6 +53,62 @@ pub trait ModelResponseParser: Send + Sync {
     fn can_handle(&self, model: &str) -> bool {
         self.supported_models().iter().any(|m| m == model)
     }
+
+    /// Determines if this parser recognizes the shape of a response
+    ///
+    /// The registry uses this to route responses that carry no model identifier,
+    /// and to choose between several parsers that handle the same model. The
+    /// default implementation recognizes nothing.
+    fn can_handle_response(&self, _response: &Value) -> bool {
+        false
+    }
+
+    /// Whether the registry may route any model's responses to this parser by shape
+    ///
+    /// Parsers for formats whose model names overlap with other providers can
+    /// return `true` to be selected for any model, as long as
+    /// [`can_handle_response`](Self::can_handle_response) recognizes the
+    /// response. The default implementation returns `false`.
+    fn routes_by_shape(&self) -> bool {
+        false
+    }
+
+    /// Parse raw response data for a model known from outside the response
+    ///
+    /// Called by [`ParserRegistry::parse_with_model`](crate::registry::ParserRegistry::parse_with_model)
+    /// when the caller knows which model produced the response, for example from the
+    /// request. Parsers for formats that do not include the model in the body should
+    /// override this to fill in `ContentFrame::model`. The default implementation
+    /// ignores `model` and calls [`parse`](Self::parse).
+    fn parse_with_model(&self, raw_response: &str, model: &str) -> Result<ContentFrame, ParseError> {
+        let _ = model;
+        self.parse(raw_response)
+    }
+
+    /// Parse every candidate completion in a response
+    ///
+    /// Use this when several samples were requested (for example with OpenAI's
+    /// `n` parameter); [`parse`](Self::parse) only returns the first candidate.
+    /// Parsers for formats that can hold several candidates should override
+    /// this. The default implementation wraps the result of `parse` as a single
+    /// candidate.
+    fn parse_all(&self, raw_response: &str) -> Result<ParsedResponse, ParseError> {
+        self.parse(raw_response).map(ParsedResponse::from)
+    }
+
+    /// Parse every candidate completion for a model known from outside the response
+    ///
+    /// The multi-candidate counterpart of [`parse_with_model`](Self::parse_with_model).
+    /// The default implementation wraps the result of `parse_with_model` as a
+    /// single candidate, so parsers that override `parse_all` should override this
+    /// as well.
+    fn parse_all_with_model(
+        &self,
+        raw_response: &str,
+        model: &str,
+    ) -> Result<ParsedResponse, ParseError> {
+        self.parse_with_model(raw_response, model).map(ParsedResponse::from)
+    }
 }
 
 /// Error type for parsing failures
@@ -73,6 +132,82 @@ pub enum ParseError {
     /// General parsing error with a custom message
     #[error("Parsing error: {0}")]
     Other(String),
+
+    /// The response is an error reported by the provider rather than a message
+    ///
+    /// Produced by the registry for provider error bodies such as
+    /// `{"type": "error", "error": {...}}` or `{"error": {"message": ...}}`;
+    /// see [`detect_provider_error`](crate::providers::detect_provider_error).
+    #[error("Provider error from {provider} ({kind}): {message}")]
+    ProviderError {
+        /// The provider whose error format was recognized, such as `anthropic` or `openai`
+        provider: String,
+        /// Normalized category of the error
+        kind: ProviderErrorKind,
+        /// Human-readable message from the provider
+        message: String,
+        /// Provider-specific error code or type, if any
+        code: Option<String>,
+        /// Whether retrying the same request later may succeed
+        retryable: bool,
+    },
+}
+
+impl ParseError {
+    /// Whether retrying the request may succeed
+    ///
+    /// Only provider errors such as rate limits and overloads are retryable;
+    /// every other parse error is deterministic.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, ParseError::ProviderError { retryable: true, .. })
+    }
+}
+
+/// Normalized category of a provider error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProviderErrorKind {
+    /// Too many requests or tokens for the account's limits
+    RateLimit,
+    /// The provider is temporarily overloaded or unavailable
+    Overloaded,
+    /// The request was malformed or rejected by validation
+    InvalidRequest,
+    /// The API key is missing, invalid or lacks permission
+    Authentication,
+    /// The prompt or requested output exceeds the model's context window
+    ContextLength,
+    /// The model or resource does not exist
+    NotFound,
+    /// An internal error on the provider's side
+    Server,
+    /// An error that fits none of the other categories
+    Other,
+}
+
+impl ProviderErrorKind {
+    /// Whether errors of this kind are usually transient
+    pub fn is_retryable(self) -> bool {
+        matches!(
+            self,
+            ProviderErrorKind::RateLimit | ProviderErrorKind::Overloaded | ProviderErrorKind::Server
+        )
+    }
+}
+
+impl std::fmt::Display for ProviderErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ProviderErrorKind::RateLimit => "rate limit",
+            ProviderErrorKind::Overloaded => "overloaded",
+            ProviderErrorKind::InvalidRequest => "invalid request",
+            ProviderErrorKind::Authentication => "authentication",
+            ProviderErrorKind::ContextLength => "context length",
+            ProviderErrorKind::NotFound => "not found",
+            ProviderErrorKind::Server => "server error",
+            ProviderErrorKind::Other => "other",
+        };
+        f.write_str(name)
+    }
 }
 
 #[cfg(test)]
@@ -99,9 +234,8 @@ mod tests {
             Ok(ContentFrame {
                 id: "mock_id".to_string(),
                 model: "mock_model".to_string(),
-                blocks: vec![ContentBlock::Text {
-                    text: "Mocked response".to_string(),
-                }],
+                blocks: vec![ContentBlock::text("Mocked response")],
+                ..Default::default()
             })
         }
     }
@@ -0,0 +1,308 @@
+//! Parser for the Anthropic Messages API
+
+use serde_json::{json, Value};
+
+use super::{blocked_by_stop_reason, matches_prefix, optional_str, required_str, usage_from};
+use crate::normalized::{Citation, CitationSource, ContentBlock, ContentFrame, ContentResultBlock};
+use crate::parser::{ModelResponseParser, ParseError};
+
+/// Parser for Anthropic Messages API responses
+///
+/// Handles `text`, `tool_use`, `tool_result`, `thinking` and `redacted_thinking`
+/// content blocks. Redacted thinking is normalized to a `Thinking` block without
+/// text whose signature holds the encrypted payload. Unknown block types are skipped.
+///
+/// # Examples
+///
+/// ```
+/// use adaptogen::parser::ModelResponseParser;
+/// use adaptogen::providers::AnthropicParser;
+///
+/// let response = r#"{
+///     "id": "msg_123",
+///     "model": "claude-3-5-sonnet-20241022",
+///     "content": [{"type": "text", "text": "Hello!"}]
+/// }"#;
+///
+/// let frame = AnthropicParser.parse(response).unwrap();
+/// assert_eq!(frame.blocks.len(), 1);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AnthropicParser;
+
+impl ModelResponseParser for AnthropicParser {
+    /// Model name prefixes handled by this parser
+    fn supported_models(&self) -> Vec<String> {
+        vec!["claude".to_string()]
+    }
+
+    fn parse(&self, raw_response: &str) -> Result<ContentFrame, ParseError> {
+        let json: Value = serde_json::from_str(raw_response)?;
+
+        let id = required_str(&json, "id")?;
+        let model = required_str(&json, "model")?;
+
+        let blocks = match json.get("content").and_then(|c| c.as_array()) {
+            Some(content) => content.iter().filter_map(parse_block).collect(),
+            None => Vec::new(),
+        };
+
+        Ok(ContentFrame {
+            id,
+            model,
+            blocks,
+            stop_reason: optional_str(&json, "stop_reason"),
+            usage: usage_from(json.get("usage"), "input_tokens", "output_tokens"),
+            safety: blocked_by_stop_reason(&json, "stop_reason", &["refusal"]),
+            ..Default::default()
+        })
+    }
+
+    fn can_handle(&self, model: &str) -> bool {
+        matches_prefix(&self.supported_models(), model)
+    }
+}
+
+/// Normalize a single Anthropic content block, skipping unknown or malformed ones
+fn parse_block(block: &Value) -> Option<ContentBlock> {
+    let str_field = |name: &str| block.get(name).and_then(|v| v.as_str());
+
+    match str_field("type")? {
+        "text" => Some(ContentBlock::Text {
+            text: str_field("text")?.to_string(),
+            citations: block
+                .get("citations")
+                .and_then(|c| c.as_array())
+                .map(|citations| citations.iter().map(parse_citation).collect())
+                .unwrap_or_default(),
+            logprobs: Vec::new(),
+        }),
+        "tool_use" => Some(ContentBlock::ToolUse {
+            id: str_field("id")?.to_string(),
+            name: str_field("name")?.to_string(),
+            input: block.get("input")?.clone(),
+        }),
+        "tool_result" => Some(ContentBlock::ToolResult {
+            tool_use_id: str_field("tool_use_id")?.to_string(),
+            content: parse_tool_result_content(block.get("content")),
+            is_error: block
+                .get("is_error")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+        }),
+        "thinking" => Some(ContentBlock::Thinking {
+            thinking: str_field("thinking").map(|t| t.to_string()),
+            signature: str_field("signature").map(|s| s.to_string()),
+        }),
+        "redacted_thinking" => Some(ContentBlock::Thinking {
+            thinking: None,
+            signature: str_field("data").map(|d| d.to_string()),
+        }),
+        // Tools run by Anthropic (web search, code execution) or by a remote MCP server
+        "server_tool_use" | "mcp_tool_use" => Some(ContentBlock::ServerToolUse {
+            id: str_field("id")?.to_string(),
+            name: str_field("name")?.to_string(),
+            input: block.get("input").cloned().unwrap_or_else(|| json!({})),
+        }),
+        // `web_search_tool_result`, `code_execution_tool_result`, `mcp_tool_result`, ...
+        server_result if server_result.ends_with("_tool_result") => {
+            let content = block.get("content").cloned().unwrap_or(Value::Null);
+            // Server tool errors are reported as content of type `<tool>_error`
+            let is_error = block.get("is_error").and_then(|v| v.as_bool()) == Some(true)
+                || content
+                    .get("type")
+                    .and_then(|t| t.as_str())
+                    .is_some_and(|t| t.ends_with("_error"));
+            Some(ContentBlock::ServerToolResult {
+                tool_use_id: str_field("tool_use_id")?.to_string(),
+                content,
+                is_error,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Anthropic citations locate a quoted passage in a document or search result
+///
+/// Their character indices refer to the source document rather than to the
+/// response text, so only the quote and the source are kept.
+fn parse_citation(citation: &Value) -> Citation {
+    let str_field = |name: &str| {
+        citation
+            .get(name)
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+    };
+
+    let source = CitationSource {
+        id: citation
+            .get("document_index")
+            .and_then(|i| i.as_u64())
+            .map(|i| i.to_string())
+            .or_else(|| str_field("source")),
+        title: str_field("document_title").or_else(|| str_field("title")),
+        url: str_field("url"),
+    };
+
+    Citation {
+        cited_text: str_field("cited_text"),
+        sources: vec![source],
+        ..Default::default()
+    }
+}
+
+/// Tool result content is either a plain string or a list of text blocks
+fn parse_tool_result_content(content: Option<&Value>) -> Vec<ContentResultBlock> {
+    match content {
+        Some(Value::String(text)) => vec![ContentResultBlock {
+            content: text.clone(),
+        }],
+        Some(Value::Array(blocks)) => blocks
+            .iter()
+            .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+            .map(|text| ContentResultBlock {
+                content: text.to_string(),
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_can_handle_by_prefix() {
+        assert!(AnthropicParser.can_handle("claude-3-5-sonnet-20241022"));
+        assert!(AnthropicParser.can_handle("claude"));
+        assert!(!AnthropicParser.can_handle("gpt-4o"));
+    }
+
+    #[test]
+    fn test_parse_all_block_types() {
+        let response = r#"{
+            "id": "msg_01",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-sonnet-4-20250514",
+            "content": [
+                {"type": "thinking", "thinking": "Let me think", "signature": "sig"},
+                {"type": "redacted_thinking", "data": "encrypted"},
+                {"type": "text", "text": "Checking the weather.", "citations": [
+                    {"type": "web_search_result_location", "cited_text": "Sunny all week", "url": "https://example.com", "title": "Forecast"}
+                ]},
+                {"type": "tool_use", "id": "toolu_01", "name": "get_weather", "input": {"city": "Paris"}},
+                {"type": "tool_result", "tool_use_id": "toolu_01", "content": "Sunny"},
+                {"type": "something_new"}
+            ],
+            "stop_reason": "tool_use",
+            "usage": {"input_tokens": 120, "output_tokens": 48}
+        }"#;
+
+        let frame = AnthropicParser.parse(response).unwrap();
+        assert_eq!(frame.id, "msg_01");
+        assert_eq!(frame.stop_reason.as_deref(), Some("tool_use"));
+        assert_eq!(frame.usage.unwrap().input_tokens, 120);
+        assert_eq!(frame.model, "claude-sonnet-4-20250514");
+        assert_eq!(frame.blocks.len(), 5);
+
+        match &frame.blocks[0] {
+            ContentBlock::Thinking { thinking, signature } => {
+                assert_eq!(thinking.as_deref(), Some("Let me think"));
+                assert_eq!(signature.as_deref(), Some("sig"));
+            }
+            _ => panic!("Expected Thinking block"),
+        }
+        match &frame.blocks[1] {
+            ContentBlock::Thinking { thinking, signature } => {
+                assert!(thinking.is_none());
+                assert_eq!(signature.as_deref(), Some("encrypted"));
+            }
+            _ => panic!("Expected Thinking block"),
+        }
+        match &frame.blocks[2] {
+            ContentBlock::Text { citations, .. } => {
+                assert_eq!(citations[0].cited_text.as_deref(), Some("Sunny all week"));
+                assert_eq!(
+                    citations[0].sources[0].url.as_deref(),
+                    Some("https://example.com")
+                );
+            }
+            _ => panic!("Expected Text block"),
+        }
+        match &frame.blocks[3] {
+            ContentBlock::ToolUse { id, name, input } => {
+                assert_eq!(id, "toolu_01");
+                assert_eq!(name, "get_weather");
+                assert_eq!(input["city"], "Paris");
+            }
+            _ => panic!("Expected ToolUse block"),
+        }
+        match &frame.blocks[4] {
+            ContentBlock::ToolResult {
+                tool_use_id,
+                content,
+                is_error,
+            } => {
+                assert_eq!(tool_use_id, "toolu_01");
+                assert_eq!(content[0].content, "Sunny");
+                assert!(!is_error);
+            }
+            _ => panic!("Expected ToolResult block"),
+        }
+    }
+
+    #[test]
+    fn test_parse_missing_id() {
+        let response = r#"{"model": "claude-3-haiku", "content": []}"#;
+        match AnthropicParser.parse(response).unwrap_err() {
+            ParseError::MissingField(field) => assert_eq!(field, "id"),
+            _ => panic!("Expected MissingField error"),
+        }
+    }
+
+    #[test]
+    fn test_refusal_stop_reason() {
+        let response = r#"{"id": "msg_1", "model": "claude-sonnet-4-5", "content": [], "stop_reason": "refusal"}"#;
+        let safety = AnthropicParser.parse(response).unwrap().safety.unwrap();
+        assert!(safety.blocked);
+        assert_eq!(safety.reason.as_deref(), Some("refusal"));
+
+        let response = r#"{"id": "msg_1", "model": "claude-sonnet-4-5", "content": [], "stop_reason": "end_turn"}"#;
+        assert!(AnthropicParser.parse(response).unwrap().safety.is_none());
+    }
+
+    #[test]
+    fn test_parse_server_tools() {
+        let response = r#"{
+            "id": "msg_1",
+            "model": "claude-sonnet-4-5",
+            "content": [
+                {"type": "server_tool_use", "id": "srvtoolu_01", "name": "web_search", "input": {"query": "rust 2024 edition"}},
+                {"type": "web_search_tool_result", "tool_use_id": "srvtoolu_01", "content": [{"type": "web_search_result", "url": "https://example.com", "title": "Example"}]},
+                {"type": "server_tool_use", "id": "srvtoolu_02", "name": "web_search", "input": {"query": "again"}},
+                {"type": "web_search_tool_result", "tool_use_id": "srvtoolu_02", "content": {"type": "web_search_tool_result_error", "error_code": "max_uses_exceeded"}}
+            ]
+        }"#;
+
+        let frame = AnthropicParser.parse(response).unwrap();
+        assert_eq!(frame.blocks.len(), 4);
+        assert!(frame.blocks.iter().all(ContentBlock::is_server_tool));
+        match &frame.blocks[0] {
+            ContentBlock::ServerToolUse { name, input, .. } => {
+                assert_eq!(name, "web_search");
+                assert_eq!(input["query"], "rust 2024 edition");
+            }
+            _ => panic!("Expected ServerToolUse block"),
+        }
+        match (&frame.blocks[1], &frame.blocks[3]) {
+            (
+                ContentBlock::ServerToolResult { content, is_error: false, .. },
+                ContentBlock::ServerToolResult { is_error: true, .. },
+            ) => assert_eq!(content[0]["title"], "Example"),
+            _ => panic!("Expected ServerToolResult blocks"),
+        }
+    }
+}
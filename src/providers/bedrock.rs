@@ -0,0 +1,515 @@
+//! Parsers for AWS Bedrock's Converse and InvokeModel APIs
+//!
+//! Bedrock responses usually carry no model identifier, so these parsers are
+//! selected by response shape (see
+//! [`ModelResponseParser::can_handle_response`]) or by the Bedrock model id
+//! passed to [`ParserRegistry::parse_with_model`](crate::registry::ParserRegistry::parse_with_model).
+//! Frames parsed without a model id have an empty `model`.
+
+use serde_json::{json, Value};
+
+use super::{
+    blocked_by_stop_reason, decode_arguments, matches_prefix, optional_str, split_thinking,
+    usage_from, AnthropicParser,
+};
+use crate::normalized::{ContentBlock, ContentFrame, ContentResultBlock, Usage};
+use crate::parser::{ModelResponseParser, ParseError};
+
+/// Bedrock model id prefixes: provider prefixes, cross-region inference
+/// profiles and ARNs
+const BEDROCK_MODEL_PREFIXES: &[&str] = &[
+    "anthropic.",
+    "meta.",
+    "amazon.",
+    "mistral.",
+    "cohere.",
+    "ai21.",
+    "deepseek.",
+    "us.",
+    "eu.",
+    "apac.",
+    "global.",
+    "arn:aws:bedrock:",
+];
+
+fn bedrock_model_prefixes() -> Vec<String> {
+    BEDROCK_MODEL_PREFIXES.iter().map(|p| p.to_string()).collect()
+}
+
+/// Parser for Bedrock Converse API responses
+///
+/// Handles `text`, `toolUse`, `toolResult` and `reasoningContent` blocks of
+/// `output.message.content`. The Converse body has no id; the frame id is taken
+/// from `ResponseMetadata.RequestId` when the response was captured with its
+/// metadata (as AWS SDKs return it) and is empty otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use adaptogen::registry::ParserRegistry;
+///
+/// let response = r#"{
+///     "output": {"message": {"role": "assistant", "content": [{"text": "Hello!"}]}},
+///     "stopReason": "end_turn",
+///     "usage": {"inputTokens": 12, "outputTokens": 3, "totalTokens": 15}
+/// }"#;
+///
+/// let registry = ParserRegistry::with_defaults();
+/// let frame = registry
+///     .parse_with_model(response, "anthropic.claude-3-5-sonnet-20240620-v1:0")
+///     .unwrap();
+/// assert_eq!(frame.model, "anthropic.claude-3-5-sonnet-20240620-v1:0");
+/// assert_eq!(frame.stop_reason.as_deref(), Some("end_turn"));
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BedrockConverseParser;
+
+impl ModelResponseParser for BedrockConverseParser {
+    /// Bedrock model id prefixes handled by this parser
+    fn supported_models(&self) -> Vec<String> {
+        bedrock_model_prefixes()
+    }
+
+    fn parse(&self, raw_response: &str) -> Result<ContentFrame, ParseError> {
+        self.parse_with_model(raw_response, "")
+    }
+
+    fn can_handle(&self, model: &str) -> bool {
+        matches_prefix(&self.supported_models(), model)
+    }
+
+    fn can_handle_response(&self, response: &Value) -> bool {
+        response.pointer("/output/message").is_some()
+    }
+
+    fn parse_with_model(&self, raw_response: &str, model: &str) -> Result<ContentFrame, ParseError> {
+        let json: Value = serde_json::from_str(raw_response)?;
+
+        let content = json
+            .pointer("/output/message/content")
+            .and_then(|c| c.as_array())
+            .ok_or_else(|| ParseError::MissingField("output.message.content".to_string()))?;
+
+        Ok(ContentFrame {
+            id: json
+                .pointer("/ResponseMetadata/RequestId")
+                .and_then(|id| id.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            model: model.to_string(),
+            blocks: content.iter().filter_map(parse_converse_block).collect(),
+            stop_reason: optional_str(&json, "stopReason"),
+            usage: usage_from(json.get("usage"), "inputTokens", "outputTokens"),
+            safety: blocked_by_stop_reason(
+                &json,
+                "stopReason",
+                &["guardrail_intervened", "content_filtered"],
+            ),
+            ..Default::default()
+        })
+    }
+}
+
+/// Normalize a Converse content block, skipping unknown or malformed ones
+fn parse_converse_block(block: &Value) -> Option<ContentBlock> {
+    if let Some(text) = block.get("text").and_then(|t| t.as_str()) {
+        return Some(ContentBlock::text(text));
+    }
+
+    if let Some(tool_use) = block.get("toolUse") {
+        return Some(ContentBlock::ToolUse {
+            id: optional_str(tool_use, "toolUseId")?,
+            name: optional_str(tool_use, "name")?,
+            input: tool_use.get("input").cloned().unwrap_or_else(|| json!({})),
+        });
+    }
+
+    if let Some(tool_result) = block.get("toolResult") {
+        let content = tool_result
+            .get("content")
+            .and_then(|c| c.as_array())
+            .map(|content| {
+                content
+                    .iter()
+                    .filter_map(parse_tool_result_content)
+                    .collect()
+            })
+            .unwrap_or_default();
+        return Some(ContentBlock::ToolResult {
+            tool_use_id: optional_str(tool_result, "toolUseId")?,
+            content,
+            is_error: tool_result.get("status").and_then(|s| s.as_str()) == Some("error"),
+        });
+    }
+
+    if let Some(reasoning) = block.get("reasoningContent") {
+        if let Some(text) = reasoning.get("reasoningText") {
+            return Some(ContentBlock::Thinking {
+                thinking: optional_str(text, "text"),
+                signature: optional_str(text, "signature"),
+            });
+        }
+        return Some(ContentBlock::Thinking {
+            thinking: None,
+            signature: optional_str(reasoning, "redactedContent"),
+        });
+    }
+
+    None
+}
+
+/// Tool result content entries hold either `text` or `json`
+fn parse_tool_result_content(content: &Value) -> Option<ContentResultBlock> {
+    let content = match (content.get("text"), content.get("json")) {
+        (Some(Value::String(text)), _) => text.clone(),
+        (_, Some(value)) => value.to_string(),
+        _ => return None,
+    };
+    Some(ContentResultBlock { content })
+}
+
+/// Parser for Bedrock InvokeModel response bodies
+///
+/// InvokeModel returns each model family's native body. Supported bodies are:
+///
+/// - Anthropic Claude: the Anthropic Messages format
+/// - Meta Llama: `generation`, `stop_reason` and token counts
+/// - Amazon Titan Text: `results[].outputText`
+/// - Mistral text completions: `outputs[].text`
+/// - Mistral chat completions, AI21 Jamba and DeepSeek: OpenAI-style `choices`
+/// - Cohere Command: `generations[].text`
+/// - Cohere Command R: `text` and `tool_calls`
+///
+/// The frame id is taken from the body's `id` or `response_id`, and is empty
+/// for families whose bodies have neither.
+///
+/// # Examples
+///
+/// ```
+/// use adaptogen::registry::ParserRegistry;
+///
+/// let response = r#"{
+///     "generation": "Hello!",
+///     "prompt_token_count": 8,
+///     "generation_token_count": 3,
+///     "stop_reason": "stop"
+/// }"#;
+///
+/// let registry = ParserRegistry::with_defaults();
+/// let frame = registry
+///     .parse_with_model(response, "meta.llama3-70b-instruct-v1:0")
+///     .unwrap();
+/// assert_eq!(frame.blocks.len(), 1);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BedrockInvokeParser;
+
+impl ModelResponseParser for BedrockInvokeParser {
+    /// Bedrock model id prefixes handled by this parser
+    fn supported_models(&self) -> Vec<String> {
+        bedrock_model_prefixes()
+    }
+
+    fn parse(&self, raw_response: &str) -> Result<ContentFrame, ParseError> {
+        self.parse_with_model(raw_response, "")
+    }
+
+    fn can_handle(&self, model: &str) -> bool {
+        matches_prefix(&self.supported_models(), model)
+    }
+
+    fn can_handle_response(&self, response: &Value) -> bool {
+        is_anthropic_body(response)
+            || response.get("generation").is_some_and(Value::is_string)
+            || response.get("results").is_some_and(Value::is_array)
+            || response.get("outputs").is_some_and(Value::is_array)
+            || is_chat_completion_body(response)
+            || response.get("generations").is_some_and(Value::is_array)
+            || (response.get("text").is_some_and(Value::is_string)
+                && response.get("finish_reason").is_some())
+    }
+
+    fn parse_with_model(&self, raw_response: &str, model: &str) -> Result<ContentFrame, ParseError> {
+        let json: Value = serde_json::from_str(raw_response)?;
+
+        if is_anthropic_body(&json) {
+            let mut frame = AnthropicParser.parse(raw_response)?;
+            if !model.is_empty() {
+                frame.model = model.to_string();
+            }
+            return Ok(frame);
+        }
+
+        let (blocks, stop_reason, usage) = if let Some(generation) = json.get("generation") {
+            // Meta Llama
+            (
+                text_blocks([generation.as_str().unwrap_or_default()]),
+                optional_str(&json, "stop_reason"),
+                usage_from(Some(&json), "prompt_token_count", "generation_token_count"),
+            )
+        } else if let Some(results) = json.get("results").and_then(|r| r.as_array()) {
+            // Amazon Titan Text
+            let output_tokens: u64 = results
+                .iter()
+                .filter_map(|r| r.get("tokenCount").and_then(|t| t.as_u64()))
+                .sum();
+            let usage = json
+                .get("inputTextTokenCount")
+                .and_then(|t| t.as_u64())
+                .map(|input_tokens| Usage {
+                    input_tokens,
+                    output_tokens,
+                });
+            (
+                text_blocks(results.iter().filter_map(|r| r.get("outputText")?.as_str())),
+                results.first().and_then(|r| optional_str(r, "completionReason")),
+                usage,
+            )
+        } else if let Some(outputs) = json.get("outputs").and_then(|o| o.as_array()) {
+            // Mistral text completion
+            (
+                text_blocks(outputs.iter().filter_map(|o| o.get("text")?.as_str())),
+                outputs.first().and_then(|o| optional_str(o, "stop_reason")),
+                None,
+            )
+        } else if let Some(choice) = json.get("choices").and_then(|c| c.get(0)) {
+            // Mistral chat completion, AI21 Jamba and DeepSeek
+            (
+                choice_blocks(choice),
+                optional_str(choice, "finish_reason").or_else(|| optional_str(choice, "stop_reason")),
+                usage_from(json.get("usage"), "prompt_tokens", "completion_tokens"),
+            )
+        } else if let Some(generations) = json.get("generations").and_then(|g| g.as_array()) {
+            // Cohere Command
+            (
+                text_blocks(generations.iter().filter_map(|g| g.get("text")?.as_str())),
+                generations.first().and_then(|g| optional_str(g, "finish_reason")),
+                None,
+            )
+        } else if let Some(text) = json.get("text").and_then(|t| t.as_str()) {
+            // Cohere Command R
+            let mut blocks = text_blocks([text]);
+            let tool_calls = json.get("tool_calls").and_then(|t| t.as_array());
+            for (index, tool_call) in tool_calls.into_iter().flatten().enumerate() {
+                let Some(name) = optional_str(tool_call, "name") else {
+                    continue;
+                };
+                blocks.push(ContentBlock::ToolUse {
+                    id: format!("call_{index}"),
+                    name,
+                    input: tool_call.get("parameters").cloned().unwrap_or_else(|| json!({})),
+                });
+            }
+            (blocks, optional_str(&json, "finish_reason"), None)
+        } else {
+            return Err(ParseError::Other(
+                "Unrecognized Bedrock InvokeModel response body".to_string(),
+            ));
+        };
+
+        Ok(ContentFrame {
+            id: optional_str(&json, "id")
+                .or_else(|| optional_str(&json, "response_id"))
+                .unwrap_or_default(),
+            model: model.to_string(),
+            blocks,
+            stop_reason,
+            usage,
+            safety: None,
+            ..Default::default()
+        })
+    }
+}
+
+/// Text blocks for the non-empty texts
+fn text_blocks<'a>(texts: impl IntoIterator<Item = &'a str>) -> Vec<ContentBlock> {
+    texts
+        .into_iter()
+        .filter(|text| !text.is_empty())
+        .map(ContentBlock::text)
+        .collect()
+}
+
+/// Normalize a chat completion choice, with either a `message` or a plain `text`
+///
+/// DeepSeek-R1 writes its reasoning before a closing `</think>` tag, which is
+/// split into a thinking block.
+fn choice_blocks(choice: &Value) -> Vec<ContentBlock> {
+    let message = choice.get("message");
+    let content = message
+        .and_then(|m| m.get("content"))
+        .or_else(|| choice.get("text"))
+        .and_then(|c| c.as_str())
+        .unwrap_or_default();
+
+    let mut blocks = Vec::new();
+    let reasoning = message
+        .and_then(|m| optional_str(m, "reasoning_content"))
+        .filter(|r| !r.trim().is_empty());
+    let (thinking, text) = split_thinking(content, "<think>", "</think>");
+    if let Some(thinking) = reasoning.map(|r| r.trim().to_string()).or(thinking) {
+        blocks.push(ContentBlock::Thinking {
+            thinking: Some(thinking),
+            signature: None,
+        });
+    }
+    blocks.extend(text_blocks([text.as_str()]));
+
+    let tool_calls = message.and_then(|m| m.get("tool_calls")).and_then(|t| t.as_array());
+    for (index, tool_call) in tool_calls.into_iter().flatten().enumerate() {
+        let function = tool_call.get("function");
+        let Some(name) = function.and_then(|f| optional_str(f, "name")) else {
+            continue;
+        };
+        blocks.push(ContentBlock::ToolUse {
+            id: optional_str(tool_call, "id").unwrap_or_else(|| format!("call_{index}")),
+            name,
+            input: decode_arguments(function.and_then(|f| f.get("arguments"))),
+        });
+    }
+
+    blocks
+}
+
+/// Chat completion bodies look like OpenAI's, but Mistral and DeepSeek report a
+/// per-choice `stop_reason` and AI21 adds a `meta` object
+fn is_chat_completion_body(response: &Value) -> bool {
+    let Some(choices) = response.get("choices").and_then(|c| c.as_array()) else {
+        return false;
+    };
+    choices.iter().any(|c| c.get("stop_reason").is_some())
+        || response.pointer("/meta/requestDurationMillis").is_some()
+}
+
+fn is_anthropic_body(response: &Value) -> bool {
+    response.get("type").and_then(|t| t.as_str()) == Some("message")
+        && response.get("content").is_some_and(Value::is_array)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_converse_all_block_types() {
+        let response = r#"{
+            "output": {"message": {"role": "assistant", "content": [
+                {"reasoningContent": {"reasoningText": {"text": "Think", "signature": "sig"}}},
+                {"reasoningContent": {"redactedContent": "c2VjcmV0"}},
+                {"text": "Checking."},
+                {"toolUse": {"toolUseId": "tooluse_1", "name": "get_weather", "input": {"city": "Paris"}}},
+                {"toolResult": {"toolUseId": "tooluse_1", "content": [{"text": "Sunny"}, {"json": {"c": 21}}], "status": "error"}},
+                {"image": {"format": "png"}}
+            ]}},
+            "stopReason": "tool_use",
+            "usage": {"inputTokens": 30, "outputTokens": 12, "totalTokens": 42},
+            "ResponseMetadata": {"RequestId": "req-1"}
+        }"#;
+
+        let frame = BedrockConverseParser
+            .parse_with_model(response, "us.anthropic.claude-sonnet-4-20250514-v1:0")
+            .unwrap();
+        assert_eq!(frame.id, "req-1");
+        assert_eq!(frame.model, "us.anthropic.claude-sonnet-4-20250514-v1:0");
+        assert_eq!(frame.stop_reason.as_deref(), Some("tool_use"));
+        assert_eq!(frame.usage.unwrap().total_tokens(), 42);
+        assert_eq!(frame.blocks.len(), 5);
+
+        match &frame.blocks[1] {
+            ContentBlock::Thinking { thinking, signature } => {
+                assert!(thinking.is_none());
+                assert_eq!(signature.as_deref(), Some("c2VjcmV0"));
+            }
+            _ => panic!("Expected Thinking block"),
+        }
+        match &frame.blocks[3] {
+            ContentBlock::ToolUse { id, input, .. } => {
+                assert_eq!(id, "tooluse_1");
+                assert_eq!(input["city"], "Paris");
+            }
+            _ => panic!("Expected ToolUse block"),
+        }
+        match &frame.blocks[4] {
+            ContentBlock::ToolResult { content, is_error, .. } => {
+                assert_eq!(content[0].content, "Sunny");
+                assert_eq!(content[1].content, r#"{"c":21}"#);
+                assert!(is_error);
+            }
+            _ => panic!("Expected ToolResult block"),
+        }
+    }
+
+    #[test]
+    fn test_converse_without_model() {
+        let response = r#"{"output": {"message": {"content": [{"text": "Hi"}]}}}"#;
+        let frame = BedrockConverseParser.parse(response).unwrap();
+        assert_eq!(frame.id, "");
+        assert_eq!(frame.model, "");
+    }
+
+    #[test]
+    fn test_converse_missing_content() {
+        match BedrockConverseParser.parse(r#"{"stopReason": "end_turn"}"#).unwrap_err() {
+            ParseError::MissingField(field) => assert_eq!(field, "output.message.content"),
+            _ => panic!("Expected MissingField error"),
+        }
+    }
+
+    #[test]
+    fn test_invoke_model_bodies() {
+        let titan = r#"{"inputTextTokenCount": 5, "results": [{"tokenCount": 7, "outputText": "Hello", "completionReason": "FINISH"}]}"#;
+        let frame = BedrockInvokeParser
+            .parse_with_model(titan, "amazon.titan-text-express-v1")
+            .unwrap();
+        assert_eq!(frame.stop_reason.as_deref(), Some("FINISH"));
+        assert_eq!(frame.usage.unwrap().output_tokens, 7);
+
+        let mistral = r#"{"outputs": [{"text": "Bonjour", "stop_reason": "stop"}]}"#;
+        let frame = BedrockInvokeParser.parse(mistral).unwrap();
+        match &frame.blocks[0] {
+            ContentBlock::Text { text, .. } => assert_eq!(text, "Bonjour"),
+            _ => panic!("Expected Text block"),
+        }
+
+        let anthropic = r#"{"id": "msg_bdrk_1", "type": "message", "model": "claude-3-5-sonnet-20240620", "content": [{"type": "text", "text": "Hi"}]}"#;
+        let frame = BedrockInvokeParser
+            .parse_with_model(anthropic, "anthropic.claude-3-5-sonnet-20240620-v1:0")
+            .unwrap();
+        assert_eq!(frame.id, "msg_bdrk_1");
+        assert_eq!(frame.model, "anthropic.claude-3-5-sonnet-20240620-v1:0");
+
+        assert!(BedrockInvokeParser.parse(r#"{"unknown": true}"#).is_err());
+    }
+
+    #[test]
+    fn test_invoke_model_chat_bodies() {
+        let mistral = json!({"choices": [{"message": {"role": "assistant", "content": "Bonjour"}, "stop_reason": "stop"}]});
+        let jamba = json!({
+            "id": "chatcmpl-1",
+            "choices": [{"message": {"role": "assistant", "content": "Hi"}, "finish_reason": "stop"}],
+            "usage": {"prompt_tokens": 4, "completion_tokens": 1},
+            "meta": {"requestDurationMillis": 120}
+        });
+        let command_r = json!({"text": "Hi", "finish_reason": "COMPLETE", "tool_calls": [{"name": "lookup", "parameters": {"q": "x"}}]});
+        for body in [&mistral, &jamba, &command_r] {
+            assert!(BedrockInvokeParser.can_handle_response(body));
+        }
+
+        // Plain OpenAI bodies are left to the OpenAI parser
+        let openai = json!({"id": "chatcmpl-1", "choices": [{"message": {"content": "Hi"}, "finish_reason": "stop"}]});
+        assert!(!BedrockInvokeParser.can_handle_response(&openai));
+
+        let frame = BedrockInvokeParser.parse(&jamba.to_string()).unwrap();
+        assert_eq!(frame.id, "chatcmpl-1");
+        assert_eq!(frame.usage.unwrap().input_tokens, 4);
+
+        let frame = BedrockInvokeParser.parse(&command_r.to_string()).unwrap();
+        match &frame.blocks[1] {
+            ContentBlock::ToolUse { id, name, input } => {
+                assert_eq!((id.as_str(), name.as_str()), ("call_0", "lookup"));
+                assert_eq!(input, &json!({"q": "x"}));
+            }
+            _ => panic!("Expected ToolUse block"),
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! Parser for the Cohere v2 Chat API
+
+use serde_json::Value;
+
+use super::{decode_arguments, matches_prefix, optional_str, required_str, usage_from};
+use crate::normalized::{Citation, CitationSource, ContentBlock, ContentFrame, Usage};
+use crate::parser::{ModelResponseParser, ParseError};
+
+/// Parser for Cohere v2 `/v2/chat` responses
+///
+/// Cohere responses do not include the model name, so they are recognized by
+/// shape; pass the model to
+/// [`ParserRegistry::parse_with_model`](crate::registry::ParserRegistry::parse_with_model)
+/// to have it recorded in the frame. The `tool_plan` and `thinking` content
+/// become `Thinking` blocks, and `citations` are attached to the text block
+/// whose span they support, with offsets relative to that block.
+///
+/// # Examples
+///
+/// ```
+/// use adaptogen::parser::ModelResponseParser;
+/// use adaptogen::providers::CohereParser;
+///
+/// let response = r#"{
+///     "id": "c14c80c3",
+///     "finish_reason": "COMPLETE",
+///     "message": {"role": "assistant", "content": [{"type": "text", "text": "Hello!"}]}
+/// }"#;
+///
+/// let frame = CohereParser.parse_with_model(response, "command-a-03-2025").unwrap();
+/// assert_eq!(frame.model, "command-a-03-2025");
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CohereParser;
+
+impl ModelResponseParser for CohereParser {
+    /// Model name prefixes handled by this parser
+    fn supported_models(&self) -> Vec<String> {
+        vec!["command".to_string(), "c4ai-".to_string()]
+    }
+
+    fn parse(&self, raw_response: &str) -> Result<ContentFrame, ParseError> {
+        self.parse_with_model(raw_response, "")
+    }
+
+    fn can_handle(&self, model: &str) -> bool {
+        matches_prefix(&self.supported_models(), model)
+    }
+
+    fn can_handle_response(&self, response: &Value) -> bool {
+        response.get("finish_reason").is_some()
+            && response.pointer("/message/role").is_some()
+            && response.get("choices").is_none()
+    }
+
+    fn parse_with_model(
+        &self,
+        raw_response: &str,
+        model: &str,
+    ) -> Result<ContentFrame, ParseError> {
+        let json: Value = serde_json::from_str(raw_response)?;
+
+        let id = required_str(&json, "id")?;
+        let message = json
+            .get("message")
+            .ok_or_else(|| ParseError::MissingField("message".to_string()))?;
+
+        Ok(ContentFrame {
+            id,
+            model: model.to_string(),
+            blocks: parse_message(message),
+            stop_reason: optional_str(&json, "finish_reason"),
+            usage: parse_usage(json.get("usage")),
+            safety: None,
+            ..Default::default()
+        })
+    }
+}
+
+fn parse_message(message: &Value) -> Vec<ContentBlock> {
+    let mut blocks = Vec::new();
+
+    if let Some(plan) = message.get("tool_plan").and_then(|p| p.as_str()) {
+        blocks.push(ContentBlock::Thinking {
+            thinking: Some(plan.to_string()),
+            signature: None,
+        });
+    }
+
+    let mut citations: Vec<&Value> = message
+        .get("citations")
+        .and_then(|c| c.as_array())
+        .map(|c| c.iter().collect())
+        .unwrap_or_default();
+
+    // Citation offsets refer to the concatenated text of all text items
+    let mut offset = 0;
+    for item in message
+        .get("content")
+        .and_then(|c| c.as_array())
+        .into_iter()
+        .flatten()
+    {
+        match item.get("type").and_then(|t| t.as_str()) {
+            Some("text") => {
+                let Some(text) = item.get("text").and_then(|t| t.as_str()) else {
+                    continue;
+                };
+                let len = text.chars().count();
+                let (inside, rest): (Vec<&Value>, Vec<&Value>) =
+                    citations.into_iter().partition(|citation| {
+                        citation
+                            .get("start")
+                            .and_then(|s| s.as_u64())
+                            .is_some_and(|start| (start as usize) < offset + len)
+                    });
+                citations = rest;
+
+                blocks.push(ContentBlock::Text {
+                    text: text.to_string(),
+                    citations: inside
+                        .into_iter()
+                        .map(|citation| parse_citation(citation, offset))
+                        .collect(),
+                    logprobs: Vec::new(),
+                });
+                offset += len;
+            }
+            Some("thinking") => {
+                if let Some(thinking) = item.get("thinking").and_then(|t| t.as_str()) {
+                    blocks.push(ContentBlock::Thinking {
+                        thinking: Some(thinking.to_string()),
+                        signature: None,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for tool_call in message
+        .get("tool_calls")
+        .and_then(|t| t.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let function = tool_call.get("function");
+        let (Some(id), Some(name)) = (
+            tool_call.get("id").and_then(|i| i.as_str()),
+            function
+                .and_then(|f| f.get("name"))
+                .and_then(|n| n.as_str()),
+        ) else {
+            continue;
+        };
+        blocks.push(ContentBlock::ToolUse {
+            id: id.to_string(),
+            name: name.to_string(),
+            input: decode_arguments(function.and_then(|f| f.get("arguments"))),
+        });
+    }
+
+    blocks
+}
+
+/// Convert a Cohere citation, making its offsets relative to the text block
+fn parse_citation(citation: &Value, block_offset: usize) -> Citation {
+    let offset = |name: &str| {
+        citation
+            .get(name)
+            .and_then(|v| v.as_u64())
+            .map(|v| (v as usize).saturating_sub(block_offset))
+    };
+
+    let sources = citation
+        .get("sources")
+        .and_then(|s| s.as_array())
+        .into_iter()
+        .flatten()
+        .map(|source| {
+            let document = source.get("document");
+            let document_str = |name: &str| document.and_then(|d| optional_str(d, name));
+            CitationSource {
+                id: optional_str(source, "id"),
+                title: document_str("title"),
+                url: document_str("url"),
+            }
+        })
+        .collect();
+
+    Citation {
+        cited_text: optional_str(citation, "text"),
+        start: offset("start"),
+        end: offset("end"),
+        sources,
+    }
+}
+
+/// Prefer the actual token counts over the billed ones
+fn parse_usage(usage: Option<&Value>) -> Option<Usage> {
+    let usage = usage?;
+    usage_from(usage.get("tokens"), "input_tokens", "output_tokens")
+        .or_else(|| usage_from(usage.get("billed_units"), "input_tokens", "output_tokens"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_text_with_citations() {
+        let response = r#"{
+            "id": "resp_1",
+            "finish_reason": "COMPLETE",
+            "message": {
+                "role": "assistant",
+                "content": [
+                    {"type": "text", "text": "Emperor penguins "},
+                    {"type": "text", "text": "are the tallest."}
+                ],
+                "citations": [
+                    {"start": 0, "end": 16, "text": "Emperor penguins", "sources": [{"type": "document", "id": "doc:0", "document": {"title": "Penguins"}}]},
+                    {"start": 25, "end": 32, "text": "tallest", "sources": [{"type": "document", "id": "doc:1"}]}
+                ]
+            },
+            "usage": {"billed_units": {"input_tokens": 5, "output_tokens": 6}, "tokens": {"input_tokens": 70, "output_tokens": 6}}
+        }"#;
+
+        let frame = CohereParser.parse(response).unwrap();
+        assert_eq!(frame.id, "resp_1");
+        assert_eq!(frame.stop_reason.as_deref(), Some("COMPLETE"));
+        assert_eq!(frame.usage.unwrap().input_tokens, 70);
+
+        match (&frame.blocks[0], &frame.blocks[1]) {
+            (
+                ContentBlock::Text {
+                    citations: first, ..
+                },
+                ContentBlock::Text {
+                    citations: second, ..
+                },
+            ) => {
+                assert_eq!(first.len(), 1);
+                assert_eq!(first[0].sources[0].title.as_deref(), Some("Penguins"));
+                assert_eq!(second.len(), 1);
+                assert_eq!((second[0].start, second[0].end), (Some(8), Some(15)));
+            }
+            _ => panic!("Expected Text blocks"),
+        }
+    }
+
+    #[test]
+    fn test_parse_tool_plan_and_calls() {
+        let response = r#"{
+            "id": "resp_2",
+            "finish_reason": "TOOL_CALL",
+            "message": {
+                "role": "assistant",
+                "tool_plan": "I will look up the weather.",
+                "tool_calls": [{"id": "get_weather_1", "type": "function", "function": {"name": "get_weather", "arguments": "{\"city\":\"Toronto\"}"}}]
+            }
+        }"#;
+
+        let frame = CohereParser.parse(response).unwrap();
+        assert_eq!(frame.blocks.len(), 2);
+        assert!(matches!(frame.blocks[0], ContentBlock::Thinking { .. }));
+        match &frame.blocks[1] {
+            ContentBlock::ToolUse { id, input, .. } => {
+                assert_eq!(id, "get_weather_1");
+                assert_eq!(input["city"], "Toronto");
+            }
+            _ => panic!("Expected ToolUse block"),
+        }
+    }
+
+    #[test]
+    fn test_recognizes_shape() {
+        let cohere = serde_json::json!({"id": "1", "finish_reason": "COMPLETE", "message": {"role": "assistant"}});
+        let openai = serde_json::json!({"id": "1", "choices": [], "message": {"role": "assistant"}, "finish_reason": "stop"});
+        assert!(CohereParser.can_handle_response(&cohere));
+        assert!(!CohereParser.can_handle_response(&openai));
+    }
+}
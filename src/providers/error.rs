@@ -0,0 +1,271 @@
+//! Recognition of provider error bodies
+
+use serde_json::Value;
+
+use super::optional_str;
+use crate::parser::{ParseError, ProviderErrorKind};
+
+/// Keywords identifying each error kind, checked in order
+///
+/// Codes and messages are lowercased with spaces and dashes replaced by
+/// underscores before matching, so `Rate limit reached` matches `rate_limit`.
+const KEYWORDS: &[(ProviderErrorKind, &[&str])] = &[
+    (
+        ProviderErrorKind::ContextLength,
+        &[
+            "context_length",
+            "context_window",
+            "maximum_context",
+            "prompt_is_too_long",
+            "input_is_too_long",
+        ],
+    ),
+    (
+        ProviderErrorKind::RateLimit,
+        &["rate_limit", "ratelimit", "throttl", "resource_exhausted", "too_many_requests"],
+    ),
+    (
+        ProviderErrorKind::Overloaded,
+        &["overloaded", "unavailable", "capacity"],
+    ),
+    (
+        ProviderErrorKind::Authentication,
+        &[
+            "auth",
+            "api_key",
+            "permission",
+            "access_denied",
+            "accessdenied",
+            "forbidden",
+        ],
+    ),
+    (ProviderErrorKind::NotFound, &["not_found", "notfound"]),
+    (
+        ProviderErrorKind::InvalidRequest,
+        &["invalid", "validation", "bad_request", "too_large", "failed_precondition"],
+    ),
+    (
+        ProviderErrorKind::Server,
+        &["api_error", "server_error", "internal", "timeout", "deadline_exceeded"],
+    ),
+];
+
+/// Recognize a provider error body
+///
+/// Returns a [`ParseError::ProviderError`] when `response` is an error payload
+/// rather than a model message. The following formats are recognized:
+///
+/// - Anthropic: `{"type": "error", "error": {"type", "message"}}`
+/// - Mistral: `{"object": "error", "message", "type", "code"}`
+/// - Gemini: `{"error": {"code", "message", "status"}}`
+/// - OpenAI and compatible servers: `{"error": {"message", "type", "code"}}`
+/// - Bedrock: `{"__type", "message"}`
+/// - Ollama: `{"error": "<message>"}`
+///
+/// The error kind is derived from the provider's error code, falling back to
+/// the message and then to an HTTP status code in the body.
+///
+/// # Examples
+///
+/// ```
+/// use adaptogen::parser::{ParseError, ProviderErrorKind};
+/// use adaptogen::providers::detect_provider_error;
+/// use serde_json::json;
+///
+/// let body = json!({"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}});
+/// match detect_provider_error(&body) {
+///     Some(ParseError::ProviderError { kind, retryable, .. }) => {
+///         assert_eq!(kind, ProviderErrorKind::Overloaded);
+///         assert!(retryable);
+///     }
+///     _ => panic!("Expected a provider error"),
+/// }
+/// ```
+pub fn detect_provider_error(response: &Value) -> Option<ParseError> {
+    let error = response.get("error");
+
+    if response.get("type").and_then(|t| t.as_str()) == Some("error") {
+        let error = error?;
+        return Some(provider_error(
+            "anthropic",
+            optional_str(error, "type"),
+            optional_str(error, "message"),
+            None,
+        ));
+    }
+
+    if response.get("object").and_then(|o| o.as_str()) == Some("error") {
+        return Some(provider_error(
+            "mistral",
+            code_str(response.get("code")).or_else(|| optional_str(response, "type")),
+            optional_str(response, "message"),
+            None,
+        ));
+    }
+
+    if let Some(error_type) = optional_str(response, "__type") {
+        // Types may be namespaced, as in `com.amazon.coral.service#ThrottlingException`
+        let code = error_type.rsplit('#').next().unwrap_or_default().to_string();
+        return Some(provider_error(
+            "bedrock",
+            Some(code),
+            optional_str(response, "message").or_else(|| optional_str(response, "Message")),
+            None,
+        ));
+    }
+
+    match error? {
+        Value::Object(_) => {
+            let error = error?;
+            let message = optional_str(error, "message");
+            if let Some(status) = optional_str(error, "status") {
+                let http_status = error.get("code").and_then(|c| c.as_u64());
+                Some(provider_error("gemini", Some(status), message, http_status))
+            } else {
+                let code = code_str(error.get("code")).or_else(|| optional_str(error, "type"));
+                Some(provider_error("openai", code, message, None))
+            }
+        }
+        Value::String(message) => Some(provider_error("ollama", None, Some(message.clone()), None)),
+        _ => None,
+    }
+}
+
+/// Error codes are strings for most providers but numbers for some
+fn code_str(code: Option<&Value>) -> Option<String> {
+    match code? {
+        Value::String(code) => Some(code.clone()),
+        Value::Number(code) => Some(code.to_string()),
+        _ => None,
+    }
+}
+
+fn provider_error(
+    provider: &str,
+    code: Option<String>,
+    message: Option<String>,
+    http_status: Option<u64>,
+) -> ParseError {
+    let message = message.unwrap_or_default();
+    let from_code = code.as_deref().and_then(kind_from_text);
+    let from_message = kind_from_text(&message);
+
+    // Context length errors are often reported as generic invalid requests
+    let kind = if from_message == Some(ProviderErrorKind::ContextLength) {
+        ProviderErrorKind::ContextLength
+    } else {
+        from_code
+            .or(from_message)
+            .or_else(|| {
+                http_status
+                    .or_else(|| code.as_deref()?.parse().ok())
+                    .and_then(kind_from_status)
+            })
+            .unwrap_or(ProviderErrorKind::Other)
+    };
+
+    ParseError::ProviderError {
+        provider: provider.to_string(),
+        kind,
+        message,
+        code,
+        retryable: kind.is_retryable(),
+    }
+}
+
+fn kind_from_text(text: &str) -> Option<ProviderErrorKind> {
+    let text = text.to_lowercase().replace([' ', '-'], "_");
+    KEYWORDS
+        .iter()
+        .find(|(_, keywords)| keywords.iter().any(|keyword| text.contains(keyword)))
+        .map(|(kind, _)| *kind)
+}
+
+fn kind_from_status(status: u64) -> Option<ProviderErrorKind> {
+    match status {
+        400 | 413 | 422 => Some(ProviderErrorKind::InvalidRequest),
+        401 | 403 => Some(ProviderErrorKind::Authentication),
+        404 => Some(ProviderErrorKind::NotFound),
+        429 => Some(ProviderErrorKind::RateLimit),
+        503 | 529 => Some(ProviderErrorKind::Overloaded),
+        500..=599 => Some(ProviderErrorKind::Server),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn detect(response: Value) -> (String, ProviderErrorKind, Option<String>, bool) {
+        match detect_provider_error(&response) {
+            Some(ParseError::ProviderError {
+                provider,
+                kind,
+                code,
+                retryable,
+                ..
+            }) => (provider, kind, code, retryable),
+            other => panic!("Expected ProviderError, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_anthropic_errors() {
+        let (provider, kind, code, retryable) = detect(json!({
+            "type": "error",
+            "error": {"type": "rate_limit_error", "message": "Number of request tokens has exceeded your per-minute rate limit"}
+        }));
+        assert_eq!(provider, "anthropic");
+        assert_eq!(kind, ProviderErrorKind::RateLimit);
+        assert_eq!(code.as_deref(), Some("rate_limit_error"));
+        assert!(retryable);
+
+        // The message refines a generic invalid request error
+        let (_, kind, _, retryable) = detect(json!({
+            "type": "error",
+            "error": {"type": "invalid_request_error", "message": "prompt is too long: 210000 tokens > 200000 maximum"}
+        }));
+        assert_eq!(kind, ProviderErrorKind::ContextLength);
+        assert!(!retryable);
+    }
+
+    #[test]
+    fn test_openai_errors() {
+        let (provider, kind, code, _) = detect(json!({"error": {
+            "message": "This model's maximum context length is 128000 tokens.",
+            "type": "invalid_request_error",
+            "param": "messages",
+            "code": "context_length_exceeded"
+        }}));
+        assert_eq!(provider, "openai");
+        assert_eq!(kind, ProviderErrorKind::ContextLength);
+        assert_eq!(code.as_deref(), Some("context_length_exceeded"));
+
+        let (_, kind, _, _) = detect(json!({"error": {"message": "Incorrect API key provided", "type": "invalid_request_error", "code": "invalid_api_key"}}));
+        assert_eq!(kind, ProviderErrorKind::Authentication);
+    }
+
+    #[test]
+    fn test_other_provider_errors() {
+        let (provider, kind, _, retryable) = detect(json!({"error": {"code": 503, "message": "The model is overloaded.", "status": "UNAVAILABLE"}}));
+        assert_eq!((provider.as_str(), kind, retryable), ("gemini", ProviderErrorKind::Overloaded, true));
+
+        let (provider, kind, code, _) = detect(json!({"__type": "com.amazon.coral.validate#ValidationException", "message": "Malformed input request"}));
+        assert_eq!((provider.as_str(), kind), ("bedrock", ProviderErrorKind::InvalidRequest));
+        assert_eq!(code.as_deref(), Some("ValidationException"));
+
+        let (provider, kind, _, _) = detect(json!({"object": "error", "message": "Unauthorized", "type": "unauthorized", "code": "1000"}));
+        assert_eq!((provider.as_str(), kind), ("mistral", ProviderErrorKind::Authentication));
+
+        let (provider, kind, _, _) = detect(json!({"error": "model \"llama9\" not found, try pulling it first"}));
+        assert_eq!((provider.as_str(), kind), ("ollama", ProviderErrorKind::NotFound));
+    }
+
+    #[test]
+    fn test_ignores_successful_responses() {
+        assert!(detect_provider_error(&json!({"id": "1", "model": "gpt-4o", "choices": []})).is_none());
+        assert!(detect_provider_error(&json!({"id": "1", "model": "o3", "error": null})).is_none());
+    }
+}
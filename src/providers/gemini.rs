@@ -0,0 +1,448 @@
+//! Parser for the Google Gemini `generateContent` API
+
+use serde_json::Value;
+
+use super::{
+    array_field, assign_offsets, decode_arguments, index_field, logprobs_in, matches_prefix,
+    optional_str, required_str, usage_from,
+};
+use crate::normalized::{
+    Candidate, ContentBlock, ContentFrame, ParsedResponse, SafetyRating, SafetyReport,
+    TokenLogprob, TopLogprob, Usage,
+};
+use crate::parser::{ModelResponseParser, ParseError};
+
+/// Parser for Gemini `generateContent` responses
+///
+/// The frame id and model are read from `responseId` and `modelVersion`. Parts
+/// flagged with `thought: true` become `Thinking` blocks and `functionCall` parts
+/// become `ToolUse` blocks. Gemini does not always assign ids to function calls;
+/// missing ids are filled in as `call_<n>`, where `n` is the position of the call
+/// among the function calls of the candidate. Code run by Gemini's code
+/// execution tool becomes `ServerToolUse` and `ServerToolResult` blocks, paired
+/// by generated `code_execution_<n>` ids.
+///
+/// [`parse`](ModelResponseParser::parse) normalizes the first candidate; use
+/// [`parse_all`](ModelResponseParser::parse_all) to get every candidate of a
+/// `candidateCount > 1` request along with its raw `logprobsResult`. Requested
+/// logprobs are also normalized onto the text blocks.
+///
+/// # Examples
+///
+/// ```
+/// use adaptogen::parser::ModelResponseParser;
+/// use adaptogen::providers::GeminiParser;
+///
+/// let response = r#"{
+///     "responseId": "resp_123",
+///     "modelVersion": "gemini-2.0-flash",
+///     "candidates": [{"content": {"role": "model", "parts": [{"text": "Hello!"}]}}]
+/// }"#;
+///
+/// let frame = GeminiParser.parse(response).unwrap();
+/// assert_eq!(frame.model, "gemini-2.0-flash");
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GeminiParser;
+
+impl ModelResponseParser for GeminiParser {
+    /// Model name prefixes handled by this parser
+    fn supported_models(&self) -> Vec<String> {
+        vec!["gemini".to_string(), "models/gemini".to_string()]
+    }
+
+    fn parse(&self, raw_response: &str) -> Result<ContentFrame, ParseError> {
+        let json: Value = serde_json::from_str(raw_response)?;
+        parse_candidate(&json, array_field(&json, "candidates").first())
+    }
+
+    fn can_handle(&self, model: &str) -> bool {
+        matches_prefix(&self.supported_models(), model)
+    }
+
+    fn parse_all(&self, raw_response: &str) -> Result<ParsedResponse, ParseError> {
+        let json: Value = serde_json::from_str(raw_response)?;
+
+        // A blocked prompt has no candidates; keep its safety report
+        if array_field(&json, "candidates").is_empty()
+            && json.pointer("/promptFeedback/blockReason").is_some()
+        {
+            return Ok(ParsedResponse::from(parse_candidate(&json, None)?));
+        }
+
+        let candidates = array_field(&json, "candidates")
+            .iter()
+            .enumerate()
+            .map(|(position, candidate)| {
+                Ok(Candidate {
+                    index: index_field(candidate, "index").unwrap_or(position),
+                    frame: parse_candidate(&json, Some(candidate))?,
+                    logprobs: candidate.get("logprobsResult").cloned(),
+                })
+            })
+            .collect::<Result<_, ParseError>>()?;
+
+        Ok(ParsedResponse { candidates })
+    }
+
+    fn parse_all_with_model(
+        &self,
+        raw_response: &str,
+        _model: &str,
+    ) -> Result<ParsedResponse, ParseError> {
+        self.parse_all(raw_response)
+    }
+}
+
+/// Build the frame for one candidate; a missing candidate yields a frame without blocks
+fn parse_candidate(json: &Value, candidate: Option<&Value>) -> Result<ContentFrame, ParseError> {
+    let id = required_str(json, "responseId")?;
+    let model = required_str(json, "modelVersion")?;
+
+    let logprobs = parse_logprobs(candidate.and_then(|c| c.get("logprobsResult")));
+    let blocks = candidate
+        .and_then(|candidate| candidate.pointer("/content/parts"))
+        .and_then(|parts| parts.as_array())
+        .map(|parts| parse_parts(parts, &logprobs))
+        .unwrap_or_default();
+
+    Ok(ContentFrame {
+        id,
+        model,
+        blocks,
+        stop_reason: candidate.and_then(|candidate| optional_str(candidate, "finishReason")),
+        usage: parse_usage(json.get("usageMetadata")),
+        safety: parse_safety(json, candidate),
+        ..Default::default()
+    })
+}
+
+/// Finish reasons meaning the candidate was stopped by a safety filter
+const BLOCKED_FINISH_REASONS: &[&str] = &[
+    "SAFETY",
+    "RECITATION",
+    "BLOCKLIST",
+    "PROHIBITED_CONTENT",
+    "SPII",
+    "IMAGE_SAFETY",
+];
+
+/// Combine prompt feedback with the candidate's safety ratings
+fn parse_safety(json: &Value, candidate: Option<&Value>) -> Option<SafetyReport> {
+    let feedback = json.get("promptFeedback");
+
+    let mut ratings = safety_ratings(feedback, true);
+    ratings.extend(safety_ratings(candidate, false));
+
+    let reason = feedback
+        .and_then(|f| optional_str(f, "blockReason"))
+        .or_else(|| {
+            candidate
+                .and_then(|c| optional_str(c, "finishReason"))
+                .filter(|r| BLOCKED_FINISH_REASONS.contains(&r.as_str()))
+        });
+
+    if reason.is_none() && ratings.is_empty() {
+        return None;
+    }
+
+    Some(SafetyReport {
+        blocked: reason.is_some(),
+        reason,
+        ratings,
+    })
+}
+
+fn safety_ratings(source: Option<&Value>, prompt: bool) -> Vec<SafetyRating> {
+    source
+        .map(|s| array_field(s, "safetyRatings"))
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|rating| {
+            Some(SafetyRating {
+                category: optional_str(rating, "category")?,
+                severity: optional_str(rating, "probability"),
+                filtered: rating.get("blocked").and_then(|b| b.as_bool()) == Some(true),
+                prompt,
+            })
+        })
+        .collect()
+}
+
+/// Gemini reports thinking tokens separately from candidate tokens
+fn parse_usage(metadata: Option<&Value>) -> Option<Usage> {
+    let mut usage = usage_from(metadata, "promptTokenCount", "candidatesTokenCount")?;
+    usage.output_tokens = usage.output_tokens.saturating_add(
+        metadata
+            .and_then(|m| m.get("thoughtsTokenCount"))
+            .and_then(|t| t.as_u64())
+            .unwrap_or(0),
+    );
+    Some(usage)
+}
+
+/// Read a `logprobsResult` into a token track over the candidate's answer text
+fn parse_logprobs(result: Option<&Value>) -> Vec<TokenLogprob> {
+    let Some(result) = result else {
+        return Vec::new();
+    };
+    let top = array_field(result, "topCandidates");
+
+    let mut tokens: Vec<TokenLogprob> = array_field(result, "chosenCandidates")
+        .iter()
+        .enumerate()
+        .filter_map(|(i, chosen)| {
+            Some(TokenLogprob {
+                token: optional_str(chosen, "token")?,
+                logprob: chosen.get("logProbability")?.as_f64()?,
+                offset: 0,
+                bytes: None,
+                top_logprobs: top
+                    .get(i)
+                    .map(|t| array_field(t, "candidates"))
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(|alternative| {
+                        Some(TopLogprob {
+                            token: optional_str(alternative, "token")?,
+                            logprob: alternative.get("logProbability")?.as_f64()?,
+                            bytes: None,
+                        })
+                    })
+                    .collect(),
+            })
+        })
+        .collect();
+
+    assign_offsets(&mut tokens);
+    tokens
+}
+
+/// Normalize candidate parts
+///
+/// `logprobs` covers the concatenated text of the non-thought parts and is
+/// split across their text blocks.
+fn parse_parts(parts: &[Value], logprobs: &[TokenLogprob]) -> Vec<ContentBlock> {
+    let mut blocks = Vec::new();
+    let mut text_offset = 0;
+    let mut function_calls = 0;
+    let mut code_executions: usize = 0;
+
+    for part in parts {
+        if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
+            let is_thought = part.get("thought").and_then(|t| t.as_bool()) == Some(true);
+            if is_thought {
+                blocks.push(ContentBlock::Thinking {
+                    thinking: Some(text.to_string()),
+                    signature: part
+                        .get("thoughtSignature")
+                        .and_then(|s| s.as_str())
+                        .map(|s| s.to_string()),
+                });
+            } else {
+                let range = text_offset..text_offset + text.len();
+                text_offset = range.end;
+                blocks.push(ContentBlock::Text {
+                    text: text.to_string(),
+                    citations: Vec::new(),
+                    logprobs: logprobs_in(logprobs, range),
+                });
+            }
+        } else if let Some(call) = part.get("functionCall") {
+            let Some(name) = call.get("name").and_then(|n| n.as_str()) else {
+                continue;
+            };
+            let id = call
+                .get("id")
+                .and_then(|i| i.as_str())
+                .map(|i| i.to_string())
+                .unwrap_or_else(|| format!("call_{function_calls}"));
+            function_calls += 1;
+
+            blocks.push(ContentBlock::ToolUse {
+                id,
+                name: name.to_string(),
+                input: decode_arguments(call.get("args")),
+            });
+        } else if let Some(code) = part.get("executableCode") {
+            code_executions += 1;
+            blocks.push(ContentBlock::ServerToolUse {
+                id: format!("code_execution_{}", code_executions - 1),
+                name: "code_execution".to_string(),
+                input: code.clone(),
+            });
+        } else if let Some(result) = part.get("codeExecutionResult") {
+            // Results follow the code they belong to
+            blocks.push(ContentBlock::ServerToolResult {
+                tool_use_id: format!("code_execution_{}", code_executions.saturating_sub(1)),
+                content: result.clone(),
+                is_error: optional_str(result, "outcome").as_deref() != Some("OUTCOME_OK"),
+            });
+        }
+    }
+
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_can_handle_by_prefix() {
+        assert!(GeminiParser.can_handle("gemini-2.5-pro"));
+        assert!(GeminiParser.can_handle("models/gemini-1.5-flash"));
+        assert!(!GeminiParser.can_handle("gpt-4o"));
+    }
+
+    #[test]
+    fn test_parse_thoughts_and_function_calls() {
+        let response = r#"{
+            "responseId": "abc",
+            "modelVersion": "gemini-2.5-flash",
+            "candidates": [{
+                "content": {"role": "model", "parts": [
+                    {"text": "Planning the lookup", "thought": true, "thoughtSignature": "sig"},
+                    {"text": "Let me check."},
+                    {"functionCall": {"name": "get_weather", "args": {"city": "Paris"}}},
+                    {"functionCall": {"id": "fc_2", "name": "get_time", "args": {}}}
+                ]},
+                "finishReason": "STOP"
+            }],
+            "usageMetadata": {"promptTokenCount": 20, "candidatesTokenCount": 10, "thoughtsTokenCount": 30}
+        }"#;
+
+        let frame = GeminiParser.parse(response).unwrap();
+        assert_eq!(frame.id, "abc");
+        assert_eq!(frame.stop_reason.as_deref(), Some("STOP"));
+        assert_eq!(frame.usage.unwrap().output_tokens, 40);
+        assert_eq!(frame.blocks.len(), 4);
+
+        match &frame.blocks[0] {
+            ContentBlock::Thinking { thinking, signature } => {
+                assert_eq!(thinking.as_deref(), Some("Planning the lookup"));
+                assert_eq!(signature.as_deref(), Some("sig"));
+            }
+            _ => panic!("Expected Thinking block"),
+        }
+        match (&frame.blocks[2], &frame.blocks[3]) {
+            (
+                ContentBlock::ToolUse { id: first, input, .. },
+                ContentBlock::ToolUse { id: second, .. },
+            ) => {
+                assert_eq!(first, "call_0");
+                assert_eq!(input["city"], "Paris");
+                assert_eq!(second, "fc_2");
+            }
+            _ => panic!("Expected ToolUse blocks"),
+        }
+    }
+
+    #[test]
+    fn test_parse_missing_model_version() {
+        let response = r#"{"responseId": "abc", "candidates": []}"#;
+        match GeminiParser.parse(response).unwrap_err() {
+            ParseError::MissingField(field) => assert_eq!(field, "modelVersion"),
+            _ => panic!("Expected MissingField error"),
+        }
+    }
+
+    #[test]
+    fn test_token_counts_saturate() {
+        let response = r#"{
+            "responseId": "abc",
+            "modelVersion": "gemini-2.5-pro",
+            "candidates": [],
+            "usageMetadata": {"promptTokenCount": 1, "candidatesTokenCount": 18446744073709551615, "thoughtsTokenCount": 2}
+        }"#;
+        assert_eq!(GeminiParser.parse(response).unwrap().usage.unwrap().output_tokens, u64::MAX);
+    }
+
+    #[test]
+    fn test_parse_all_candidates() {
+        let response = r#"{
+            "responseId": "abc",
+            "modelVersion": "gemini-2.0-flash",
+            "candidates": [
+                {"index": 0, "content": {"parts": [{"text": "Heads"}]}, "finishReason": "STOP"},
+                {"index": 1, "content": {"parts": [{"text": "Tails"}]}, "finishReason": "STOP", "logprobsResult": {
+                    "chosenCandidates": [{"token": "Tail", "logProbability": -0.5}, {"token": "s", "logProbability": -0.1}],
+                    "topCandidates": [{"candidates": [{"token": "Tail", "logProbability": -0.5}, {"token": "Head", "logProbability": -1.0}]}]
+                }}
+            ]
+        }"#;
+
+        let response = GeminiParser.parse_all(response).unwrap();
+        assert_eq!(response.len(), 2);
+        assert_eq!(response.candidates[1].index, 1);
+        assert!(response.candidates[1].logprobs.is_some());
+        assert!(response.frames().all(|frame| frame.id == "abc"));
+
+        match &response.candidates[1].frame.blocks[0] {
+            ContentBlock::Text { logprobs, .. } => {
+                assert_eq!(logprobs.len(), 2);
+                assert_eq!(logprobs[0].top_logprobs[1].token, "Head");
+                assert_eq!(logprobs[1].offset, 4);
+            }
+            _ => panic!("Expected Text block"),
+        }
+    }
+
+    #[test]
+    fn test_safety_ratings_and_blocked_prompt() {
+        let response = r#"{
+            "responseId": "abc",
+            "modelVersion": "gemini-2.0-flash",
+            "candidates": [{"finishReason": "SAFETY", "safetyRatings": [
+                {"category": "HARM_CATEGORY_HARASSMENT", "probability": "HIGH", "blocked": true},
+                {"category": "HARM_CATEGORY_HATE_SPEECH", "probability": "NEGLIGIBLE"}
+            ]}]
+        }"#;
+
+        let safety = GeminiParser.parse(response).unwrap().safety.unwrap();
+        assert!(safety.blocked);
+        assert_eq!(safety.reason.as_deref(), Some("SAFETY"));
+        assert!(safety.ratings[0].filtered);
+        assert!(!safety.ratings[1].filtered);
+
+        let response = r#"{
+            "responseId": "abc",
+            "modelVersion": "gemini-2.0-flash",
+            "promptFeedback": {"blockReason": "PROHIBITED_CONTENT"}
+        }"#;
+
+        let parsed = GeminiParser.parse_all(response).unwrap();
+        assert_eq!(parsed.len(), 1);
+        let frame = parsed.into_first().unwrap();
+        assert!(frame.blocks.is_empty());
+        assert_eq!(frame.safety.unwrap().reason.as_deref(), Some("PROHIBITED_CONTENT"));
+    }
+
+    #[test]
+    fn test_parse_code_execution() {
+        let response = r#"{
+            "responseId": "abc",
+            "modelVersion": "gemini-2.5-flash",
+            "candidates": [{"content": {"parts": [
+                {"executableCode": {"language": "PYTHON", "code": "print(2 + 2)"}},
+                {"codeExecutionResult": {"outcome": "OUTCOME_OK", "output": "4\n"}},
+                {"text": "The answer is 4."}
+            ]}}]
+        }"#;
+
+        let frame = GeminiParser.parse(response).unwrap();
+        assert_eq!(frame.blocks.len(), 3);
+        match (&frame.blocks[0], &frame.blocks[1]) {
+            (
+                ContentBlock::ServerToolUse { id, input, .. },
+                ContentBlock::ServerToolResult { tool_use_id, content, is_error },
+            ) => {
+                assert_eq!(id, tool_use_id);
+                assert_eq!(input["code"], "print(2 + 2)");
+                assert_eq!(content["output"], "4\n");
+                assert!(!is_error);
+            }
+            _ => panic!("Expected server tool blocks"),
+        }
+    }
+}
@@ -0,0 +1,346 @@
+//! Parser for Mistral's native Chat Completions API
+
+use serde_json::Value;
+
+use super::{
+    array_field, decode_arguments, index_field, matches_prefix, optional_str, required_str,
+    usage_from,
+};
+use crate::normalized::{Candidate, ContentBlock, ContentFrame, ParsedResponse};
+use crate::parser::{ModelResponseParser, ParseError};
+
+const TOOL_CALLS: &str = "[TOOL_CALLS]";
+const ARGS: &str = "[ARGS]";
+const CALL_ID: &str = "[CALL_ID]";
+
+/// Parser for Mistral `/v1/chat/completions` responses
+///
+/// Message content may be a string or a list of `text` and `thinking` chunks.
+/// Besides structured `tool_calls`, tool calls that open-weight Mistral models
+/// emit as raw text are recognized in both the `[TOOL_CALLS][{...}]` JSON form
+/// and the `[TOOL_CALLS]name[ARGS]{...}` form. Calls without an id are given
+/// Mistral-style 9-character ids (`call00000`, `call00001`, ...).
+///
+/// [`parse`](ModelResponseParser::parse) normalizes the first choice; use
+/// [`parse_all`](ModelResponseParser::parse_all) to get every choice.
+///
+/// # Examples
+///
+/// ```
+/// use adaptogen::parser::ModelResponseParser;
+/// use adaptogen::providers::MistralParser;
+///
+/// let response = r#"{
+///     "id": "cmpl-1",
+///     "model": "mistral-large-latest",
+///     "choices": [{"index": 0, "message": {"role": "assistant", "content": "Bonjour!"}, "finish_reason": "stop"}]
+/// }"#;
+///
+/// let frame = MistralParser.parse(response).unwrap();
+/// assert_eq!(frame.blocks.len(), 1);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MistralParser;
+
+impl ModelResponseParser for MistralParser {
+    /// Model name prefixes handled by this parser
+    fn supported_models(&self) -> Vec<String> {
+        [
+            "mistral-",
+            "magistral-",
+            "codestral-",
+            "devstral-",
+            "ministral-",
+            "pixtral-",
+            "open-mistral",
+            "open-mixtral",
+            "open-codestral",
+        ]
+        .iter()
+        .map(|m| m.to_string())
+        .collect()
+    }
+
+    fn parse(&self, raw_response: &str) -> Result<ContentFrame, ParseError> {
+        let json: Value = serde_json::from_str(raw_response)?;
+        parse_choice(&json, array_field(&json, "choices").first())
+    }
+
+    fn can_handle(&self, model: &str) -> bool {
+        matches_prefix(&self.supported_models(), model)
+    }
+
+    fn parse_all(&self, raw_response: &str) -> Result<ParsedResponse, ParseError> {
+        let json: Value = serde_json::from_str(raw_response)?;
+
+        let candidates = array_field(&json, "choices")
+            .iter()
+            .enumerate()
+            .map(|(position, choice)| {
+                Ok(Candidate {
+                    index: index_field(choice, "index").unwrap_or(position),
+                    frame: parse_choice(&json, Some(choice))?,
+                    logprobs: None,
+                })
+            })
+            .collect::<Result<_, ParseError>>()?;
+
+        Ok(ParsedResponse { candidates })
+    }
+
+    fn parse_all_with_model(
+        &self,
+        raw_response: &str,
+        _model: &str,
+    ) -> Result<ParsedResponse, ParseError> {
+        self.parse_all(raw_response)
+    }
+}
+
+/// Build the frame for one choice; a missing choice yields a frame without blocks
+fn parse_choice(json: &Value, choice: Option<&Value>) -> Result<ContentFrame, ParseError> {
+    let id = required_str(json, "id")?;
+    let model = required_str(json, "model")?;
+
+    let blocks = choice
+        .and_then(|choice| choice.get("message"))
+        .map(parse_message)
+        .unwrap_or_default();
+
+    Ok(ContentFrame {
+        id,
+        model,
+        blocks,
+        stop_reason: choice.and_then(|choice| optional_str(choice, "finish_reason")),
+        usage: usage_from(json.get("usage"), "prompt_tokens", "completion_tokens"),
+        safety: None,
+        ..Default::default()
+    })
+}
+
+fn parse_message(message: &Value) -> Vec<ContentBlock> {
+    let mut blocks = Vec::new();
+    let mut text = String::new();
+
+    match message.get("content") {
+        Some(Value::String(content)) => text.push_str(content),
+        Some(Value::Array(chunks)) => {
+            for chunk in chunks {
+                match chunk.get("type").and_then(|t| t.as_str()) {
+                    Some("text") => {
+                        if let Some(t) = chunk.get("text").and_then(|t| t.as_str()) {
+                            text.push_str(t);
+                        }
+                    }
+                    Some("thinking") => {
+                        let thinking = thinking_text(chunk.get("thinking"));
+                        if !thinking.is_empty() {
+                            blocks.push(ContentBlock::Thinking {
+                                thinking: Some(thinking),
+                                signature: None,
+                            });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        _ => {}
+    }
+
+    let mut tool_calls = Vec::new();
+    if let Some(marker) = text.find(TOOL_CALLS) {
+        tool_calls = parse_text_tool_calls(&text[marker..]);
+        text.truncate(marker);
+    }
+
+    let text = text.trim();
+    if !text.is_empty() {
+        blocks.push(ContentBlock::text(text));
+    }
+
+    for tool_call in message
+        .get("tool_calls")
+        .and_then(|t| t.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let function = tool_call.get("function");
+        let Some(name) = function.and_then(|f| optional_str(f, "name")) else {
+            continue;
+        };
+        tool_calls.push(RawToolCall {
+            id: optional_str(tool_call, "id"),
+            name,
+            arguments: function.and_then(|f| f.get("arguments")).cloned(),
+        });
+    }
+
+    blocks.extend(
+        tool_calls
+            .into_iter()
+            .enumerate()
+            .map(|(index, call)| ContentBlock::ToolUse {
+                id: call.id.unwrap_or_else(|| format!("call{index:05}")),
+                name: call.name,
+                input: decode_arguments(call.arguments.as_ref()),
+            }),
+    );
+
+    blocks
+}
+
+/// Thinking chunks hold either a string or a list of text chunks
+fn thinking_text(thinking: Option<&Value>) -> String {
+    match thinking {
+        Some(Value::String(thinking)) => thinking.trim().to_string(),
+        Some(Value::Array(chunks)) => chunks
+            .iter()
+            .filter_map(|c| c.get("text").and_then(|t| t.as_str()))
+            .collect::<String>()
+            .trim()
+            .to_string(),
+        _ => String::new(),
+    }
+}
+
+struct RawToolCall {
+    id: Option<String>,
+    name: String,
+    arguments: Option<Value>,
+}
+
+/// Parse tool calls emitted as text, starting at the first `[TOOL_CALLS]` marker
+fn parse_text_tool_calls(text: &str) -> Vec<RawToolCall> {
+    let body = text[TOOL_CALLS.len()..].trim_start();
+
+    // Older format: [TOOL_CALLS][{"name": ..., "arguments": ...}, ...]
+    if body.starts_with('[') {
+        let mut calls = serde_json::Deserializer::from_str(body).into_iter::<Vec<Value>>();
+        let Some(Ok(calls)) = calls.next() else {
+            return Vec::new();
+        };
+        return calls
+            .iter()
+            .filter_map(|call| {
+                Some(RawToolCall {
+                    id: optional_str(call, "id"),
+                    name: optional_str(call, "name")?,
+                    arguments: call.get("arguments").cloned(),
+                })
+            })
+            .collect();
+    }
+
+    // Newer format: [TOOL_CALLS]name[CALL_ID]id[ARGS]{...}, repeated per call
+    text.split(TOOL_CALLS)
+        .filter_map(|segment| {
+            let (head, arguments) = segment.split_once(ARGS)?;
+            let (name, id) = match head.split_once(CALL_ID) {
+                Some((name, id)) => (name, Some(id.trim().to_string())),
+                None => (head, None),
+            };
+            Some(RawToolCall {
+                id,
+                name: name.trim().to_string(),
+                arguments: Some(Value::String(arguments.trim().to_string())),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response_with_message(message: &str) -> String {
+        format!(
+            r#"{{"id": "cmpl-1", "model": "magistral-medium-latest", "choices": [{{"index": 0, "message": {message}, "finish_reason": "stop"}}], "usage": {{"prompt_tokens": 10, "completion_tokens": 20}}}}"#
+        )
+    }
+
+    #[test]
+    fn test_parse_thinking_chunks() {
+        let response = response_with_message(
+            r#"{"role": "assistant", "content": [
+                {"type": "thinking", "thinking": [{"type": "text", "text": "Working it out"}]},
+                {"type": "text", "text": "The answer is 4."}
+            ]}"#,
+        );
+
+        let frame = MistralParser.parse(&response).unwrap();
+        assert_eq!(frame.usage.unwrap().output_tokens, 20);
+        assert_eq!(frame.blocks.len(), 2);
+        match &frame.blocks[0] {
+            ContentBlock::Thinking { thinking, .. } => {
+                assert_eq!(thinking.as_deref(), Some("Working it out"))
+            }
+            _ => panic!("Expected Thinking block"),
+        }
+    }
+
+    #[test]
+    fn test_parse_structured_tool_calls() {
+        let response = response_with_message(
+            r#"{"role": "assistant", "content": "", "tool_calls": [{"id": "D681PevKs", "function": {"name": "get_weather", "arguments": "{\"city\": \"Paris\"}"}}]}"#,
+        );
+
+        let frame = MistralParser.parse(&response).unwrap();
+        assert_eq!(frame.blocks.len(), 1);
+        match &frame.blocks[0] {
+            ContentBlock::ToolUse { id, input, .. } => {
+                assert_eq!(id, "D681PevKs");
+                assert_eq!(input["city"], "Paris");
+            }
+            _ => panic!("Expected ToolUse block"),
+        }
+    }
+
+    #[test]
+    fn test_parse_json_text_tool_calls() {
+        let response = response_with_message(
+            r#"{"role": "assistant", "content": "Let me check. [TOOL_CALLS][{\"name\": \"get_weather\", \"arguments\": {\"city\": \"Paris\"}}, {\"name\": \"get_time\", \"arguments\": {}}]"}"#,
+        );
+
+        let frame = MistralParser.parse(&response).unwrap();
+        assert_eq!(frame.blocks.len(), 3);
+        match &frame.blocks[0] {
+            ContentBlock::Text { text, .. } => assert_eq!(text, "Let me check."),
+            _ => panic!("Expected Text block"),
+        }
+        match (&frame.blocks[1], &frame.blocks[2]) {
+            (
+                ContentBlock::ToolUse {
+                    id: first, input, ..
+                },
+                ContentBlock::ToolUse {
+                    id: second, name, ..
+                },
+            ) => {
+                assert_eq!(first, "call00000");
+                assert_eq!(input["city"], "Paris");
+                assert_eq!(second, "call00001");
+                assert_eq!(name, "get_time");
+            }
+            _ => panic!("Expected ToolUse blocks"),
+        }
+    }
+
+    #[test]
+    fn test_parse_args_text_tool_calls() {
+        let response = response_with_message(
+            r#"{"role": "assistant", "content": "[TOOL_CALLS]get_weather[ARGS]{\"city\": \"Paris\"}[TOOL_CALLS]get_time[CALL_ID]a1b2c3d4e[ARGS]{}"}"#,
+        );
+
+        let frame = MistralParser.parse(&response).unwrap();
+        assert_eq!(frame.blocks.len(), 2);
+        match (&frame.blocks[0], &frame.blocks[1]) {
+            (ContentBlock::ToolUse { name, input, .. }, ContentBlock::ToolUse { id, .. }) => {
+                assert_eq!(name, "get_weather");
+                assert_eq!(input["city"], "Paris");
+                assert_eq!(id, "a1b2c3d4e");
+            }
+            _ => panic!("Expected ToolUse blocks"),
+        }
+    }
+}
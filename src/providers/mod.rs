@@ -0,0 +1,181 @@
+//! Built-in parsers for common LLM providers
+//!
+//! Each provider lives in its own module behind a cargo feature of the same
+//! name, so only the parsers you need are compiled. All of them are enabled by
+//! default.
+//!
+//! Built-in parsers match models by prefix rather than by exact name, so a
+//! single parser covers a whole model family (for example every model whose
+//! name starts with `claude`).
+
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+
+use crate::normalized::Usage;
+use crate::parser::ModelResponseParser;
+
+mod error;
+
+// Each shared helper is gated on the providers that use it
+#[cfg(any(
+    feature = "anthropic",
+    feature = "cohere",
+    feature = "gemini",
+    feature = "mistral",
+    feature = "openai"
+))]
+mod shared;
+
+#[cfg(feature = "anthropic")]
+pub mod anthropic;
+#[cfg(feature = "bedrock")]
+pub mod bedrock;
+#[cfg(feature = "cohere")]
+pub mod cohere;
+#[cfg(feature = "gemini")]
+pub mod gemini;
+#[cfg(feature = "mistral")]
+pub mod mistral;
+#[cfg(feature = "ollama")]
+pub mod ollama;
+#[cfg(feature = "openai")]
+pub mod openai;
+#[cfg(feature = "openai")]
+pub mod openai_responses;
+
+pub use error::detect_provider_error;
+#[cfg(any(
+    feature = "anthropic",
+    feature = "cohere",
+    feature = "gemini",
+    feature = "mistral",
+    feature = "openai"
+))]
+pub(crate) use shared::*;
+
+#[cfg(feature = "anthropic")]
+pub use anthropic::AnthropicParser;
+#[cfg(feature = "bedrock")]
+pub use bedrock::{BedrockConverseParser, BedrockInvokeParser};
+#[cfg(feature = "cohere")]
+pub use cohere::CohereParser;
+#[cfg(feature = "gemini")]
+pub use gemini::GeminiParser;
+#[cfg(feature = "mistral")]
+pub use mistral::MistralParser;
+#[cfg(feature = "ollama")]
+pub use ollama::OllamaParser;
+#[cfg(feature = "openai")]
+pub use openai::OpenAiParser;
+#[cfg(feature = "openai")]
+pub use openai_responses::OpenAiResponsesParser;
+
+/// Instances of every built-in parser enabled by cargo features
+///
+/// This is the set of parsers registered by
+/// [`ParserRegistry::with_defaults`](crate::registry::ParserRegistry::with_defaults).
+#[allow(unused_mut, clippy::vec_init_then_push)]
+pub fn default_parsers() -> Vec<Arc<dyn ModelResponseParser>> {
+    let mut parsers: Vec<Arc<dyn ModelResponseParser>> = Vec::new();
+    #[cfg(feature = "anthropic")]
+    parsers.push(Arc::new(AnthropicParser));
+    #[cfg(feature = "openai")]
+    parsers.push(Arc::new(OpenAiParser));
+    #[cfg(feature = "openai")]
+    parsers.push(Arc::new(OpenAiResponsesParser));
+    #[cfg(feature = "gemini")]
+    parsers.push(Arc::new(GeminiParser));
+    #[cfg(feature = "bedrock")]
+    parsers.push(Arc::new(BedrockConverseParser));
+    #[cfg(feature = "bedrock")]
+    parsers.push(Arc::new(BedrockInvokeParser));
+    #[cfg(feature = "cohere")]
+    parsers.push(Arc::new(CohereParser));
+    #[cfg(feature = "mistral")]
+    parsers.push(Arc::new(MistralParser));
+    #[cfg(feature = "ollama")]
+    parsers.push(Arc::new(OllamaParser));
+    parsers
+}
+
+/// Extract an optional string field from a JSON object
+pub(crate) fn optional_str(json: &Value, field: &str) -> Option<String> {
+    json.get(field).and_then(|v| v.as_str()).map(|s| s.to_string())
+}
+
+/// Read token counts from a provider usage object
+///
+/// Returns `None` when the object is absent or reports neither count.
+pub(crate) fn usage_from(usage: Option<&Value>, input: &str, output: &str) -> Option<Usage> {
+    let usage = usage?;
+    let input_tokens = usage.get(input).and_then(|v| v.as_u64());
+    let output_tokens = usage.get(output).and_then(|v| v.as_u64());
+    if input_tokens.is_none() && output_tokens.is_none() {
+        return None;
+    }
+
+    Some(Usage {
+        input_tokens: input_tokens.unwrap_or(0),
+        output_tokens: output_tokens.unwrap_or(0),
+    })
+}
+
+/// Decode tool call arguments
+///
+/// Arguments sent as a JSON-encoded string are decoded; strings that are not
+/// valid JSON are kept as `{"raw": "<arguments>"}`. Missing arguments become an
+/// empty object.
+pub(crate) fn decode_arguments(arguments: Option<&Value>) -> Value {
+    match arguments {
+        Some(Value::String(args)) => {
+            serde_json::from_str(args).unwrap_or_else(|_| json!({ "raw": args }))
+        }
+        Some(args) => args.clone(),
+        None => json!({}),
+    }
+}
+
+/// Split a leading tagged thinking section (such as `<think>...</think>`) from content
+///
+/// Some deployments put the opening tag in the prompt, so content that only
+/// contains the closing tag is treated as starting inside the thinking section.
+/// Returns the trimmed thinking (if any) and the trimmed remaining text.
+pub(crate) fn split_thinking(content: &str, open: &str, close: &str) -> (Option<String>, String) {
+    let Some(end) = content.find(close) else {
+        return (None, content.trim().to_string());
+    };
+
+    let before = content[..end].trim_start();
+    let thinking = before.strip_prefix(open).unwrap_or(before).trim();
+    let text = content[end + close.len()..].trim();
+
+    let thinking = (!thinking.is_empty()).then(|| thinking.to_string());
+    (thinking, text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_thinking() {
+        let split = |content| split_thinking(content, "<think>", "</think>");
+
+        assert_eq!(split("Hello"), (None, "Hello".to_string()));
+        assert_eq!(
+            split("<think>hmm</think> Hi"),
+            (Some("hmm".to_string()), "Hi".to_string())
+        );
+        assert_eq!(split("hmm</think>Hi"), (Some("hmm".to_string()), "Hi".to_string()));
+        assert_eq!(split("<think></think>Hi"), (None, "Hi".to_string()));
+    }
+
+    #[test]
+    fn test_decode_arguments() {
+        assert_eq!(decode_arguments(Some(&json!("{\"a\": 1}"))), json!({"a": 1}));
+        assert_eq!(decode_arguments(Some(&json!("{oops"))), json!({"raw": "{oops"}));
+        assert_eq!(decode_arguments(Some(&json!({"a": 1}))), json!({"a": 1}));
+        assert_eq!(decode_arguments(None), json!({}));
+    }
+}
@@ -0,0 +1,170 @@
+//! Parser for Ollama's native `/api/chat` API
+
+use serde_json::Value;
+
+use super::{decode_arguments, optional_str, usage_from};
+use crate::normalized::{ContentBlock, ContentFrame};
+use crate::parser::{ModelResponseParser, ParseError};
+
+/// Parser for Ollama `/api/chat` responses
+///
+/// Ollama serves models from every family under their own names (`qwen3:8b`,
+/// `llama3.2`, ...), so this parser claims no model names and opts into
+/// [`routes_by_shape`](ModelResponseParser::routes_by_shape) instead: it is
+/// selected for any model whose response has a `message` object alongside a
+/// `done` flag.
+///
+/// Ollama responses carry no id, so the frame id is empty. Tool calls are
+/// given sequential ids (`call_0`, `call_1`, ...).
+///
+/// # Examples
+///
+/// ```
+/// use adaptogen::parser::ModelResponseParser;
+/// use adaptogen::providers::OllamaParser;
+///
+/// let response = r#"{
+///     "model": "qwen3:8b",
+///     "message": {"role": "assistant", "content": "Hello!", "thinking": "Greet the user."},
+///     "done": true,
+///     "done_reason": "stop"
+/// }"#;
+///
+/// let frame = OllamaParser.parse(response).unwrap();
+/// assert_eq!(frame.blocks.len(), 2);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OllamaParser;
+
+impl ModelResponseParser for OllamaParser {
+    fn supported_models(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn parse(&self, raw_response: &str) -> Result<ContentFrame, ParseError> {
+        let json: Value = serde_json::from_str(raw_response)?;
+        let model = optional_str(&json, "model").unwrap_or_default();
+        parse_response(&json, model)
+    }
+
+    fn can_handle(&self, _model: &str) -> bool {
+        false
+    }
+
+    fn can_handle_response(&self, response: &Value) -> bool {
+        response.get("message").is_some_and(|m| m.is_object())
+            && response.get("done").is_some_and(|d| d.is_boolean())
+    }
+
+    fn routes_by_shape(&self) -> bool {
+        true
+    }
+
+    fn parse_with_model(
+        &self,
+        raw_response: &str,
+        model: &str,
+    ) -> Result<ContentFrame, ParseError> {
+        let json: Value = serde_json::from_str(raw_response)?;
+        parse_response(&json, model.to_string())
+    }
+}
+
+fn parse_response(json: &Value, model: String) -> Result<ContentFrame, ParseError> {
+    let message = json
+        .get("message")
+        .ok_or_else(|| ParseError::MissingField("message".to_string()))?;
+
+    let mut blocks = Vec::new();
+
+    if let Some(thinking) = message.get("thinking").and_then(|t| t.as_str()) {
+        if !thinking.is_empty() {
+            blocks.push(ContentBlock::Thinking {
+                thinking: Some(thinking.to_string()),
+                signature: None,
+            });
+        }
+    }
+
+    if let Some(content) = message.get("content").and_then(|c| c.as_str()) {
+        if !content.is_empty() {
+            blocks.push(ContentBlock::text(content));
+        }
+    }
+
+    let tool_calls = message.get("tool_calls").and_then(|t| t.as_array());
+    for (index, tool_call) in tool_calls.into_iter().flatten().enumerate() {
+        let function = tool_call.get("function");
+        let Some(name) = function.and_then(|f| optional_str(f, "name")) else {
+            continue;
+        };
+        blocks.push(ContentBlock::ToolUse {
+            id: optional_str(tool_call, "id").unwrap_or_else(|| format!("call_{index}")),
+            name,
+            input: decode_arguments(function.and_then(|f| f.get("arguments"))),
+        });
+    }
+
+    Ok(ContentFrame {
+        id: String::new(),
+        model,
+        blocks,
+        stop_reason: optional_str(json, "done_reason"),
+        usage: usage_from(Some(json), "prompt_eval_count", "eval_count"),
+        safety: None,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_thinking_and_usage() {
+        let response = r#"{
+            "model": "qwen3:8b",
+            "created_at": "2025-06-01T12:00:00Z",
+            "message": {"role": "assistant", "content": "4", "thinking": "2 + 2 = 4"},
+            "done": true,
+            "done_reason": "stop",
+            "prompt_eval_count": 12,
+            "eval_count": 34
+        }"#;
+
+        let frame = OllamaParser.parse(response).unwrap();
+        assert_eq!(frame.id, "");
+        assert_eq!(frame.model, "qwen3:8b");
+        assert_eq!(frame.stop_reason.as_deref(), Some("stop"));
+        assert_eq!(frame.usage.unwrap().total_tokens(), 46);
+        assert!(matches!(frame.blocks[0], ContentBlock::Thinking { .. }));
+        match &frame.blocks[1] {
+            ContentBlock::Text { text, .. } => assert_eq!(text, "4"),
+            _ => panic!("Expected Text block"),
+        }
+    }
+
+    #[test]
+    fn test_parse_tool_calls() {
+        let response = r#"{
+            "model": "llama3.2",
+            "message": {
+                "role": "assistant",
+                "content": "",
+                "tool_calls": [{"function": {"name": "get_weather", "arguments": {"city": "Paris"}}}]
+            },
+            "done": true
+        }"#;
+
+        let frame = OllamaParser.parse(response).unwrap();
+        assert_eq!(frame.blocks.len(), 1);
+        match &frame.blocks[0] {
+            ContentBlock::ToolUse { id, name, input } => {
+                assert_eq!(id, "call_0");
+                assert_eq!(name, "get_weather");
+                assert_eq!(input["city"], "Paris");
+            }
+            _ => panic!("Expected ToolUse block"),
+        }
+    }
+}
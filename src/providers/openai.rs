@@ -0,0 +1,386 @@
+//! Parser for the OpenAI Chat Completions API and compatible endpoints
+
+use serde_json::Value;
+
+use super::{
+    array_field, decode_arguments, index_field, logprobs_in, matches_prefix, openai_logprobs,
+    optional_str, required_str, split_thinking, usage_from,
+};
+use crate::normalized::{
+    Candidate, ContentBlock, ContentFrame, ParsedResponse, SafetyRating, SafetyReport,
+    TokenLogprob,
+};
+use crate::parser::{ModelResponseParser, ParseError};
+
+const THINK_OPEN: &str = "<think>";
+const THINK_CLOSE: &str = "</think>";
+
+/// Parser for OpenAI Chat Completions responses
+///
+/// Besides OpenAI's own models this covers OpenAI-compatible endpoints such as
+/// Fireworks, DeepSeek and Qwen deployments. Reasoning is read from either a
+/// `reasoning_content` / `reasoning` field on the message or from a leading
+/// `<think>...</think>` section of the content. Tool call arguments are decoded
+/// as JSON; arguments that are not valid JSON are kept as `{"raw": "<arguments>"}`.
+///
+/// [`parse`](ModelResponseParser::parse) normalizes the first choice; use
+/// [`parse_all`](ModelResponseParser::parse_all) to get every choice of an
+/// `n > 1` request along with its raw `logprobs`. Requested logprobs are also
+/// normalized onto the text block, aligned to the text after any `<think>`
+/// section is split off.
+///
+/// # Examples
+///
+/// ```
+/// use adaptogen::parser::ModelResponseParser;
+/// use adaptogen::providers::OpenAiParser;
+///
+/// let response = r#"{
+///     "id": "chatcmpl-123",
+///     "model": "gpt-4o",
+///     "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hello!"}}]
+/// }"#;
+///
+/// let frame = OpenAiParser.parse(response).unwrap();
+/// assert_eq!(frame.blocks.len(), 1);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OpenAiParser;
+
+impl ModelResponseParser for OpenAiParser {
+    /// Model name prefixes handled by this parser
+    fn supported_models(&self) -> Vec<String> {
+        [
+            "gpt-",
+            "chatgpt-",
+            "o1",
+            "o3",
+            "o4",
+            "qwen",
+            "deepseek",
+            "accounts/fireworks/models/",
+        ]
+        .iter()
+        .map(|m| m.to_string())
+        .collect()
+    }
+
+    fn parse(&self, raw_response: &str) -> Result<ContentFrame, ParseError> {
+        let json: Value = serde_json::from_str(raw_response)?;
+        parse_choice(&json, array_field(&json, "choices").first())
+    }
+
+    fn can_handle(&self, model: &str) -> bool {
+        matches_prefix(&self.supported_models(), model)
+    }
+
+    fn parse_all(&self, raw_response: &str) -> Result<ParsedResponse, ParseError> {
+        let json: Value = serde_json::from_str(raw_response)?;
+
+        let candidates = array_field(&json, "choices")
+            .iter()
+            .enumerate()
+            .map(|(position, choice)| {
+                Ok(Candidate {
+                    index: index_field(choice, "index").unwrap_or(position),
+                    frame: parse_choice(&json, Some(choice))?,
+                    logprobs: choice.get("logprobs").filter(|l| !l.is_null()).cloned(),
+                })
+            })
+            .collect::<Result<_, ParseError>>()?;
+
+        Ok(ParsedResponse { candidates })
+    }
+
+    fn parse_all_with_model(
+        &self,
+        raw_response: &str,
+        _model: &str,
+    ) -> Result<ParsedResponse, ParseError> {
+        self.parse_all(raw_response)
+    }
+}
+
+/// Build the frame for one choice; a missing choice yields a frame without blocks
+fn parse_choice(json: &Value, choice: Option<&Value>) -> Result<ContentFrame, ParseError> {
+    let id = required_str(json, "id")?;
+    let model = required_str(json, "model")?;
+
+    let logprobs = openai_logprobs(choice.and_then(|choice| choice.get("logprobs")));
+    let blocks = choice
+        .and_then(|choice| choice.get("message"))
+        .map(|message| parse_message(message, &logprobs))
+        .unwrap_or_default();
+
+    Ok(ContentFrame {
+        id,
+        model,
+        blocks,
+        stop_reason: choice.and_then(|choice| optional_str(choice, "finish_reason")),
+        usage: usage_from(json.get("usage"), "prompt_tokens", "completion_tokens"),
+        safety: parse_safety(json, choice),
+        ..Default::default()
+    })
+}
+
+/// Collect refusals and Azure OpenAI content filter results
+fn parse_safety(json: &Value, choice: Option<&Value>) -> Option<SafetyReport> {
+    let refused = choice
+        .and_then(|choice| choice.pointer("/message/refusal"))
+        .is_some_and(|refusal| refusal.is_string());
+    let filtered = choice.and_then(|choice| optional_str(choice, "finish_reason"))
+        == Some("content_filter".to_string());
+
+    let mut ratings = Vec::new();
+    for prompt in array_field(json, "prompt_filter_results") {
+        ratings.extend(filter_ratings(prompt.get("content_filter_results"), true));
+    }
+    ratings.extend(filter_ratings(
+        choice.and_then(|choice| choice.get("content_filter_results")),
+        false,
+    ));
+
+    if !refused && !filtered && ratings.is_empty() {
+        return None;
+    }
+
+    let reason = if refused {
+        Some("refusal".to_string())
+    } else if filtered {
+        Some("content_filter".to_string())
+    } else {
+        None
+    };
+
+    Some(SafetyReport {
+        blocked: refused || filtered,
+        reason,
+        ratings,
+    })
+}
+
+/// Azure reports `{"<category>": {"filtered": bool, "severity": "..."}}`
+fn filter_ratings(results: Option<&Value>, prompt: bool) -> Vec<SafetyRating> {
+    results
+        .and_then(|r| r.as_object())
+        .into_iter()
+        .flatten()
+        .filter(|(_, result)| result.is_object())
+        .map(|(category, result)| SafetyRating {
+            category: category.clone(),
+            severity: optional_str(result, "severity"),
+            filtered: result.get("filtered").and_then(|f| f.as_bool()) == Some(true),
+            prompt,
+        })
+        .collect()
+}
+
+/// Normalize an assistant message into thinking, refusal, text and tool use blocks
+///
+/// `logprobs` covers the message content and is attached to the text block.
+fn parse_message(message: &Value, logprobs: &[TokenLogprob]) -> Vec<ContentBlock> {
+    let mut blocks = Vec::new();
+
+    let reasoning = message
+        .get("reasoning_content")
+        .or_else(|| message.get("reasoning"))
+        .and_then(|r| r.as_str())
+        .filter(|r| !r.trim().is_empty());
+    if let Some(reasoning) = reasoning {
+        blocks.push(ContentBlock::Thinking {
+            thinking: Some(reasoning.trim().to_string()),
+            signature: None,
+        });
+    }
+
+    if let Some(refusal) = message.get("refusal").and_then(|r| r.as_str()) {
+        blocks.push(ContentBlock::Refusal {
+            refusal: refusal.to_string(),
+        });
+    }
+
+    if let Some(content) = message_text(message.get("content")) {
+        let (thinking, text) = split_thinking(&content, THINK_OPEN, THINK_CLOSE);
+        if let Some(thinking) = thinking {
+            blocks.push(ContentBlock::Thinking {
+                thinking: Some(thinking),
+                signature: None,
+            });
+        }
+        if !text.is_empty() {
+            // The text is the trimmed end of the content
+            let start = content.trim_end().len() - text.len();
+            let logprobs = logprobs_in(logprobs, start..start + text.len());
+            blocks.push(ContentBlock::Text {
+                text,
+                citations: Vec::new(),
+                logprobs,
+            });
+        }
+    }
+
+    if let Some(tool_calls) = message.get("tool_calls").and_then(|t| t.as_array()) {
+        blocks.extend(tool_calls.iter().filter_map(parse_tool_call));
+    }
+
+    blocks
+}
+
+/// Message content is either a string or a list of content parts
+fn message_text(content: Option<&Value>) -> Option<String> {
+    match content? {
+        Value::String(text) => Some(text.clone()),
+        Value::Array(parts) => Some(
+            parts
+                .iter()
+                .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+fn parse_tool_call(tool_call: &Value) -> Option<ContentBlock> {
+    let function = tool_call.get("function")?;
+    let id = tool_call.get("id").and_then(|i| i.as_str())?;
+    let name = function.get("name").and_then(|n| n.as_str())?;
+    Some(ContentBlock::ToolUse {
+        id: id.to_string(),
+        name: name.to_string(),
+        input: decode_arguments(function.get("arguments")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_can_handle_by_prefix() {
+        assert!(OpenAiParser.can_handle("gpt-4o-mini"));
+        assert!(OpenAiParser.can_handle("o3-mini"));
+        assert!(OpenAiParser.can_handle("accounts/fireworks/models/qwen3-30b-a3b"));
+        assert!(!OpenAiParser.can_handle("claude-3-opus"));
+    }
+
+    #[test]
+    fn test_parse_think_tags_and_tool_calls() {
+        let response = r#"{"id":"example-id","object":"chat.completion","model":"accounts/fireworks/models/qwen3-30b-a3b","choices":[{"index":0,"message":{"role":"assistant","content":"<think>\nThe user wants the capital of France.\n</think>\n\n","tool_calls":[{"index":0,"id":"call_1","type":"function","function":{"name":"search_capital","arguments":"{\"country\": \"France\"}"}}]},"finish_reason":"tool_calls"}],"usage":{"prompt_tokens":172,"total_tokens":290,"completion_tokens":118}}"#;
+
+        let frame = OpenAiParser.parse(response).unwrap();
+        assert_eq!(frame.blocks.len(), 2);
+        assert_eq!(frame.stop_reason.as_deref(), Some("tool_calls"));
+        assert_eq!(frame.usage.unwrap().total_tokens(), 290);
+
+        match &frame.blocks[0] {
+            ContentBlock::Thinking { thinking, .. } => {
+                assert_eq!(thinking.as_deref(), Some("The user wants the capital of France."));
+            }
+            _ => panic!("Expected Thinking block"),
+        }
+        match &frame.blocks[1] {
+            ContentBlock::ToolUse { id, name, input } => {
+                assert_eq!(id, "call_1");
+                assert_eq!(name, "search_capital");
+                assert_eq!(input["country"], "France");
+            }
+            _ => panic!("Expected ToolUse block"),
+        }
+    }
+
+    #[test]
+    fn test_parse_reasoning_content() {
+        let response = r#"{"id":"1","model":"deepseek-reasoner","choices":[{"message":{"content":"Paris","reasoning_content":"France's capital is Paris."}}]}"#;
+
+        let frame = OpenAiParser.parse(response).unwrap();
+        assert_eq!(frame.blocks.len(), 2);
+        assert!(matches!(frame.blocks[0], ContentBlock::Thinking { .. }));
+        match &frame.blocks[1] {
+            ContentBlock::Text { text, .. } => assert_eq!(text, "Paris"),
+            _ => panic!("Expected Text block"),
+        }
+    }
+
+    #[test]
+    fn test_invalid_tool_arguments_kept_raw() {
+        let response = r#"{"id":"1","model":"gpt-4o","choices":[{"message":{"content":null,"tool_calls":[{"id":"call_1","type":"function","function":{"name":"f","arguments":"{not json"}}]}}]}"#;
+
+        let frame = OpenAiParser.parse(response).unwrap();
+        match &frame.blocks[0] {
+            ContentBlock::ToolUse { input, .. } => assert_eq!(input["raw"], "{not json"),
+            _ => panic!("Expected ToolUse block"),
+        }
+    }
+
+    #[test]
+    fn test_parse_all_choices() {
+        let response = r#"{"id":"1","model":"gpt-4o","choices":[
+            {"index":0,"message":{"content":"Paris"},"finish_reason":"stop","logprobs":null},
+            {"index":1,"message":{"content":"Lyon"},"finish_reason":"length","logprobs":{"content":[]}}
+        ],"usage":{"prompt_tokens":5,"completion_tokens":2}}"#;
+
+        let response = OpenAiParser.parse_all(response).unwrap();
+        assert_eq!(response.len(), 2);
+
+        let second = &response.candidates[1];
+        assert_eq!(second.index, 1);
+        assert_eq!(second.frame.stop_reason.as_deref(), Some("length"));
+        assert!(second.logprobs.is_some());
+        assert!(response.candidates[0].logprobs.is_none());
+        match &second.frame.blocks[0] {
+            ContentBlock::Text { text, .. } => assert_eq!(text, "Lyon"),
+            _ => panic!("Expected Text block"),
+        }
+    }
+
+    #[test]
+    fn test_logprobs_aligned_to_text() {
+        let response = r#"{"id":"1","model":"qwen3-8b","choices":[{"message":{"content":"<think>ok</think>\nHi there"},"logprobs":{"content":[
+            {"token":"<think>","logprob":-0.1},
+            {"token":"ok","logprob":-0.2},
+            {"token":"</think>","logprob":-0.1},
+            {"token":"\nHi","logprob":-0.3,"top_logprobs":[{"token":"\nHi","logprob":-0.3}]},
+            {"token":" there","logprob":-0.4}
+        ]}}]}"#;
+
+        let frame = OpenAiParser.parse(response).unwrap();
+        match &frame.blocks[1] {
+            ContentBlock::Text { text, logprobs, .. } => {
+                assert_eq!(text, "Hi there");
+                assert_eq!(logprobs.len(), 2);
+                assert_eq!(logprobs[0].token, "\nHi");
+                assert_eq!(logprobs[0].offset, 0);
+                assert_eq!(logprobs[1].offset, 2);
+                assert_eq!(&text[logprobs[1].offset..], " there");
+            }
+            _ => panic!("Expected Text block"),
+        }
+    }
+
+    #[test]
+    fn test_refusal_and_content_filter() {
+        let response = r#"{"id":"1","model":"gpt-4o","choices":[{"message":{"content":null,"refusal":"I can't help with that."},"finish_reason":"stop"}]}"#;
+
+        let frame = OpenAiParser.parse(response).unwrap();
+        match &frame.blocks[0] {
+            ContentBlock::Refusal { refusal } => assert_eq!(refusal, "I can't help with that."),
+            _ => panic!("Expected Refusal block"),
+        }
+        let safety = frame.safety.unwrap();
+        assert!(safety.blocked);
+        assert_eq!(safety.reason.as_deref(), Some("refusal"));
+
+        // Azure OpenAI content filtering
+        let response = r#"{"id":"2","model":"gpt-4o","prompt_filter_results":[{"prompt_index":0,"content_filter_results":{"hate":{"filtered":false,"severity":"safe"}}}],
+            "choices":[{"message":{"content":"Partial"},"finish_reason":"content_filter","content_filter_results":{"violence":{"filtered":true,"severity":"high"},"jailbreak":{"filtered":false,"detected":false}}}]}"#;
+
+        let safety = OpenAiParser.parse(response).unwrap().safety.unwrap();
+        assert!(safety.blocked);
+        assert_eq!(safety.reason.as_deref(), Some("content_filter"));
+        assert_eq!(safety.ratings.len(), 3);
+        assert!(safety.ratings[0].prompt);
+        let violence = safety.ratings.iter().find(|r| r.category == "violence").unwrap();
+        assert!(violence.filtered);
+        assert_eq!(violence.severity.as_deref(), Some("high"));
+    }
+}
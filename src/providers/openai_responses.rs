@@ -0,0 +1,328 @@
+//! Parser for the OpenAI Responses API
+
+use serde_json::Value;
+
+use super::{
+    array_field, decode_arguments, matches_prefix, optional_str, required_str, token_logprobs,
+    usage_from, OpenAiParser,
+};
+use crate::normalized::{Citation, CitationSource, ContentBlock, ContentFrame, SafetyReport};
+use crate::parser::{ModelResponseParser, ParseError};
+
+/// Parser for OpenAI `/v1/responses` responses
+///
+/// Handles the same models as [`OpenAiParser`] and is selected by response
+/// shape (`"object": "response"`). Output items are normalized as follows:
+///
+/// - `message` items become `Text` blocks, with `url_citation` annotations as
+///   citations and any requested logprobs, and `Refusal` blocks
+/// - `reasoning` items become `Thinking` blocks holding the reasoning summary
+/// - `function_call`, `custom_tool_call`, `computer_call` and `local_shell_call`
+///   items, which the client must act on, become `ToolUse` blocks identified by
+///   their `call_id`
+/// - tools run by OpenAI (`web_search_call`, `file_search_call`,
+///   `code_interpreter_call`, `image_generation_call` and `mcp_call`) become
+///   `ServerToolUse` blocks, followed by a `ServerToolResult` when the item
+///   carries results or outputs
+///
+/// # Examples
+///
+/// ```
+/// use adaptogen::parser::ModelResponseParser;
+/// use adaptogen::providers::OpenAiResponsesParser;
+///
+/// let response = r#"{
+///     "id": "resp_123",
+///     "object": "response",
+///     "model": "gpt-4.1",
+///     "status": "completed",
+///     "output": [{"type": "message", "role": "assistant", "content": [{"type": "output_text", "text": "Hello!"}]}]
+/// }"#;
+///
+/// let frame = OpenAiResponsesParser.parse(response).unwrap();
+/// assert_eq!(frame.blocks.len(), 1);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OpenAiResponsesParser;
+
+impl ModelResponseParser for OpenAiResponsesParser {
+    /// Model name prefixes handled by this parser, shared with [`OpenAiParser`]
+    fn supported_models(&self) -> Vec<String> {
+        OpenAiParser.supported_models()
+    }
+
+    fn parse(&self, raw_response: &str) -> Result<ContentFrame, ParseError> {
+        let json: Value = serde_json::from_str(raw_response)?;
+
+        let id = required_str(&json, "id")?;
+        let model = required_str(&json, "model")?;
+
+        let blocks: Vec<ContentBlock> = array_field(&json, "output")
+            .iter()
+            .flat_map(parse_output_item)
+            .collect();
+
+        // Incomplete responses say why in `incomplete_details.reason`
+        let stop_reason = json
+            .pointer("/incomplete_details/reason")
+            .and_then(|r| r.as_str())
+            .map(|r| r.to_string())
+            .or_else(|| optional_str(&json, "status"));
+
+        let refused = blocks
+            .iter()
+            .any(|block| matches!(block, ContentBlock::Refusal { .. }));
+        let reason = if refused {
+            Some("refusal".to_string())
+        } else {
+            stop_reason.clone().filter(|reason| reason == "content_filter")
+        };
+        let safety = reason.map(|reason| SafetyReport {
+            blocked: true,
+            reason: Some(reason),
+            ratings: Vec::new(),
+        });
+
+        Ok(ContentFrame {
+            id,
+            model,
+            blocks,
+            stop_reason,
+            usage: usage_from(json.get("usage"), "input_tokens", "output_tokens"),
+            safety,
+            ..Default::default()
+        })
+    }
+
+    fn can_handle(&self, model: &str) -> bool {
+        matches_prefix(&self.supported_models(), model)
+    }
+
+    fn can_handle_response(&self, response: &Value) -> bool {
+        response.get("object").and_then(|o| o.as_str()) == Some("response")
+    }
+}
+
+/// Normalize one output item into zero or more blocks
+fn parse_output_item(item: &Value) -> Vec<ContentBlock> {
+    let Some(item_type) = item.get("type").and_then(|t| t.as_str()) else {
+        return Vec::new();
+    };
+
+    match item_type {
+        "message" => array_field(item, "content")
+            .iter()
+            .filter_map(parse_message_content)
+            .collect(),
+        "reasoning" => {
+            let summary: Vec<&str> = array_field(item, "summary")
+                .iter()
+                .filter_map(|s| s.get("text").and_then(|t| t.as_str()))
+                .collect();
+            vec![ContentBlock::Thinking {
+                thinking: (!summary.is_empty()).then(|| summary.join("\n\n")),
+                signature: optional_str(item, "encrypted_content"),
+            }]
+        }
+        "function_call" | "custom_tool_call" => {
+            let (Some(id), Some(name)) = (call_id(item), optional_str(item, "name")) else {
+                return Vec::new();
+            };
+            vec![ContentBlock::ToolUse {
+                id,
+                name,
+                input: decode_arguments(item.get("arguments").or_else(|| item.get("input"))),
+            }]
+        }
+        // Built-in tools whose actions the client carries out
+        "computer_call" | "local_shell_call" => {
+            let Some(id) = call_id(item) else {
+                return Vec::new();
+            };
+            vec![ContentBlock::ToolUse {
+                id,
+                name: item_type.trim_end_matches("_call").to_string(),
+                input: item.get("action").cloned().unwrap_or_default(),
+            }]
+        }
+        "web_search_call" | "file_search_call" | "code_interpreter_call"
+        | "image_generation_call" | "mcp_call" => parse_server_call(item, item_type),
+        _ => Vec::new(),
+    }
+}
+
+/// Client tool calls are answered by their `call_id` rather than the item id
+fn call_id(item: &Value) -> Option<String> {
+    optional_str(item, "call_id").or_else(|| optional_str(item, "id"))
+}
+
+fn parse_message_content(content: &Value) -> Option<ContentBlock> {
+    match content.get("type")?.as_str()? {
+        "output_text" => Some(ContentBlock::Text {
+            text: optional_str(content, "text")?,
+            citations: array_field(content, "annotations")
+                .iter()
+                .filter(|a| a.get("type").and_then(|t| t.as_str()) == Some("url_citation"))
+                .map(|annotation| Citation {
+                    start: annotation
+                        .get("start_index")
+                        .and_then(|i| i.as_u64())
+                        .map(|i| i as usize),
+                    end: annotation
+                        .get("end_index")
+                        .and_then(|i| i.as_u64())
+                        .map(|i| i as usize),
+                    sources: vec![CitationSource {
+                        id: None,
+                        title: optional_str(annotation, "title"),
+                        url: optional_str(annotation, "url"),
+                    }],
+                    ..Default::default()
+                })
+                .collect(),
+            logprobs: token_logprobs(array_field(content, "logprobs")),
+        }),
+        "refusal" => Some(ContentBlock::Refusal {
+            refusal: optional_str(content, "refusal")?,
+        }),
+        _ => None,
+    }
+}
+
+/// Normalize a tool call executed by OpenAI, such as `web_search_call`
+fn parse_server_call(item: &Value, item_type: &str) -> Vec<ContentBlock> {
+    let Some(id) = optional_str(item, "id") else {
+        return Vec::new();
+    };
+    let name = item_type.trim_end_matches("_call").to_string();
+
+    // Everything but the bookkeeping and the results describes the call
+    let mut input = item.as_object().cloned().unwrap_or_default();
+    for field in ["type", "id", "status", "results", "outputs", "result", "output"] {
+        input.remove(field);
+    }
+
+    let mut blocks = vec![ContentBlock::ServerToolUse {
+        id: id.clone(),
+        name,
+        input: Value::Object(input),
+    }];
+
+    let result = ["results", "outputs", "result", "output"]
+        .iter()
+        .find_map(|field| item.get(*field).filter(|r| !r.is_null()));
+    if let Some(result) = result {
+        blocks.push(ContentBlock::ServerToolResult {
+            tool_use_id: id,
+            content: result.clone(),
+            is_error: optional_str(item, "status").as_deref() == Some("failed"),
+        });
+    }
+
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_output_items() {
+        let response = r#"{
+            "id": "resp_1",
+            "object": "response",
+            "model": "o4-mini",
+            "status": "completed",
+            "output": [
+                {"type": "reasoning", "id": "rs_1", "summary": [{"type": "summary_text", "text": "Search first."}]},
+                {"type": "web_search_call", "id": "ws_1", "status": "completed", "action": {"type": "search", "query": "weather paris"}},
+                {"type": "code_interpreter_call", "id": "ci_1", "status": "completed", "code": "print(1)", "container_id": "cntr_1", "outputs": [{"type": "logs", "logs": "1"}]},
+                {"type": "function_call", "id": "fc_1", "call_id": "call_abc", "name": "get_weather", "arguments": "{\"city\": \"Paris\"}"},
+                {"type": "message", "role": "assistant", "content": [
+                    {"type": "output_text", "text": "Sunny, per example.com.", "annotations": [{"type": "url_citation", "start_index": 11, "end_index": 22, "url": "https://example.com", "title": "Example"}]}
+                ]}
+            ],
+            "usage": {"input_tokens": 20, "output_tokens": 30}
+        }"#;
+
+        let frame = OpenAiResponsesParser.parse(response).unwrap();
+        assert_eq!(frame.stop_reason.as_deref(), Some("completed"));
+        assert_eq!(frame.usage.unwrap().total_tokens(), 50);
+        assert_eq!(frame.blocks.len(), 6);
+
+        assert!(matches!(frame.blocks[0], ContentBlock::Thinking { .. }));
+        match &frame.blocks[1] {
+            ContentBlock::ServerToolUse { id, name, input } => {
+                assert_eq!(id, "ws_1");
+                assert_eq!(name, "web_search");
+                assert_eq!(input["action"]["query"], "weather paris");
+            }
+            _ => panic!("Expected ServerToolUse block"),
+        }
+        match &frame.blocks[3] {
+            ContentBlock::ServerToolResult { tool_use_id, content, is_error } => {
+                assert_eq!(tool_use_id, "ci_1");
+                assert_eq!(content[0]["logs"], "1");
+                assert!(!is_error);
+            }
+            _ => panic!("Expected ServerToolResult block"),
+        }
+        match &frame.blocks[4] {
+            ContentBlock::ToolUse { id, input, .. } => {
+                assert_eq!(id, "call_abc");
+                assert_eq!(input["city"], "Paris");
+            }
+            _ => panic!("Expected ToolUse block"),
+        }
+        match &frame.blocks[5] {
+            ContentBlock::Text { citations, .. } => {
+                assert_eq!(citations[0].start, Some(11));
+                assert_eq!(citations[0].sources[0].url.as_deref(), Some("https://example.com"));
+            }
+            _ => panic!("Expected Text block"),
+        }
+    }
+
+    #[test]
+    fn test_output_text_logprobs() {
+        let response = r#"{"id": "resp_2", "object": "response", "model": "gpt-4.1", "status": "completed",
+            "output": [{"type": "message", "role": "assistant", "content": [{"type": "output_text", "text": "Hi there", "annotations": [],
+                "logprobs": [{"token": "Hi", "logprob": -0.1, "bytes": [72, 105], "top_logprobs": [{"token": "Hi", "logprob": -0.1}, {"token": "Hey", "logprob": -2.5}]},
+                             {"token": " there", "logprob": -0.3, "top_logprobs": []}]}]}]}"#;
+
+        let frame = OpenAiResponsesParser.parse(response).unwrap();
+        match &frame.blocks[0] {
+            ContentBlock::Text { logprobs, .. } => {
+                assert_eq!(logprobs.len(), 2);
+                assert_eq!(logprobs[0].top_logprobs[1].token, "Hey");
+                assert_eq!(logprobs[1].offset, 2);
+            }
+            _ => panic!("Expected Text block"),
+        }
+    }
+
+    #[test]
+    fn test_refusal() {
+        let response = r#"{"id": "resp_3", "object": "response", "model": "gpt-4.1", "status": "completed",
+            "output": [{"type": "message", "role": "assistant", "content": [{"type": "refusal", "refusal": "I can't help with that."}]}]}"#;
+
+        let frame = OpenAiResponsesParser.parse(response).unwrap();
+        match &frame.blocks[0] {
+            ContentBlock::Refusal { refusal } => assert_eq!(refusal, "I can't help with that."),
+            _ => panic!("Expected Refusal block"),
+        }
+        let safety = frame.safety.unwrap();
+        assert!(safety.blocked);
+        assert_eq!(safety.reason.as_deref(), Some("refusal"));
+    }
+
+    #[test]
+    fn test_incomplete_response() {
+        let response = r#"{"id": "resp_2", "object": "response", "model": "gpt-4.1", "status": "incomplete",
+            "incomplete_details": {"reason": "max_output_tokens"}, "output": []}"#;
+
+        let frame = OpenAiResponsesParser.parse(response).unwrap();
+        assert_eq!(frame.stop_reason.as_deref(), Some("max_output_tokens"));
+        assert!(frame.safety.is_none());
+    }
+}
@@ -0,0 +1,227 @@
+//! Helpers shared by the built-in provider parsers
+
+#[cfg(any(feature = "gemini", feature = "openai"))]
+use std::ops::Range;
+
+use serde_json::Value;
+
+#[cfg(any(feature = "anthropic", feature = "openai"))]
+use super::optional_str;
+#[cfg(feature = "anthropic")]
+use crate::normalized::SafetyReport;
+#[cfg(any(feature = "gemini", feature = "openai"))]
+use crate::normalized::TokenLogprob;
+#[cfg(feature = "openai")]
+use crate::normalized::TopLogprob;
+use crate::parser::ParseError;
+
+/// Check a model name against a list of supported prefixes
+pub(crate) fn matches_prefix(prefixes: &[String], model: &str) -> bool {
+    prefixes.iter().any(|prefix| model.starts_with(prefix.as_str()))
+}
+
+/// Extract a required string field from a JSON object
+pub(crate) fn required_str(json: &Value, field: &str) -> Result<String, ParseError> {
+    json.get(field)
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+        .ok_or_else(|| ParseError::MissingField(field.to_string()))
+}
+
+/// Borrow an array field of a JSON object, treating a missing field as empty
+#[cfg(any(feature = "gemini", feature = "mistral", feature = "openai"))]
+pub(crate) fn array_field<'a>(json: &'a Value, field: &str) -> &'a [Value] {
+    json.get(field)
+        .and_then(|v| v.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+/// Extract an optional non-negative integer field, such as a choice index
+#[cfg(any(feature = "gemini", feature = "mistral", feature = "openai"))]
+pub(crate) fn index_field(json: &Value, field: &str) -> Option<usize> {
+    json.get(field).and_then(|v| v.as_u64()).map(|v| v as usize)
+}
+
+/// Report a blocked response when the stop reason is one of `blocked_reasons`
+#[cfg(feature = "anthropic")]
+pub(crate) fn blocked_by_stop_reason(
+    json: &Value,
+    field: &str,
+    blocked_reasons: &[&str],
+) -> Option<SafetyReport> {
+    let reason = optional_str(json, field).filter(|r| blocked_reasons.contains(&r.as_str()))?;
+    Some(SafetyReport {
+        blocked: true,
+        reason: Some(reason),
+        ratings: Vec::new(),
+    })
+}
+
+/// Read an OpenAI-style `logprobs` object into a token track
+///
+/// Both the Chat Completions format (`content: [{token, logprob, bytes, top_logprobs}]`)
+/// and the legacy Completions format (`tokens`, `token_logprobs`, `top_logprobs`)
+/// returned by some vLLM and Fireworks endpoints are understood. Offsets are
+/// counted from the start of the generated text.
+#[cfg(feature = "openai")]
+pub(crate) fn openai_logprobs(logprobs: Option<&Value>) -> Vec<TokenLogprob> {
+    let Some(logprobs) = logprobs.filter(|l| !l.is_null()) else {
+        return Vec::new();
+    };
+    if logprobs.get("content").is_some() {
+        return token_logprobs(array_field(logprobs, "content"));
+    }
+
+    let top = array_field(logprobs, "top_logprobs");
+    let mut tokens: Vec<TokenLogprob> = array_field(logprobs, "tokens")
+        .iter()
+        .zip(array_field(logprobs, "token_logprobs"))
+        .enumerate()
+        .filter_map(|(i, (token, logprob))| {
+            let mut top_logprobs: Vec<TopLogprob> = top
+                .get(i)
+                .and_then(|t| t.as_object())
+                .into_iter()
+                .flatten()
+                .filter_map(|(token, logprob)| {
+                    Some(TopLogprob {
+                        token: token.clone(),
+                        logprob: logprob.as_f64()?,
+                        bytes: None,
+                    })
+                })
+                .collect();
+            top_logprobs.sort_by(|a, b| b.logprob.total_cmp(&a.logprob));
+
+            Some(TokenLogprob {
+                token: token.as_str()?.to_string(),
+                // The first token of a prompt has no logprob
+                logprob: logprob.as_f64().unwrap_or(0.0),
+                offset: 0,
+                bytes: None,
+                top_logprobs,
+            })
+        })
+        .collect();
+
+    assign_offsets(&mut tokens);
+    tokens
+}
+
+/// Read a list of `{token, logprob, bytes, top_logprobs}` entries into a token track
+///
+/// This is the entry format of both Chat Completions `logprobs.content` and
+/// Responses `output_text.logprobs`. Offsets are counted from the start of
+/// the text the entries cover.
+#[cfg(feature = "openai")]
+pub(crate) fn token_logprobs(entries: &[Value]) -> Vec<TokenLogprob> {
+    let mut tokens: Vec<TokenLogprob> = entries
+        .iter()
+        .filter_map(|entry| {
+            Some(TokenLogprob {
+                token: optional_str(entry, "token")?,
+                logprob: entry.get("logprob")?.as_f64()?,
+                offset: 0,
+                bytes: token_bytes(entry.get("bytes")),
+                top_logprobs: array_field(entry, "top_logprobs")
+                    .iter()
+                    .filter_map(|top| {
+                        Some(TopLogprob {
+                            token: optional_str(top, "token")?,
+                            logprob: top.get("logprob")?.as_f64()?,
+                            bytes: token_bytes(top.get("bytes")),
+                        })
+                    })
+                    .collect(),
+            })
+        })
+        .collect();
+
+    assign_offsets(&mut tokens);
+    tokens
+}
+
+#[cfg(feature = "openai")]
+fn token_bytes(bytes: Option<&Value>) -> Option<Vec<u8>> {
+    bytes?
+        .as_array()?
+        .iter()
+        .map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok()))
+        .collect()
+}
+
+/// Number of bytes a token contributes to the generated text
+#[cfg(any(feature = "gemini", feature = "openai"))]
+fn token_len(token: &TokenLogprob) -> usize {
+    token.bytes.as_ref().map_or(token.token.len(), Vec::len)
+}
+
+/// Set each token's offset to the sum of the lengths of the tokens before it
+#[cfg(any(feature = "gemini", feature = "openai"))]
+pub(crate) fn assign_offsets(tokens: &mut [TokenLogprob]) {
+    let mut offset = 0;
+    for token in tokens {
+        token.offset = offset;
+        offset += token_len(token);
+    }
+}
+
+/// Select the tokens overlapping `range` of the generated text
+///
+/// Offsets of the returned tokens are made relative to the start of `range`;
+/// a token that starts before the range is given offset 0.
+#[cfg(any(feature = "gemini", feature = "openai"))]
+pub(crate) fn logprobs_in(tokens: &[TokenLogprob], range: Range<usize>) -> Vec<TokenLogprob> {
+    tokens
+        .iter()
+        .filter(|token| {
+            let end = token.offset + token_len(token);
+            token.offset < range.end && (end > range.start || token.offset >= range.start)
+        })
+        .map(|token| TokenLogprob {
+            offset: token.offset.saturating_sub(range.start),
+            ..token.clone()
+        })
+        .collect()
+}
+
+#[cfg(all(test, feature = "openai"))]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_openai_logprobs_alignment() {
+        let logprobs = json!({"content": [
+            {"token": "</think>", "logprob": -0.1, "bytes": [60, 47, 116, 104, 105, 110, 107, 62], "top_logprobs": []},
+            {"token": "\nPar", "logprob": -0.2, "top_logprobs": [{"token": "\nPar", "logprob": -0.2}, {"token": "\nLy", "logprob": -2.0}]},
+            {"token": "is", "logprob": -0.01}
+        ]});
+
+        let tokens = openai_logprobs(Some(&logprobs));
+        assert_eq!(tokens.iter().map(|t| t.offset).collect::<Vec<_>>(), [0, 8, 12]);
+        assert_eq!(tokens[1].top_logprobs.len(), 2);
+
+        // The text "Paris" starts after the newline at byte 9
+        let aligned = logprobs_in(&tokens, 9..14);
+        assert_eq!(aligned.len(), 2);
+        assert_eq!((aligned[0].offset, aligned[1].offset), (0, 3));
+    }
+
+    #[test]
+    fn test_legacy_logprobs() {
+        let logprobs = json!({
+            "tokens": ["Hel", "lo"],
+            "token_logprobs": [-0.5, -0.25],
+            "top_logprobs": [{"Hel": -0.5, "Hi": -1.5}, null]
+        });
+
+        let tokens = openai_logprobs(Some(&logprobs));
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[1].offset, 3);
+        assert_eq!(tokens[0].top_logprobs[0].token, "Hel");
+        assert!(openai_logprobs(Some(&Value::Null)).is_empty());
+    }
+}
@@ -1,9 +1,43 @@
 use serde_json::Value;
-use std::sync::Arc;
+use std::io::BufRead;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
-use crate::normalized::ContentFrame;
+use crate::batch::JsonlFrames;
+use crate::normalized::{ContentFrame, ParsedResponse};
 use crate::parser::ModelResponseParser;
 use crate::parser::ParseError;
+use crate::providers;
+
+/// Handle identifying a parser registered with a [`ParserRegistry`]
+///
+/// Returned by the `register_*` methods and used to unregister, replace or
+/// re-prioritize that parser later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ParserId(u64);
+
+/// A parser entry as stored in a [`ParserRegistry`]
+///
+/// Returned by [`ParserRegistry::parsers`] for introspection.
+#[derive(Clone)]
+pub struct RegisteredParser {
+    /// Handle of this registration
+    pub id: ParserId,
+    /// Priority of this parser; higher priorities are consulted first
+    pub priority: i32,
+    /// The registered parser
+    pub parser: Arc<dyn ModelResponseParser>,
+}
+
+impl std::fmt::Debug for RegisteredParser {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RegisteredParser")
+            .field("id", &self.id)
+            .field("priority", &self.priority)
+            .field("supported_models", &self.parser.supported_models())
+            .finish()
+    }
+}
 
 /// Registry of model parsers
 ///
@@ -11,6 +45,13 @@ use crate::parser::ParseError;
 /// functionality to parse LLM responses by selecting the appropriate parser
 /// based on the model identifier in the response.
 ///
+/// The registry is safe to share between threads (for example behind an `Arc`)
+/// and can be modified at runtime through a shared reference: parsers can be
+/// registered, unregistered or replaced while other threads are parsing.
+///
+/// Parsers are consulted in descending priority order; parsers with equal
+/// priority are consulted in the order they were registered.
+///
 /// # Examples
 ///
 /// ```
@@ -31,23 +72,29 @@ use crate::parser::ParseError;
 ///         Ok(ContentFrame {
 ///             id: "msg_123".to_string(),
 ///             model: "claude".to_string(),
-///             blocks: vec![]
+///             blocks: vec![],
+///             ..Default::default()
 ///         })
 ///     }
 /// }
 ///
 /// // Create a new registry
-/// let mut registry = ParserRegistry::new();
+/// let registry = ParserRegistry::new();
 ///
 /// // Register the Claude parser
-/// registry.register_parser(Arc::new(ClaudeParser));
+/// let id = registry.register_parser(Arc::new(ClaudeParser));
 ///
 /// // Parse a response
 /// let response = r#"{"id": "msg_123", "model": "claude", "content": []}"#;
 /// let result = registry.parse(response);
+///
+/// // Remove the parser again
+/// assert!(registry.unregister(id).is_some());
+/// assert!(registry.is_empty());
 /// ```
 pub struct ParserRegistry {
-    parsers: Vec<Arc<dyn ModelResponseParser>>,
+    parsers: RwLock<Vec<RegisteredParser>>,
+    next_id: AtomicU64,
 }
 
 impl ParserRegistry {
@@ -56,16 +103,131 @@ impl ParserRegistry {
     /// Returns a `ParserRegistry` with no registered parsers.
     pub fn new() -> Self {
         Self {
-            parsers: Vec::new(),
+            parsers: RwLock::new(Vec::new()),
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    /// Create a registry with every built-in parser
+    ///
+    /// Registers the parsers from [`providers::default_parsers`] with the default
+    /// priority. Which providers are included depends on the enabled cargo features.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use adaptogen::registry::ParserRegistry;
+    ///
+    /// let registry = ParserRegistry::with_defaults();
+    /// let response = r#"{"id": "msg_1", "model": "claude-3-5-haiku-latest", "content": []}"#;
+    /// # #[cfg(feature = "anthropic")]
+    /// assert!(registry.parse(response).is_ok());
+    /// ```
+    pub fn with_defaults() -> Self {
+        let registry = Self::new();
+        for parser in providers::default_parsers() {
+            registry.register_parser(parser);
         }
+        registry
     }
 
     /// Register a new parser
     ///
-    /// Adds a parser to the registry. When parsing responses, parsers are checked in
-    /// the order they were registered.
-    pub fn register_parser(&mut self, parser: Arc<dyn ModelResponseParser>) {
-        self.parsers.push(parser);
+    /// Adds a parser to the registry with the default priority of `0`. When parsing
+    /// responses, parsers of equal priority are checked in the order they were
+    /// registered, except that a parser recognizing the response shape (see
+    /// [`can_handle_response`](ModelResponseParser::can_handle_response)) is
+    /// preferred over one that only handles the model. To override a parser for
+    /// its models, register the override with a higher priority.
+    pub fn register_parser(&self, parser: Arc<dyn ModelResponseParser>) -> ParserId {
+        self.register_parser_with_priority(parser, 0)
+    }
+
+    /// Register a new parser with an explicit priority
+    ///
+    /// Parsers with a higher priority are checked before parsers with a lower one,
+    /// which allows overriding an existing parser for some models without removing it.
+    pub fn register_parser_with_priority(
+        &self,
+        parser: Arc<dyn ModelResponseParser>,
+        priority: i32,
+    ) -> ParserId {
+        let id = ParserId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        let mut parsers = self.write();
+        Self::insert_sorted(
+            &mut parsers,
+            RegisteredParser {
+                id,
+                priority,
+                parser,
+            },
+        );
+        id
+    }
+
+    /// Remove a parser from the registry
+    ///
+    /// Returns the removed parser, or `None` if no parser is registered under `id`.
+    pub fn unregister(&self, id: ParserId) -> Option<Arc<dyn ModelResponseParser>> {
+        let mut parsers = self.write();
+        let index = parsers.iter().position(|entry| entry.id == id)?;
+        Some(parsers.remove(index).parser)
+    }
+
+    /// Replace a registered parser
+    ///
+    /// The new parser keeps the id, priority and position of the one it replaces.
+    /// Returns the previous parser, or `None` (leaving the registry unchanged) if no
+    /// parser is registered under `id`.
+    pub fn replace(
+        &self,
+        id: ParserId,
+        parser: Arc<dyn ModelResponseParser>,
+    ) -> Option<Arc<dyn ModelResponseParser>> {
+        let mut parsers = self.write();
+        let entry = parsers.iter_mut().find(|entry| entry.id == id)?;
+        Some(std::mem::replace(&mut entry.parser, parser))
+    }
+
+    /// Change the priority of a registered parser
+    ///
+    /// Returns `false` if no parser is registered under `id`.
+    pub fn set_priority(&self, id: ParserId, priority: i32) -> bool {
+        let mut parsers = self.write();
+        let Some(index) = parsers.iter().position(|entry| entry.id == id) else {
+            return false;
+        };
+        let mut entry = parsers.remove(index);
+        entry.priority = priority;
+        Self::insert_sorted(&mut parsers, entry);
+        true
+    }
+
+    /// List the registered parsers
+    ///
+    /// Returns a snapshot of the registry in the order parsers are consulted.
+    pub fn parsers(&self) -> Vec<RegisteredParser> {
+        self.read().clone()
+    }
+
+    /// Find the parser that would be used for a model
+    ///
+    /// Returns the highest-priority parser whose `can_handle` accepts `model`.
+    pub fn parser_for(&self, model: &str) -> Option<Arc<dyn ModelResponseParser>> {
+        self.read()
+            .iter()
+            .find(|entry| entry.parser.can_handle(model))
+            .map(|entry| Arc::clone(&entry.parser))
+    }
+
+    /// Number of registered parsers
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    /// Whether no parsers are registered
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
     }
 
     /// Parse a raw LLM response
@@ -75,43 +237,233 @@ impl ParserRegistry {
     /// 2. Finding a parser that supports the identified model
     /// 3. Using that parser to parse the complete response
     ///
+    /// Parsers are tried in priority order. Responses without a model
+    /// identifier (such as Bedrock Converse responses) are routed to the first
+    /// parser whose `can_handle_response` recognizes them. When several parsers
+    /// of the same priority support the identified model, one that also
+    /// recognizes the response shape is preferred. Parsers that opt into
+    /// [`routes_by_shape`](ModelResponseParser::routes_by_shape) (such as the
+    /// Ollama parser, whose model names overlap with other providers) are
+    /// selected by response shape for any model.
+    ///
     /// # Errors
     ///
     /// Returns a `ParseError` if:
     /// - The JSON is invalid
-    /// - The model field is missing
+    /// - The response is a provider error body, reported as [`ParseError::ProviderError`]
+    /// - The model field is missing and no parser recognizes the response
     /// - No parser is registered for the identified model
     /// - The selected parser fails to parse the response
     pub fn parse(&self, raw_response: &str) -> Result<ContentFrame, ParseError> {
-        let model = Self::extract_model(raw_response)?;
-
-        for parser in &self.parsers {
-            if parser.can_handle(&model) {
-                return parser.parse(raw_response);
-            }
-        }
+        let json = Self::read_response(raw_response)?;
+        self.parser_for_response(&json)?.parse(raw_response)
+    }
 
-        Err(ParseError::UnsupportedModel(model))
+    /// Parse every candidate completion of a raw LLM response
+    ///
+    /// Like [`parse`](Self::parse), but returns all candidates when the response
+    /// holds several, for example from an OpenAI request with `n > 1`. Parsers
+    /// that only understand single-message formats yield one candidate.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`parse`](Self::parse).
+    pub fn parse_all(&self, raw_response: &str) -> Result<ParsedResponse, ParseError> {
+        let json = Self::read_response(raw_response)?;
+        self.parser_for_response(&json)?.parse_all(raw_response)
     }
 
-    /// Extract the model identifier from a response
+    /// Parse a raw LLM response produced by a known model
     ///
-    /// Parses the response as JSON and extracts the "model" field.
+    /// Use this when the model is known from outside the response body, for
+    /// example from the request or from a Bedrock model id. The parser is
+    /// selected by `model` instead of by the response's own model field, and
+    /// receives `model` through [`ModelResponseParser::parse_with_model`].
     ///
     /// # Errors
     ///
     /// Returns a `ParseError` if:
     /// - The JSON is invalid
-    /// - The model field is missing
-    fn extract_model(raw_response: &str) -> Result<String, ParseError> {
+    /// - The response is a provider error body, reported as [`ParseError::ProviderError`]
+    /// - No parser is registered for `model`
+    /// - The selected parser fails to parse the response
+    pub fn parse_with_model(
+        &self,
+        raw_response: &str,
+        model: &str,
+    ) -> Result<ContentFrame, ParseError> {
+        let json = Self::read_response(raw_response)?;
+
+        let parser = self
+            .select_parser(Some(model), &json)
+            .ok_or_else(|| ParseError::UnsupportedModel(model.to_string()))?;
+
+        parser.parse_with_model(raw_response, model)
+    }
+
+    /// Parse every candidate completion of a raw LLM response produced by a known model
+    ///
+    /// The multi-candidate counterpart of [`parse_with_model`](Self::parse_with_model).
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`parse_with_model`](Self::parse_with_model).
+    pub fn parse_all_with_model(
+        &self,
+        raw_response: &str,
+        model: &str,
+    ) -> Result<ParsedResponse, ParseError> {
+        let json = Self::read_response(raw_response)?;
+
+        let parser = self
+            .select_parser(Some(model), &json)
+            .ok_or_else(|| ParseError::UnsupportedModel(model.to_string()))?;
+
+        parser.parse_all_with_model(raw_response, model)
+    }
+
+    /// Parse a JSON Lines stream with one raw response per line
+    ///
+    /// Returns a lazy iterator of `(line number, result)` pairs, so a failing
+    /// line is reported without stopping the rest; see [`JsonlFrames`]. Each
+    /// line is parsed as by [`parse`](Self::parse).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use adaptogen::registry::ParserRegistry;
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let registry = ParserRegistry::with_defaults();
+    /// let mut frames = registry.parse_jsonl(BufReader::new(File::open("responses.jsonl")?));
+    /// for (line, result) in &mut frames {
+    ///     match result {
+    ///         Ok(frame) => println!("{line}: {} blocks", frame.blocks.len()),
+    ///         Err(error) => eprintln!("{line}: {error}"),
+    ///     }
+    /// }
+    /// println!("{:?}", frames.stats().by_error);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn parse_jsonl<R: BufRead>(&self, reader: R) -> JsonlFrames<'_, R> {
+        JsonlFrames::new(self, reader, false)
+    }
+
+    /// Parse a JSON Lines stream on the rayon thread pool
+    ///
+    /// Like [`parse_jsonl`](Self::parse_jsonl), and yields results in the same
+    /// order, but reads lines in batches and parses each batch in parallel.
+    /// Requires the `parallel` feature.
+    #[cfg(feature = "parallel")]
+    pub fn parse_jsonl_parallel<R: BufRead>(&self, reader: R) -> JsonlFrames<'_, R> {
+        JsonlFrames::new(self, reader, true)
+    }
+
+    /// Choose the parser for a response based on its own model field or shape
+    ///
+    /// The returned parser is a clone of the registered `Arc`, so the lock is
+    /// released before parsing and parsers never run while holding it.
+    fn parser_for_response(&self, json: &Value) -> Result<Arc<dyn ModelResponseParser>, ParseError> {
+        match Self::extract_model(json) {
+            Ok(model) => self
+                .select_parser(Some(&model), json)
+                .ok_or(ParseError::UnsupportedModel(model)),
+            Err(missing) => self.select_parser(None, json).ok_or(missing),
+        }
+    }
+
+    /// Parse the response JSON, turning provider error bodies into errors
+    fn read_response(raw_response: &str) -> Result<Value, ParseError> {
         let json: Value = serde_json::from_str(raw_response)?;
+        match providers::detect_provider_error(&json) {
+            Some(error) => Err(error),
+            None => Ok(json),
+        }
+    }
 
-        if let Some(model) = json.get("model").and_then(|m| m.as_str()) {
+    /// Extract the model identifier from a response
+    ///
+    /// Extracts the "model" field, falling back to Gemini's "modelVersion" field.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ParseError` if the model field is missing
+    fn extract_model(json: &Value) -> Result<String, ParseError> {
+        let model = json
+            .get("model")
+            .or_else(|| json.get("modelVersion"))
+            .and_then(|m| m.as_str());
+
+        if let Some(model) = model {
             Ok(model.to_string())
         } else {
             Err(ParseError::MissingField("model".to_string()))
         }
     }
+
+    /// Choose the parser for a response
+    ///
+    /// Parsers are tried in priority order, and the first eligible one wins. A
+    /// parser is eligible when it handles `model` or, when the model is unknown,
+    /// when it recognizes the response shape. Parsers that opt into
+    /// [`routes_by_shape`](ModelResponseParser::routes_by_shape) are also
+    /// eligible for any model whose response they recognize. Among eligible
+    /// parsers of the same priority, one that recognizes the response shape is
+    /// preferred.
+    fn select_parser(
+        &self,
+        model: Option<&str>,
+        json: &Value,
+    ) -> Option<Arc<dyn ModelResponseParser>> {
+        let parsers = self.read();
+        let mut fallback: Option<&RegisteredParser> = None;
+
+        for entry in parsers.iter() {
+            if fallback.is_some_and(|first| entry.priority < first.priority) {
+                break;
+            }
+
+            let recognized = entry.parser.can_handle_response(json);
+            let eligible = match model {
+                Some(model) => {
+                    entry.parser.can_handle(model)
+                        || (recognized && entry.parser.routes_by_shape())
+                }
+                None => recognized,
+            };
+            if !eligible {
+                continue;
+            }
+            if recognized {
+                return Some(Arc::clone(&entry.parser));
+            }
+            fallback.get_or_insert(entry);
+        }
+
+        fallback.map(|entry| Arc::clone(&entry.parser))
+    }
+
+    /// Insert an entry after all entries with the same or a higher priority
+    fn insert_sorted(parsers: &mut Vec<RegisteredParser>, entry: RegisteredParser) {
+        let index = parsers
+            .iter()
+            .position(|existing| existing.priority < entry.priority)
+            .unwrap_or(parsers.len());
+        parsers.insert(index, entry);
+    }
+
+    // A panic in another thread cannot leave the parser list half-updated, so a
+    // poisoned lock is still safe to use.
+    fn read(&self) -> RwLockReadGuard<'_, Vec<RegisteredParser>> {
+        self.parsers.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Vec<RegisteredParser>> {
+        self.parsers.write().unwrap_or_else(PoisonError::into_inner)
+    }
 }
 
 impl Default for ParserRegistry {
@@ -141,9 +493,8 @@ mod tests {
                 Ok(ContentFrame {
                     id: "test_id".to_string(),
                     model: self.models.first().unwrap_or(&"unknown".to_string()).clone(),
-                    blocks: vec![ContentBlock::Text { 
-                        text: "Test response".to_string() 
-                    }],
+                    blocks: vec![ContentBlock::text("Test response")],
+                    ..Default::default()
                 })
             } else {
                 Err(ParseError::Other("Simulated failure".to_string()))
@@ -154,25 +505,25 @@ mod tests {
     #[test]
     fn test_registry_new() {
         let registry = ParserRegistry::new();
-        assert_eq!(registry.parsers.len(), 0);
+        assert_eq!(registry.len(), 0);
     }
 
     #[test]
     fn test_register_parser() {
-        let mut registry = ParserRegistry::new();
+        let registry = ParserRegistry::new();
         let parser = Arc::new(MockParser {
             models: vec!["test_model".to_string()],
             should_succeed: true,
         });
         
         registry.register_parser(parser);
-        assert_eq!(registry.parsers.len(), 1);
+        assert_eq!(registry.len(), 1);
     }
 
     #[test]
     fn test_extract_model_success() {
-        let json_str = r#"{"id": "123", "model": "test_model", "content": "test"}"#;
-        let result = ParserRegistry::extract_model(json_str);
+        let json = serde_json::json!({"id": "123", "model": "test_model", "content": "test"});
+        let result = ParserRegistry::extract_model(&json);
         
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "test_model");
@@ -180,8 +531,8 @@ mod tests {
 
     #[test]
     fn test_extract_model_missing() {
-        let json_str = r#"{"id": "123", "content": "test"}"#;
-        let result = ParserRegistry::extract_model(json_str);
+        let json = serde_json::json!({"id": "123", "content": "test"});
+        let result = ParserRegistry::extract_model(&json);
         
         assert!(result.is_err());
         match result.unwrap_err() {
@@ -192,7 +543,7 @@ mod tests {
 
     #[test]
     fn test_parse_success() {
-        let mut registry = ParserRegistry::new();
+        let registry = ParserRegistry::new();
         let parser = Arc::new(MockParser {
             models: vec!["test_model".to_string()],
             should_succeed: true,
@@ -210,7 +561,7 @@ mod tests {
 
     #[test]
     fn test_parse_unsupported_model() {
-        let mut registry = ParserRegistry::new();
+        let registry = ParserRegistry::new();
         let parser = Arc::new(MockParser {
             models: vec!["test_model".to_string()],
             should_succeed: true,
@@ -227,4 +578,289 @@ mod tests {
             _ => panic!("Expected UnsupportedModel error"),
         }
     }
+
+    fn mock(models: &[&str]) -> Arc<MockParser> {
+        Arc::new(MockParser {
+            models: models.iter().map(|m| m.to_string()).collect(),
+            should_succeed: true,
+        })
+    }
+
+    #[test]
+    fn test_unregister() {
+        let registry = ParserRegistry::new();
+        let id = registry.register_parser(mock(&["test_model"]));
+
+        assert!(registry.unregister(id).is_some());
+        assert!(registry.is_empty());
+        assert!(registry.unregister(id).is_none());
+
+        let response = r#"{"id": "123", "model": "test_model", "content": "test"}"#;
+        assert!(matches!(
+            registry.parse(response),
+            Err(ParseError::UnsupportedModel(_))
+        ));
+    }
+
+    #[test]
+    fn test_replace_keeps_position() {
+        let registry = ParserRegistry::new();
+        let first = registry.register_parser(mock(&["first", "shared"]));
+        registry.register_parser(mock(&["second", "shared"]));
+
+        let previous = registry.replace(first, mock(&["replacement", "shared"]));
+        assert_eq!(previous.unwrap().supported_models()[0], "first");
+
+        let parsers = registry.parsers();
+        assert_eq!(parsers.len(), 2);
+        assert_eq!(parsers[0].id, first);
+
+        let response = r#"{"id": "123", "model": "shared", "content": "test"}"#;
+        assert_eq!(registry.parse(response).unwrap().model, "replacement");
+    }
+
+    #[test]
+    fn test_replace_unknown_id() {
+        let registry = ParserRegistry::new();
+        let id = registry.register_parser(mock(&["test_model"]));
+        registry.unregister(id);
+
+        assert!(registry.replace(id, mock(&["other"])).is_none());
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn test_priority_ordering() {
+        let registry = ParserRegistry::new();
+        registry.register_parser(mock(&["low", "shared"]));
+        let high = registry.register_parser_with_priority(mock(&["high", "shared"]), 10);
+        registry.register_parser(mock(&["later", "shared"]));
+
+        let order: Vec<String> = registry
+            .parsers()
+            .iter()
+            .map(|entry| entry.parser.supported_models()[0].clone())
+            .collect();
+        assert_eq!(order, vec!["high", "low", "later"]);
+
+        let response = r#"{"id": "123", "model": "shared", "content": "test"}"#;
+        assert_eq!(registry.parse(response).unwrap().model, "high");
+
+        assert!(registry.set_priority(high, -1));
+        assert_eq!(registry.parse(response).unwrap().model, "low");
+        assert_eq!(registry.parsers()[2].id, high);
+    }
+
+    #[test]
+    fn test_shared_across_threads() {
+        let registry = Arc::new(ParserRegistry::new());
+        registry.register_parser(mock(&["test_model"]));
+
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let registry = Arc::clone(&registry);
+                std::thread::spawn(move || {
+                    let model = format!("model_{i}");
+                    registry.register_parser(mock(&[model.as_str()]));
+                    let response = format!(r#"{{"id": "123", "model": "{model}"}}"#);
+                    registry.parse(&response).unwrap().model
+                })
+            })
+            .collect();
+
+        for (i, handle) in handles.into_iter().enumerate() {
+            assert_eq!(handle.join().unwrap(), format!("model_{i}"));
+        }
+        assert_eq!(registry.len(), 5);
+    }
+
+    #[test]
+    fn test_extract_model_version() {
+        let json = serde_json::json!({"responseId": "123", "modelVersion": "gemini-2.0-flash"});
+        assert_eq!(
+            ParserRegistry::extract_model(&json).unwrap(),
+            "gemini-2.0-flash"
+        );
+    }
+
+    #[test]
+    fn test_with_defaults() {
+        let registry = ParserRegistry::with_defaults();
+        assert_eq!(registry.len(), providers::default_parsers().len());
+    }
+
+    // Parser that recognizes responses by the presence of a marker field
+    struct ShapeParser {
+        marker: &'static str,
+    }
+
+    impl ModelResponseParser for ShapeParser {
+        fn supported_models(&self) -> Vec<String> {
+            vec!["shared".to_string()]
+        }
+
+        fn can_handle_response(&self, response: &Value) -> bool {
+            response.get(self.marker).is_some()
+        }
+
+        fn parse(&self, raw_response: &str) -> Result<ContentFrame, ParseError> {
+            self.parse_with_model(raw_response, "")
+        }
+
+        fn parse_with_model(&self, _raw_response: &str, model: &str) -> Result<ContentFrame, ParseError> {
+            Ok(ContentFrame::new(self.marker, model, vec![]))
+        }
+    }
+
+    #[test]
+    fn test_parse_routes_by_response_shape() {
+        let registry = ParserRegistry::new();
+        registry.register_parser(mock(&["test_model"]));
+        registry.register_parser(Arc::new(ShapeParser { marker: "output" }));
+
+        let frame = registry.parse(r#"{"output": {}}"#).unwrap();
+        assert_eq!(frame.id, "output");
+
+        // A recognized shape does not claim responses for other models
+        let frame = registry.parse(r#"{"model": "test_model", "output": {}}"#).unwrap();
+        assert_eq!(frame.id, "test_id");
+
+        match registry.parse(r#"{"unknown": {}}"#).unwrap_err() {
+            ParseError::MissingField(field) => assert_eq!(field, "model"),
+            _ => panic!("Expected MissingField error"),
+        }
+    }
+
+    #[test]
+    fn test_parse_with_model() {
+        let registry = ParserRegistry::new();
+        registry.register_parser(Arc::new(ShapeParser { marker: "first" }));
+        registry.register_parser(Arc::new(ShapeParser { marker: "second" }));
+
+        // The response shape breaks the tie between parsers for the same model
+        let frame = registry.parse_with_model(r#"{"second": 1}"#, "shared").unwrap();
+        assert_eq!(frame.id, "second");
+        assert_eq!(frame.model, "shared");
+
+        // Without a recognized shape, the first parser for the model is used
+        let frame = registry.parse_with_model(r#"{}"#, "shared").unwrap();
+        assert_eq!(frame.id, "first");
+
+        assert!(matches!(
+            registry.parse_with_model(r#"{}"#, "other"),
+            Err(ParseError::UnsupportedModel(_))
+        ));
+    }
+
+    // Parser that supports no model names and opts into routing by shape
+    struct ShapeOnlyParser;
+
+    impl ModelResponseParser for ShapeOnlyParser {
+        fn supported_models(&self) -> Vec<String> {
+            Vec::new()
+        }
+
+        fn can_handle_response(&self, response: &Value) -> bool {
+            response.get("done").is_some()
+        }
+
+        fn routes_by_shape(&self) -> bool {
+            true
+        }
+
+        fn parse(&self, _raw_response: &str) -> Result<ContentFrame, ParseError> {
+            Ok(ContentFrame::new("shape_only", "", vec![]))
+        }
+    }
+
+    #[test]
+    fn test_priority_wins_over_response_shape() {
+        let registry = ParserRegistry::new();
+        registry.register_parser(Arc::new(ShapeOnlyParser));
+        registry.register_parser(mock(&["qwen3:8b"]));
+
+        // Among parsers of the same priority the recognized shape is preferred
+        let response = r#"{"model": "qwen3:8b", "done": true}"#;
+        assert_eq!(registry.parse(response).unwrap().id, "shape_only");
+
+        let registry = ParserRegistry::new();
+        registry.register_parser(Arc::new(ShapeOnlyParser));
+        registry.register_parser_with_priority(mock(&["qwen3:8b"]), 1);
+        assert_eq!(registry.parse(response).unwrap().id, "test_id");
+
+        // Without a model match, the shape-only parser is still used
+        let frame = registry.parse(r#"{"model": "llama3.2", "done": true}"#).unwrap();
+        assert_eq!(frame.id, "shape_only");
+    }
+
+    #[cfg(all(feature = "openai", feature = "ollama"))]
+    #[test]
+    fn test_priority_overrides_defaults() {
+        let registry = ParserRegistry::with_defaults();
+        registry.register_parser_with_priority(mock(&["gpt-4o", "llama3.2"]), 100);
+
+        let responses = [
+            r#"{"id": "resp_1", "object": "response", "model": "gpt-4o", "output": []}"#,
+            r#"{"model": "llama3.2", "message": {"role": "assistant", "content": "Hi"}, "done": true}"#,
+        ];
+        for response in responses {
+            assert_eq!(registry.parse(response).unwrap().id, "test_id");
+        }
+    }
+
+    #[cfg(feature = "openai")]
+    #[test]
+    fn test_shape_preferred_over_registration_order() {
+        let registry = ParserRegistry::new();
+        registry.register_parser(mock(&["gpt-4o"]));
+        registry.register_parser(Arc::new(crate::providers::OpenAiResponsesParser));
+
+        let response = r#"{"id": "resp_1", "object": "response", "model": "gpt-4o", "output": []}"#;
+        assert_eq!(registry.parse(response).unwrap().id, "resp_1");
+
+        registry.register_parser_with_priority(mock(&["gpt-4o"]), 1);
+        assert_eq!(registry.parse(response).unwrap().id, "test_id");
+    }
+
+    #[test]
+    fn test_parse_all_wraps_single_frame() {
+        let registry = ParserRegistry::new();
+        registry.register_parser(mock(&["test_model"]));
+
+        let response = registry
+            .parse_all(r#"{"model": "test_model"}"#)
+            .unwrap();
+        assert_eq!(response.len(), 1);
+        assert_eq!(response.candidates[0].index, 0);
+        assert_eq!(response.first().unwrap().id, "test_id");
+
+        let response = registry
+            .parse_all_with_model(r#"{}"#, "test_model")
+            .unwrap();
+        assert_eq!(response.len(), 1);
+    }
+
+    #[test]
+    fn test_provider_error_body() {
+        let registry = ParserRegistry::new();
+        registry.register_parser(mock(&["test_model"]));
+
+        let body = r#"{"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}}"#;
+        let error = registry.parse(body).unwrap_err();
+        assert!(error.is_retryable());
+        match error {
+            ParseError::ProviderError { provider, kind, message, .. } => {
+                assert_eq!(provider, "anthropic");
+                assert_eq!(kind, crate::parser::ProviderErrorKind::Overloaded);
+                assert_eq!(message, "Overloaded");
+            }
+            other => panic!("Expected ProviderError, got {other:?}"),
+        }
+
+        let body = r#"{"error": {"message": "Invalid API key", "type": "invalid_request_error", "code": "invalid_api_key"}}"#;
+        assert!(matches!(
+            registry.parse_with_model(body, "test_model"),
+            Err(ParseError::ProviderError { retryable: false, .. })
+        ));
+    }
 }
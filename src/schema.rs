@@ -0,0 +1,182 @@
+//! JSON Schema of the normalized format
+//!
+//! Consumers in other languages can validate and generate types from the
+//! schema of [`ContentFrame`] JSON produced by this crate. The schema for the
+//! current [`SCHEMA_VERSION`] is published in the repository as
+//! `schema/v2/content-frame.schema.json`, and tests keep it in sync with the
+//! Rust types.
+//!
+//! Requires the `schema` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! let schema = adaptogen::schema::content_frame_schema();
+//! assert_eq!(schema["title"], "ContentFrame");
+//! assert!(schema["definitions"]["ContentBlock"].is_object());
+//! ```
+
+use schemars::gen::SchemaSettings;
+use serde_json::{json, Value};
+
+use crate::normalized::{ContentFrame, BLOCK_TYPES, SCHEMA_VERSION};
+
+/// JSON Schema (draft 7) of a serialized [`ContentFrame`]
+///
+/// Every other normalized type, such as `ContentBlock` (tagged by `type`) and
+/// `ContentResultBlock`, is included under `definitions`. The schema's `$id`
+/// carries the [`SCHEMA_VERSION`].
+///
+/// Blocks of any other `type` are accepted, as they deserialize to
+/// [`ContentBlock::Unknown`](crate::normalized::ContentBlock::Unknown).
+pub fn content_frame_schema() -> Value {
+    let mut schema = SchemaSettings::draft07()
+        .into_generator()
+        .into_root_schema_for::<ContentFrame>();
+    schema.schema.metadata().id = Some(format!(
+        "https://github.com/sylow-solutions/adaptogen/schema/v{SCHEMA_VERSION}/content-frame.schema.json"
+    ));
+    let mut schema = serde_json::to_value(schema).expect("schemas always serialize");
+
+    if let Some(variants) = schema
+        .pointer_mut("/definitions/ContentBlock/oneOf")
+        .and_then(|v| v.as_array_mut())
+    {
+        variants.push(json!({
+            "description": "A block of a type not known to this version of the format, kept as is",
+            "type": "object",
+            "required": ["type"],
+            "properties": {"type": {"type": "string", "not": {"enum": BLOCK_TYPES}}}
+        }));
+    }
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::normalized::{
+        Citation, CitationSource, ContentBlock, ContentResultBlock, SafetyRating, SafetyReport, TokenLogprob,
+        TopLogprob, Usage,
+    };
+
+    const PUBLISHED_SCHEMA: &str = include_str!("../schema/v2/content-frame.schema.json");
+
+    #[test]
+    fn test_published_schema_is_current() {
+        let schema = content_frame_schema();
+
+        // Regenerate with ADAPTOGEN_UPDATE_SCHEMA=1 cargo test --features schema
+        if std::env::var_os("ADAPTOGEN_UPDATE_SCHEMA").is_some() {
+            let path = concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v2/content-frame.schema.json");
+            std::fs::write(path, serde_json::to_string_pretty(&schema).unwrap() + "\n").unwrap();
+            return;
+        }
+
+        let published: Value = serde_json::from_str(PUBLISHED_SCHEMA).unwrap();
+        assert_eq!(published, schema, "schema/v2/content-frame.schema.json is out of date");
+    }
+
+    #[test]
+    fn test_every_variant_validates() {
+        let validator = jsonschema::validator_for(&content_frame_schema()).unwrap();
+
+        let blocks = vec![
+            ContentBlock::text("plain"),
+            ContentBlock::Text {
+                text: "cited".to_string(),
+                citations: vec![Citation {
+                    cited_text: Some("source text".to_string()),
+                    start: Some(0),
+                    end: Some(5),
+                    sources: vec![CitationSource {
+                        id: Some("doc_1".to_string()),
+                        title: Some("Doc".to_string()),
+                        url: Some("https://example.com".to_string()),
+                    }],
+                }],
+                logprobs: vec![TokenLogprob {
+                    token: "cited".to_string(),
+                    logprob: -0.25,
+                    offset: 0,
+                    bytes: Some(b"cited".to_vec()),
+                    top_logprobs: vec![TopLogprob {
+                        token: "cited".to_string(),
+                        logprob: -0.25,
+                        bytes: None,
+                    }],
+                }],
+            },
+            ContentBlock::ToolUse {
+                id: "call_1".to_string(),
+                name: "get_weather".to_string(),
+                input: json!({"city": "Paris"}),
+            },
+            ContentBlock::ToolResult {
+                tool_use_id: "call_1".to_string(),
+                content: vec![ContentResultBlock {
+                    content: "Sunny".to_string(),
+                }],
+                is_error: false,
+            },
+            ContentBlock::ServerToolUse {
+                id: "srvtoolu_1".to_string(),
+                name: "web_search".to_string(),
+                input: json!({"query": "weather"}),
+            },
+            ContentBlock::ServerToolResult {
+                tool_use_id: "srvtoolu_1".to_string(),
+                content: json!([{"url": "https://example.com"}]),
+                is_error: false,
+            },
+            ContentBlock::Refusal {
+                refusal: "I can't help with that.".to_string(),
+            },
+            ContentBlock::Thinking {
+                thinking: Some("Hmm".to_string()),
+                signature: Some("sig".to_string()),
+            },
+            ContentBlock::Thinking {
+                thinking: None,
+                signature: None,
+            },
+            ContentBlock::Unknown {
+                raw: json!({"type": "image", "url": "https://example.com/cat.png"}),
+            },
+        ];
+
+        let frame = ContentFrame {
+            schema_version: SCHEMA_VERSION,
+            id: "msg_1".to_string(),
+            model: "model".to_string(),
+            blocks,
+            stop_reason: Some("end_turn".to_string()),
+            usage: Some(Usage {
+                input_tokens: 1,
+                output_tokens: 2,
+            }),
+            safety: Some(SafetyReport {
+                blocked: false,
+                reason: None,
+                ratings: vec![SafetyRating {
+                    category: "hate".to_string(),
+                    severity: Some("safe".to_string()),
+                    filtered: false,
+                    prompt: true,
+                }],
+            }),
+        };
+
+        for frame in [frame, ContentFrame::default()] {
+            let instance = serde_json::to_value(&frame).unwrap();
+            let errors: Vec<String> = validator.iter_errors(&instance).map(|e| e.to_string()).collect();
+            assert!(errors.is_empty(), "{errors:?}");
+        }
+
+        // Unknown block types are accepted, but known ones must be complete
+        let unknown = json!({"id": "1", "model": "m", "blocks": [{"type": "image"}]});
+        assert!(validator.is_valid(&unknown));
+        let invalid = json!({"id": "1", "model": "m", "blocks": [{"type": "tool_use", "id": "1"}]});
+        assert!(!validator.is_valid(&invalid));
+    }
+}
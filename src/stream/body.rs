@@ -0,0 +1,382 @@
+//! Async adapter over streamed response bodies
+//!
+//! [`EventStream`] reads a provider's server-sent events from an HTTP body,
+//! given as a [`Stream`] of byte chunks (as returned by HTTP clients such as
+//! `reqwest`) or as an [`AsyncRead`], and yields [`StreamEvent`]s as they
+//! arrive, followed by the [`ContentFrame`] of the complete response.
+//!
+//! Requires the `async` feature.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use adaptogen::stream::{EventStream, StreamEvent, StreamItem};
+//! use futures_util::{Stream, StreamExt};
+//!
+//! # async fn example(
+//! #     body: impl Stream<Item = Result<Vec<u8>, std::io::Error>> + Unpin,
+//! # ) -> Result<(), Box<dyn std::error::Error>> {
+//! let mut events = EventStream::new(body);
+//! while let Some(item) = events.next().await {
+//!     match item? {
+//!         StreamItem::Event(StreamEvent::TextDelta { text, .. }) => print!("{text}"),
+//!         StreamItem::Event(_) => {}
+//!         StreamItem::Frame(frame) => println!("\n{} blocks", frame.blocks.len()),
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use tokio::io::{AsyncRead, ReadBuf};
+
+use super::{StreamEvent, StreamParser};
+use crate::normalized::ContentFrame;
+use crate::parser::ParseError;
+use crate::registry::ParserRegistry;
+
+/// Bytes requested from an [`AsyncRead`] at a time
+const READ_SIZE: usize = 8 * 1024;
+
+/// An item of an [`EventStream`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamItem {
+    /// An event of the response, as it arrives
+    Event(StreamEvent),
+    /// The complete response, after the last event
+    Frame(ContentFrame),
+}
+
+/// Error reading a streamed response
+#[derive(Debug, thiserror::Error)]
+pub enum StreamError {
+    /// The underlying body failed, ending the stream
+    #[error("Failed to read stream: {0}")]
+    Source(Box<dyn Error + Send + Sync>),
+
+    /// The complete response could not be parsed into a frame
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+}
+
+/// Stream of the events and final frame of a streamed response
+///
+/// Chunks of the body may split lines and UTF-8 sequences anywhere. The
+/// last item is the frame, or a [`StreamError::Parse`] if the complete
+/// response does not parse, for example after a provider error event. If the
+/// body itself fails, the error is the last item.
+///
+/// The body must be [`Unpin`]; pin it with `Box::pin` if it is not.
+pub struct EventStream<'a, S> {
+    source: S,
+    parser: StreamParser,
+    registry: &'a ParserRegistry,
+    model: Option<String>,
+    pending: VecDeque<Result<StreamItem, StreamError>>,
+    done: bool,
+}
+
+impl<S> EventStream<'static, S> {
+    /// Events of a body given as a stream of byte chunks
+    ///
+    /// The final frame is parsed with the [default registry](crate::default_registry).
+    pub fn new(source: S) -> Self {
+        EventStream {
+            source,
+            parser: StreamParser::new(),
+            registry: crate::default_registry(),
+            model: None,
+            pending: VecDeque::new(),
+            done: false,
+        }
+    }
+}
+
+impl<R> EventStream<'static, ReaderChunks<R>> {
+    /// Events of a body given as an [`AsyncRead`]
+    pub fn from_reader(reader: R) -> Self {
+        Self::new(ReaderChunks::new(reader))
+    }
+}
+
+impl<'a, S> EventStream<'a, S> {
+    /// Parse the final frame with `registry` instead of the default registry
+    pub fn registry<'b>(self, registry: &'b ParserRegistry) -> EventStream<'b, S> {
+        EventStream {
+            source: self.source,
+            parser: self.parser,
+            registry,
+            model: self.model,
+            pending: self.pending,
+            done: self.done,
+        }
+    }
+
+    /// The model the request was sent to, for streams that do not name it
+    ///
+    /// See [`ParserRegistry::parse_with_model`].
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    fn finish(&mut self) {
+        self.done = true;
+        self.pending.extend(
+            self.parser
+                .finish()
+                .into_iter()
+                .map(|event| Ok(StreamItem::Event(event))),
+        );
+        let frame = match &self.model {
+            Some(model) => self.parser.frame_with_model(self.registry, model),
+            None => self.parser.frame(self.registry),
+        };
+        self.pending
+            .push_back(frame.map(StreamItem::Frame).map_err(StreamError::from));
+    }
+}
+
+impl<S, B, E> Stream for EventStream<'_, S>
+where
+    S: Stream<Item = Result<B, E>> + Unpin,
+    B: AsRef<[u8]>,
+    E: Into<Box<dyn Error + Send + Sync>>,
+{
+    type Item = Result<StreamItem, StreamError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(item) = this.pending.pop_front() {
+                return Poll::Ready(Some(item));
+            }
+            if this.done {
+                return Poll::Ready(None);
+            }
+
+            match Pin::new(&mut this.source).poll_next(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Some(Ok(chunk))) => {
+                    let events = this.parser.push_bytes(chunk.as_ref());
+                    this.pending
+                        .extend(events.into_iter().map(|event| Ok(StreamItem::Event(event))));
+                }
+                Poll::Ready(Some(Err(error))) => {
+                    this.done = true;
+                    this.pending
+                        .push_back(Err(StreamError::Source(error.into())));
+                }
+                Poll::Ready(None) => this.finish(),
+            }
+        }
+    }
+}
+
+impl<S> std::fmt::Debug for EventStream<'_, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventStream")
+            .field("parser", &self.parser)
+            .field("model", &self.model)
+            .field("done", &self.done)
+            .finish()
+    }
+}
+
+/// Stream of the chunks read from an [`AsyncRead`]
+#[derive(Debug)]
+pub struct ReaderChunks<R> {
+    reader: R,
+    buffer: Box<[u8]>,
+}
+
+impl<R> ReaderChunks<R> {
+    /// Chunks of `reader`, until it reaches its end
+    pub fn new(reader: R) -> Self {
+        ReaderChunks {
+            reader,
+            buffer: vec![0; READ_SIZE].into_boxed_slice(),
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for ReaderChunks<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let mut buffer = ReadBuf::new(&mut this.buffer);
+        match Pin::new(&mut this.reader).poll_read(cx, &mut buffer) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Err(error)) => Poll::Ready(Some(Err(error))),
+            Poll::Ready(Ok(())) if buffer.filled().is_empty() => Poll::Ready(None),
+            Poll::Ready(Ok(())) => Poll::Ready(Some(Ok(buffer.filled().to_vec()))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::{stream, StreamExt};
+
+    const CAPTURE: &str = concat!(
+        "event: message_start\r\ndata: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\",\"model\":\"acme-1\",\"content\":[]}}\r\n\r\n",
+        "event: content_block_start\r\ndata: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\r\n\r\n",
+        "event: content_block_delta\r\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Grüße 🦀\"}}\r\n\r\n",
+        "event: content_block_stop\r\ndata: {\"type\":\"content_block_stop\",\"index\":0}\r\n\r\n",
+        "event: message_stop\r\ndata: {\"type\":\"message_stop\"}\r\n\r\n",
+    );
+
+    struct AcmeParser;
+
+    impl crate::parser::ModelResponseParser for AcmeParser {
+        fn supported_models(&self) -> Vec<String> {
+            vec!["acme-1".to_string()]
+        }
+
+        fn parse(&self, raw_response: &str) -> Result<ContentFrame, ParseError> {
+            let json: serde_json::Value = serde_json::from_str(raw_response)?;
+            let text = json
+                .pointer("/content/0/text")
+                .and_then(|t| t.as_str())
+                .unwrap_or_default();
+            Ok(ContentFrame::new(
+                "msg_1",
+                "acme-1",
+                vec![crate::normalized::ContentBlock::text(text)],
+            ))
+        }
+    }
+
+    /// Reads a few bytes at a time, returning `Pending` before every read
+    struct TrickleReader {
+        data: &'static [u8],
+        ready: bool,
+    }
+
+    impl AsyncRead for TrickleReader {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            if !self.ready {
+                self.ready = true;
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            self.ready = false;
+            let len = self.data.len().min(3).min(buf.remaining());
+            buf.put_slice(&self.data[..len]);
+            self.data = &self.data[len..];
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    fn registry() -> ParserRegistry {
+        let registry = ParserRegistry::new();
+        registry.register_parser(std::sync::Arc::new(AcmeParser));
+        registry
+    }
+
+    fn text_of(items: &[StreamItem]) -> String {
+        items
+            .iter()
+            .filter_map(|item| match item {
+                StreamItem::Event(StreamEvent::TextDelta { text, .. }) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_reader() {
+        let registry = registry();
+        let reader = TrickleReader {
+            data: CAPTURE.as_bytes(),
+            ready: false,
+        };
+        let items: Vec<_> = block_on(
+            EventStream::from_reader(reader)
+                .registry(&registry)
+                .collect::<Vec<_>>(),
+        );
+        let items: Vec<StreamItem> = items.into_iter().map(Result::unwrap).collect();
+
+        assert_eq!(text_of(&items), "Grüße 🦀");
+        assert!(matches!(
+            items[0],
+            StreamItem::Event(StreamEvent::MessageStart { .. })
+        ));
+        match items.last() {
+            Some(StreamItem::Frame(frame)) => assert_eq!(frame.text(), "Grüße 🦀"),
+            other => panic!("Expected a frame, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_chunk_stream() {
+        let registry = registry();
+        // Two-byte chunks split the multi-byte characters
+        let chunks = CAPTURE
+            .as_bytes()
+            .chunks(2)
+            .map(|chunk| Ok::<_, io::Error>(chunk.to_vec()));
+        let items: Vec<_> = block_on(
+            EventStream::new(stream::iter(chunks))
+                .registry(&registry)
+                .collect::<Vec<_>>(),
+        );
+        let items: Vec<StreamItem> = items.into_iter().map(Result::unwrap).collect();
+        assert_eq!(text_of(&items), "Grüße 🦀");
+        assert!(matches!(items.last(), Some(StreamItem::Frame(_))));
+    }
+
+    #[test]
+    fn test_errors() {
+        let registry = registry();
+        let chunks = vec![
+            Ok(CAPTURE.as_bytes()[..40].to_vec()),
+            Err(io::Error::other("connection reset")),
+        ];
+        let items: Vec<_> = block_on(
+            EventStream::new(stream::iter(chunks))
+                .registry(&registry)
+                .collect::<Vec<_>>(),
+        );
+        assert!(
+            matches!(items.last(), Some(Err(StreamError::Source(error))) if error.to_string() == "connection reset")
+        );
+
+        // The model is unknown to the registry
+        let chunks = vec![Ok::<_, io::Error>(CAPTURE.as_bytes().to_vec())];
+        let items: Vec<_> = block_on(
+            EventStream::new(stream::iter(chunks))
+                .registry(&ParserRegistry::new())
+                .collect::<Vec<_>>(),
+        );
+        assert!(matches!(
+            items.last(),
+            Some(Err(StreamError::Parse(ParseError::UnsupportedModel(_))))
+        ));
+        assert!(matches!(
+            items[items.len() - 2],
+            Ok(StreamItem::Event(StreamEvent::MessageStop { .. }))
+        ));
+    }
+}
@@ -0,0 +1,640 @@
+//! Re-encoding of stream events in a provider's streaming format
+//!
+//! [`SseEncoder`] turns [`StreamEvent`]s, from any provider's stream, back
+//! into the server-sent events of OpenAI Chat Completions or Anthropic
+//! Messages, so clients written for one of those formats can be served from
+//! any backend.
+
+use std::collections::{HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+
+use super::SseEvent;
+use crate::normalized::{BlockKind, StreamEvent, Usage};
+
+/// Streaming format to encode events in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StreamFormat {
+    /// OpenAI Chat Completions `chat.completion.chunk` events, ending with `[DONE]`
+    ///
+    /// Thinking is sent as `reasoning_content` deltas, as DeepSeek and other
+    /// compatible APIs do.
+    OpenAi,
+    /// Anthropic Messages events, from `message_start` to `message_stop`
+    ///
+    /// Refusals are sent as text blocks.
+    Anthropic,
+}
+
+/// Why a response stopped, as far as both formats can tell apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Finish {
+    EndTurn,
+    MaxTokens,
+    ToolUse,
+    StopSequence,
+    Refusal,
+}
+
+impl Finish {
+    /// Classify a provider's raw stop reason
+    ///
+    /// Some providers, such as Gemini, stop with their usual reason after a
+    /// tool call; a response with tool calls that ended normally stopped for
+    /// tool use.
+    fn of(stop_reason: Option<&str>, tool_calls: bool) -> Finish {
+        let finish = match stop_reason.map(str::to_ascii_lowercase).as_deref() {
+            Some("length" | "max_tokens" | "max_output_tokens" | "model_length") => {
+                Finish::MaxTokens
+            }
+            Some("tool_calls" | "tool_use" | "tool_call" | "function_call") => Finish::ToolUse,
+            Some("stop_sequence") => Finish::StopSequence,
+            Some(
+                "content_filter" | "refusal" | "safety" | "recitation" | "blocklist"
+                | "prohibited_content" | "spii",
+            ) => Finish::Refusal,
+            _ => Finish::EndTurn,
+        };
+        if tool_calls && finish == Finish::EndTurn {
+            Finish::ToolUse
+        } else {
+            finish
+        }
+    }
+
+    fn openai(self) -> &'static str {
+        match self {
+            Finish::EndTurn | Finish::StopSequence => "stop",
+            Finish::MaxTokens => "length",
+            Finish::ToolUse => "tool_calls",
+            Finish::Refusal => "content_filter",
+        }
+    }
+
+    fn anthropic(self) -> &'static str {
+        match self {
+            Finish::EndTurn => "end_turn",
+            Finish::MaxTokens => "max_tokens",
+            Finish::ToolUse => "tool_use",
+            Finish::StopSequence => "stop_sequence",
+            Finish::Refusal => "refusal",
+        }
+    }
+}
+
+/// Encoder of stream events as a provider's server-sent events
+///
+/// Feed the events of one response, in order, to [`encode`](Self::encode) and
+/// send the returned server-sent events to the client; their
+/// [`Display`](std::fmt::Display) is the wire format. Stop reasons are mapped
+/// to the closest reason of the target format, and the usage of the last
+/// [`UsageUpdate`](StreamEvent::UsageUpdate) is sent with the end of the
+/// message.
+///
+/// # Examples
+///
+/// ```
+/// use adaptogen::stream::{SseEncoder, StreamEvent, StreamFormat, StreamParser};
+///
+/// let mut parser = StreamParser::new();
+/// let mut events = parser.push(concat!(
+///     "event: message_start\ndata: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\",\"model\":\"claude-sonnet-4-5\"}}\n\n",
+///     "event: content_block_start\ndata: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+///     "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hi\"}}\n\n",
+///     "event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n",
+/// ));
+/// events.extend(parser.finish());
+///
+/// let mut encoder = SseEncoder::new(StreamFormat::OpenAi);
+/// let body: String = events
+///     .iter()
+///     .flat_map(|event| encoder.encode(event))
+///     .map(|sse| sse.to_string())
+///     .collect();
+/// assert!(body.contains("\"object\":\"chat.completion.chunk\""));
+/// assert!(body.ends_with("data: [DONE]\n\n"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SseEncoder {
+    format: StreamFormat,
+    created: u64,
+    id: String,
+    model: String,
+    usage: Option<Usage>,
+    /// OpenAI tool call index of each tool use block
+    tool_calls: HashMap<usize, usize>,
+    refusals: HashSet<usize>,
+}
+
+impl SseEncoder {
+    /// An encoder at the start of a response
+    pub fn new(format: StreamFormat) -> Self {
+        SseEncoder {
+            format,
+            created: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            id: String::new(),
+            model: String::new(),
+            usage: None,
+            tool_calls: HashMap::new(),
+            refusals: HashSet::new(),
+        }
+    }
+
+    /// Unix timestamp to send as `created` in OpenAI chunks, instead of the current time
+    pub fn created(mut self, created: u64) -> Self {
+        self.created = created;
+        self
+    }
+
+    /// The server-sent events of `event` in the target format
+    ///
+    /// Events with no counterpart in the format, such as the start and stop
+    /// of a text block in OpenAI chunks, give no server-sent events.
+    pub fn encode(&mut self, event: &StreamEvent) -> Vec<SseEvent> {
+        match event {
+            StreamEvent::MessageStart { id, model } => {
+                self.id = id.clone();
+                self.model = model.clone();
+            }
+            StreamEvent::BlockStart { index, kind } => match kind {
+                BlockKind::ToolUse { .. } => {
+                    let position = self.tool_calls.len();
+                    self.tool_calls.insert(*index, position);
+                }
+                BlockKind::Refusal => {
+                    self.refusals.insert(*index);
+                }
+                BlockKind::Text | BlockKind::Thinking => {}
+            },
+            StreamEvent::UsageUpdate { usage } => self.usage = Some(*usage),
+            _ => {}
+        }
+
+        match self.format {
+            StreamFormat::OpenAi => self.encode_openai(event),
+            StreamFormat::Anthropic => self.encode_anthropic(event),
+        }
+    }
+
+    fn encode_openai(&self, event: &StreamEvent) -> Vec<SseEvent> {
+        match event {
+            StreamEvent::MessageStart { .. } => {
+                vec![self.openai_chunk(json!({"role": "assistant", "content": ""}), None)]
+            }
+            StreamEvent::BlockStart {
+                index,
+                kind: BlockKind::ToolUse { id, name },
+            } => {
+                let call = json!({
+                    "index": self.tool_calls[index],
+                    "id": id,
+                    "type": "function",
+                    "function": {"name": name, "arguments": ""},
+                });
+                vec![self.openai_chunk(json!({"tool_calls": [call]}), None)]
+            }
+            StreamEvent::TextDelta { index, text } if self.refusals.contains(index) => {
+                vec![self.openai_chunk(json!({"refusal": text}), None)]
+            }
+            StreamEvent::TextDelta { text, .. } => {
+                vec![self.openai_chunk(json!({"content": text}), None)]
+            }
+            StreamEvent::ThinkingDelta { thinking, .. } => {
+                vec![self.openai_chunk(json!({"reasoning_content": thinking}), None)]
+            }
+            StreamEvent::ToolInputDelta {
+                index,
+                partial_json,
+            } => {
+                let call = json!({
+                    "index": self.tool_calls.get(index).copied().unwrap_or_default(),
+                    "function": {"arguments": partial_json},
+                });
+                vec![self.openai_chunk(json!({"tool_calls": [call]}), None)]
+            }
+            StreamEvent::MessageStop { stop_reason } => {
+                let finish = Finish::of(stop_reason.as_deref(), !self.tool_calls.is_empty());
+                let mut events = vec![self.openai_chunk(json!({}), Some(finish.openai()))];
+                if let Some(usage) = self.usage {
+                    let chunk = json!({
+                        "id": self.id,
+                        "object": "chat.completion.chunk",
+                        "created": self.created,
+                        "model": self.model,
+                        "choices": [],
+                        "usage": {
+                            "prompt_tokens": usage.input_tokens,
+                            "completion_tokens": usage.output_tokens,
+                            "total_tokens": usage.total_tokens(),
+                        },
+                    });
+                    events.push(data(None, chunk));
+                }
+                events.push(SseEvent {
+                    event: None,
+                    data: "[DONE]".to_string(),
+                });
+                events
+            }
+            StreamEvent::Error { message } => {
+                vec![data(
+                    None,
+                    json!({"error": {"message": message, "type": "server_error", "param": null, "code": null}}),
+                )]
+            }
+            StreamEvent::BlockStart { .. }
+            | StreamEvent::BlockStop { .. }
+            | StreamEvent::UsageUpdate { .. } => Vec::new(),
+        }
+    }
+
+    fn openai_chunk(&self, delta: Value, finish_reason: Option<&str>) -> SseEvent {
+        let chunk = json!({
+            "id": self.id,
+            "object": "chat.completion.chunk",
+            "created": self.created,
+            "model": self.model,
+            "choices": [{"index": 0, "delta": delta, "finish_reason": finish_reason}],
+        });
+        data(None, chunk)
+    }
+
+    fn encode_anthropic(&self, event: &StreamEvent) -> Vec<SseEvent> {
+        let event = match event {
+            StreamEvent::MessageStart { id, model } => json!({
+                "type": "message_start",
+                "message": {
+                    "id": id,
+                    "type": "message",
+                    "role": "assistant",
+                    "model": model,
+                    "content": [],
+                    "stop_reason": null,
+                    "stop_sequence": null,
+                    "usage": {"input_tokens": self.usage.map(|u| u.input_tokens).unwrap_or_default(), "output_tokens": 0},
+                },
+            }),
+            StreamEvent::BlockStart { index, kind } => {
+                let block = match kind {
+                    BlockKind::Text | BlockKind::Refusal => json!({"type": "text", "text": ""}),
+                    BlockKind::Thinking => json!({"type": "thinking", "thinking": ""}),
+                    BlockKind::ToolUse { id, name } => {
+                        json!({"type": "tool_use", "id": id, "name": name, "input": {}})
+                    }
+                };
+                json!({"type": "content_block_start", "index": index, "content_block": block})
+            }
+            StreamEvent::TextDelta { index, text } => json!({
+                "type": "content_block_delta",
+                "index": index,
+                "delta": {"type": "text_delta", "text": text},
+            }),
+            StreamEvent::ThinkingDelta { index, thinking } => json!({
+                "type": "content_block_delta",
+                "index": index,
+                "delta": {"type": "thinking_delta", "thinking": thinking},
+            }),
+            StreamEvent::ToolInputDelta {
+                index,
+                partial_json,
+            } => json!({
+                "type": "content_block_delta",
+                "index": index,
+                "delta": {"type": "input_json_delta", "partial_json": partial_json},
+            }),
+            StreamEvent::BlockStop { index } => {
+                json!({"type": "content_block_stop", "index": index})
+            }
+            StreamEvent::UsageUpdate { .. } => return Vec::new(),
+            StreamEvent::MessageStop { stop_reason } => {
+                let finish = Finish::of(stop_reason.as_deref(), !self.tool_calls.is_empty());
+                let usage = self.usage.unwrap_or_default();
+                let delta = json!({
+                    "type": "message_delta",
+                    "delta": {"stop_reason": finish.anthropic(), "stop_sequence": null},
+                    "usage": {"input_tokens": usage.input_tokens, "output_tokens": usage.output_tokens},
+                });
+                return vec![
+                    data(Some("message_delta"), delta),
+                    data(Some("message_stop"), json!({"type": "message_stop"})),
+                ];
+            }
+            StreamEvent::Error { message } => json!({
+                "type": "error",
+                "error": {"type": "api_error", "message": message},
+            }),
+        };
+        let name = event["type"].as_str().map(str::to_string);
+        vec![SseEvent {
+            event: name,
+            data: event.to_string(),
+        }]
+    }
+}
+
+fn data(event: Option<&str>, data: Value) -> SseEvent {
+    SseEvent {
+        event: event.map(str::to_string),
+        data: data.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stream::{SseDecoder, StreamParser};
+
+    fn events() -> Vec<StreamEvent> {
+        vec![
+            StreamEvent::MessageStart {
+                id: "resp_1".to_string(),
+                model: "gemini-2.5-flash".to_string(),
+            },
+            StreamEvent::BlockStart {
+                index: 0,
+                kind: BlockKind::Thinking,
+            },
+            StreamEvent::ThinkingDelta {
+                index: 0,
+                thinking: "Weather first".to_string(),
+            },
+            StreamEvent::BlockStop { index: 0 },
+            StreamEvent::BlockStart {
+                index: 1,
+                kind: BlockKind::Text,
+            },
+            StreamEvent::TextDelta {
+                index: 1,
+                text: "Checking.".to_string(),
+            },
+            StreamEvent::BlockStop { index: 1 },
+            StreamEvent::BlockStart {
+                index: 2,
+                kind: BlockKind::ToolUse {
+                    id: "call_0".to_string(),
+                    name: "get_weather".to_string(),
+                },
+            },
+            StreamEvent::ToolInputDelta {
+                index: 2,
+                partial_json: "{\"city\":".to_string(),
+            },
+            StreamEvent::ToolInputDelta {
+                index: 2,
+                partial_json: "\"Paris\"}".to_string(),
+            },
+            StreamEvent::BlockStop { index: 2 },
+            StreamEvent::BlockStart {
+                index: 3,
+                kind: BlockKind::ToolUse {
+                    id: "call_1".to_string(),
+                    name: "get_time".to_string(),
+                },
+            },
+            StreamEvent::ToolInputDelta {
+                index: 3,
+                partial_json: "{}".to_string(),
+            },
+            StreamEvent::BlockStop { index: 3 },
+            StreamEvent::UsageUpdate {
+                usage: Usage {
+                    input_tokens: 12,
+                    output_tokens: 30,
+                },
+            },
+            StreamEvent::MessageStop {
+                stop_reason: Some("STOP".to_string()),
+            },
+        ]
+    }
+
+    fn encode(format: StreamFormat, events: &[StreamEvent]) -> String {
+        let mut encoder = SseEncoder::new(format).created(1700000000);
+        events
+            .iter()
+            .flat_map(|event| encoder.encode(event))
+            .map(|sse| sse.to_string())
+            .collect()
+    }
+
+    /// Decode an encoded stream again
+    fn decode(capture: &str) -> Vec<StreamEvent> {
+        let mut parser = StreamParser::new();
+        let mut events = parser.push(capture);
+        events.extend(parser.finish());
+        events
+    }
+
+    /// The events without usage updates, which formats send at different points, and the last usage
+    fn split_usage(events: Vec<StreamEvent>) -> (Vec<StreamEvent>, Option<Usage>) {
+        let usage = events.iter().rev().find_map(|e| match e {
+            StreamEvent::UsageUpdate { usage } => Some(*usage),
+            _ => None,
+        });
+        (
+            events
+                .into_iter()
+                .filter(|e| !matches!(e, StreamEvent::UsageUpdate { .. }))
+                .collect(),
+            usage,
+        )
+    }
+
+    /// The test events, with the stop reason given by a format
+    fn expected(stop_reason: &str) -> (Vec<StreamEvent>, Option<Usage>) {
+        let mut events = events();
+        *events.last_mut().unwrap() = StreamEvent::MessageStop {
+            stop_reason: Some(stop_reason.to_string()),
+        };
+        split_usage(events)
+    }
+
+    #[test]
+    fn test_encode_openai() {
+        let capture = encode(StreamFormat::OpenAi, &events());
+        let chunks: Vec<Value> = SseDecoder::decode(&capture)
+            .iter()
+            .filter(|sse| sse.data != "[DONE]")
+            .map(|sse| serde_json::from_str(&sse.data).unwrap())
+            .collect();
+
+        assert!(chunks
+            .iter()
+            .all(|c| c["object"] == "chat.completion.chunk" && c["created"] == 1700000000));
+        assert_eq!(
+            chunks[0]["choices"][0]["delta"],
+            json!({"role": "assistant", "content": ""})
+        );
+        assert_eq!(
+            chunks[3]["choices"][0]["delta"]["tool_calls"][0],
+            json!({"index": 0, "id": "call_0", "type": "function", "function": {"name": "get_weather", "arguments": ""}})
+        );
+        assert_eq!(
+            chunks[6]["choices"][0]["delta"]["tool_calls"][0]["index"],
+            1
+        );
+        assert_eq!(
+            chunks[7]["choices"][0]["delta"]["tool_calls"][0],
+            json!({"index": 1, "function": {"arguments": "{}"}})
+        );
+        assert_eq!(chunks[8]["choices"][0]["finish_reason"], "tool_calls");
+        assert_eq!(
+            chunks[9]["usage"],
+            json!({"prompt_tokens": 12, "completion_tokens": 30, "total_tokens": 42})
+        );
+        assert!(capture.ends_with("data: [DONE]\n\n"));
+
+        assert_eq!(split_usage(decode(&capture)), expected("tool_calls"));
+    }
+
+    #[test]
+    fn test_encode_anthropic() {
+        let capture = encode(StreamFormat::Anthropic, &events());
+        let sse = SseDecoder::decode(&capture);
+        let names: Vec<&str> = sse.iter().map(|e| e.event.as_deref().unwrap()).collect();
+        assert_eq!(names.first(), Some(&"message_start"));
+        assert_eq!(names[names.len() - 2..], ["message_delta", "message_stop"]);
+        assert!(sse
+            .iter()
+            .all(|e| serde_json::from_str::<Value>(&e.data).unwrap()["type"]
+                == e.event.as_deref().unwrap()));
+
+        let delta: Value = serde_json::from_str(&sse[sse.len() - 2].data).unwrap();
+        assert_eq!(delta["delta"]["stop_reason"], "tool_use");
+        assert_eq!(
+            delta["usage"],
+            json!({"input_tokens": 12, "output_tokens": 30})
+        );
+
+        assert_eq!(split_usage(decode(&capture)), expected("tool_use"));
+    }
+
+    #[test]
+    fn test_refusals_and_errors() {
+        let events = vec![
+            StreamEvent::MessageStart {
+                id: "chatcmpl-1".to_string(),
+                model: "gpt-4o".to_string(),
+            },
+            StreamEvent::BlockStart {
+                index: 0,
+                kind: BlockKind::Refusal,
+            },
+            StreamEvent::TextDelta {
+                index: 0,
+                text: "I can't help with that.".to_string(),
+            },
+            StreamEvent::BlockStop { index: 0 },
+            StreamEvent::MessageStop {
+                stop_reason: Some("content_filter".to_string()),
+            },
+        ];
+        assert_eq!(decode(&encode(StreamFormat::OpenAi, &events)), events);
+
+        // Anthropic has no refusal blocks
+        let (decoded, _) = split_usage(decode(&encode(StreamFormat::Anthropic, &events)));
+        assert_eq!(
+            decoded[1],
+            StreamEvent::BlockStart {
+                index: 0,
+                kind: BlockKind::Text
+            }
+        );
+        assert_eq!(
+            decoded.last(),
+            Some(&StreamEvent::MessageStop {
+                stop_reason: Some("refusal".to_string())
+            })
+        );
+
+        let failed = [
+            events[0].clone(),
+            StreamEvent::Error {
+                message: "Overloaded".to_string(),
+            },
+        ];
+        for format in [StreamFormat::OpenAi, StreamFormat::Anthropic] {
+            let decoded = decode(&encode(format, &failed));
+            assert!(
+                matches!(decoded.last(), Some(StreamEvent::Error { message }) if message.contains("Overloaded")),
+                "{format:?}"
+            );
+        }
+    }
+
+    /// Streams in the corpus survive re-encoding in either format
+    #[cfg(all(
+        feature = "anthropic",
+        feature = "openai",
+        feature = "gemini",
+        feature = "cohere",
+        feature = "mistral"
+    ))]
+    #[test]
+    fn test_reencode_corpus() {
+        use crate::normalized::ContentFrame;
+        use crate::parser::ModelResponseParser;
+        use crate::providers::{AnthropicParser, OpenAiParser};
+        use crate::registry::ParserRegistry;
+
+        let registry = ParserRegistry::with_defaults();
+        let fixtures =
+            crate::testkit::FixtureSet::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures"))
+                .fixtures()
+                .unwrap();
+
+        let mut checked = 0;
+        for fixture in fixtures.iter().filter(|f| f.is_stream()) {
+            let Ok(frame) = fixture.parse_with_registry(&registry) else {
+                continue;
+            };
+            checked += 1;
+            let events = decode(&fixture.raw);
+            let parsers: [(StreamFormat, &dyn ModelResponseParser); 2] = [
+                (StreamFormat::OpenAi, &OpenAiParser),
+                (StreamFormat::Anthropic, &AnthropicParser),
+            ];
+            for (format, parser) in parsers {
+                let mut stream = StreamParser::new();
+                stream.push(&encode(format, &events));
+                stream.finish();
+                let reencoded = parser.parse(&stream.body().unwrap().to_string()).unwrap();
+
+                assert_eq!(
+                    reencoded.text(),
+                    frame.text(),
+                    "{} as {format:?}",
+                    fixture.name
+                );
+                assert_eq!(
+                    reencoded.thinking(),
+                    frame.thinking(),
+                    "{} as {format:?}",
+                    fixture.name
+                );
+                let calls = |frame: &ContentFrame| -> Vec<(String, String, Value)> {
+                    frame
+                        .tool_uses()
+                        .map(|t| (t.id.to_string(), t.name.to_string(), t.input.clone()))
+                        .collect()
+                };
+                assert_eq!(
+                    calls(&reencoded),
+                    calls(&frame),
+                    "{} as {format:?}",
+                    fixture.name
+                );
+                assert_eq!(
+                    reencoded.usage, frame.usage,
+                    "{} as {format:?}",
+                    fixture.name
+                );
+            }
+        }
+        assert!(checked >= 6);
+    }
+}
@@ -0,0 +1,1094 @@
+//! Incremental parsing of streams into delta events
+//!
+//! [`StreamParser`] turns a provider's stream, fed in chunks as it arrives,
+//! into provider-independent [`StreamEvent`]s, and once the stream is complete
+//! into the same [`ContentFrame`] the non-streaming response would give.
+
+use serde_json::Value;
+
+use super::{assemble, Format, SseDecoder, SseEvent};
+use crate::normalized::{BlockKind, ContentFrame, StreamEvent, Usage};
+use crate::parser::ParseError;
+use crate::providers::{detect_provider_error, usage_from};
+use crate::registry::ParserRegistry;
+
+/// Tags around the thinking that some OpenAI-compatible models put at the
+/// start of their content, as split by the OpenAI parser
+const THINK_OPEN: &str = "<think>";
+const THINK_CLOSE: &str = "</think>";
+
+/// Incremental parser of a streamed response
+///
+/// Feed the stream with [`push`](Self::push) or [`push_bytes`](Self::push_bytes)
+/// as it arrives, then call [`finish`](Self::finish). Each call returns the
+/// events that the new data completes. Chunks may split lines, events and
+/// UTF-8 sequences anywhere.
+///
+/// Only the first candidate of a multi-candidate stream is turned into events.
+///
+/// # Examples
+///
+/// ```
+/// use adaptogen::stream::{StreamEvent, StreamParser};
+///
+/// let mut parser = StreamParser::new();
+/// let mut events = parser.push("data: {\"id\":\"1\",\"model\":\"gpt-4o\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Hel");
+/// events.extend(parser.push("lo\"}}]}\n\ndata: [DONE]\n\n"));
+/// events.extend(parser.finish());
+///
+/// assert!(events.contains(&StreamEvent::TextDelta { index: 0, text: "Hello".to_string() }));
+/// # #[cfg(feature = "openai")]
+/// assert_eq!(parser.frame(adaptogen::default_registry()).unwrap().text(), "Hello");
+/// ```
+#[derive(Debug, Default)]
+pub struct StreamParser {
+    decoder: SseDecoder,
+    /// Bytes of an incomplete UTF-8 sequence at the end of the last chunk
+    partial_utf8: Vec<u8>,
+    events: Vec<SseEvent>,
+    format: Option<Format>,
+    started: bool,
+    stopped: bool,
+    failed: bool,
+    /// Key and index of the open block
+    open: Option<(String, usize)>,
+    next_index: usize,
+    stop_reason: Option<String>,
+    usage: Option<Usage>,
+    /// Function calls seen in a Gemini stream, to number calls without ids as the parser does
+    function_calls: usize,
+    /// Position of OpenAI-style string content relative to `<think>` tags
+    think_tags: ThinkTags,
+    /// Content held back until it is known where it belongs
+    held_content: String,
+}
+
+/// Position of streamed content relative to a leading `<think>` section
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum ThinkTags {
+    /// Not yet known whether the content starts with `<think>`
+    #[default]
+    Undecided,
+    /// The content has no leading `<think>` section
+    Absent,
+    /// Inside the `<think>` section
+    Thinking,
+    /// After `</think>`
+    Answer,
+}
+
+impl StreamParser {
+    /// A parser at the start of a stream
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the next piece of the stream
+    pub fn push(&mut self, text: &str) -> Vec<StreamEvent> {
+        let events = self.decoder.push(text);
+        let mut out = Vec::new();
+        for event in events {
+            self.read_event(event, &mut out);
+        }
+        out
+    }
+
+    /// Feed the next chunk of raw bytes, such as a chunk of an HTTP body
+    ///
+    /// A UTF-8 sequence split between chunks is put back together; invalid
+    /// bytes are replaced with U+FFFD.
+    pub fn push_bytes(&mut self, bytes: &[u8]) -> Vec<StreamEvent> {
+        self.partial_utf8.extend_from_slice(bytes);
+        let mut text = String::new();
+        loop {
+            match std::str::from_utf8(&self.partial_utf8) {
+                Ok(valid) => {
+                    text.push_str(valid);
+                    self.partial_utf8.clear();
+                    break;
+                }
+                Err(error) => {
+                    let valid = error.valid_up_to();
+                    text.push_str(
+                        std::str::from_utf8(&self.partial_utf8[..valid]).unwrap_or_default(),
+                    );
+                    match error.error_len() {
+                        // An incomplete sequence at the end may be completed by the next chunk
+                        None => {
+                            self.partial_utf8.drain(..valid);
+                            break;
+                        }
+                        Some(len) => {
+                            text.push(char::REPLACEMENT_CHARACTER);
+                            self.partial_utf8.drain(..valid + len);
+                        }
+                    }
+                }
+            }
+        }
+        self.push(&text)
+    }
+
+    /// End the stream, returning the remaining events
+    ///
+    /// Closes the open block and, unless the stream failed or already ended,
+    /// emits `MessageStop`, without a stop reason if the stream was cut short.
+    pub fn finish(&mut self) -> Vec<StreamEvent> {
+        let mut out = Vec::new();
+        if !self.partial_utf8.is_empty() {
+            self.partial_utf8.clear();
+            out.extend(self.push("\u{fffd}"));
+        }
+        if let Some(event) = self.decoder.finish() {
+            self.read_event(event, &mut out);
+        }
+        if self.started && !self.failed {
+            self.stop(&mut out);
+        }
+        out
+    }
+
+    /// The regular response body rebuilt from the stream so far
+    ///
+    /// See [`assemble`](super::assemble).
+    pub fn body(&self) -> Result<Value, ParseError> {
+        assemble(&self.events)
+    }
+
+    /// The frame of the complete stream, parsed with `registry`
+    ///
+    /// The same frame as the non-streaming response would give. A provider
+    /// error in the stream is returned as [`ParseError::ProviderError`].
+    pub fn frame(&self, registry: &ParserRegistry) -> Result<ContentFrame, ParseError> {
+        registry.parse(&self.body()?.to_string())
+    }
+
+    /// The frame of the complete stream of a model known from the request
+    ///
+    /// Use this for streams that do not name the model, such as Cohere's; see
+    /// [`ParserRegistry::parse_with_model`].
+    pub fn frame_with_model(
+        &self,
+        registry: &ParserRegistry,
+        model: &str,
+    ) -> Result<ContentFrame, ParseError> {
+        registry.parse_with_model(&self.body()?.to_string(), model)
+    }
+
+    fn read_event(&mut self, event: SseEvent, out: &mut Vec<StreamEvent>) {
+        let data = event.data.trim();
+        if data == "[DONE]" {
+            self.stop(out);
+        } else if !data.is_empty() && !self.failed && !self.stopped {
+            // Data that is not JSON fails the final frame; there are no deltas to take from it
+            if let Ok(chunk) = serde_json::from_str::<Value>(data) {
+                self.read_chunk(&chunk, out);
+            }
+        }
+        self.events.push(event);
+    }
+
+    fn read_chunk(&mut self, chunk: &Value, out: &mut Vec<StreamEvent>) {
+        if let Some(error) = detect_provider_error(chunk) {
+            self.fail(error.to_string(), out);
+            return;
+        }
+        if self.format.is_none() {
+            self.format = Format::of(chunk);
+        }
+        match self.format {
+            Some(Format::Anthropic) => self.read_anthropic(chunk, out),
+            Some(Format::OpenAi) => self.read_openai(chunk, out),
+            Some(Format::OpenAiResponses) => self.read_openai_responses(chunk, out),
+            Some(Format::Gemini) => self.read_gemini(chunk, out),
+            Some(Format::Cohere) => self.read_cohere(chunk, out),
+            None => {}
+        }
+    }
+
+    fn read_anthropic(&mut self, chunk: &Value, out: &mut Vec<StreamEvent>) {
+        let key = format!(
+            "{}",
+            chunk.get("index").and_then(|i| i.as_u64()).unwrap_or(0)
+        );
+        let delta = chunk.get("delta").unwrap_or(&Value::Null);
+
+        match str_at(chunk, "/type") {
+            Some("message_start") => {
+                self.start(
+                    str_at(chunk, "/message/id"),
+                    str_at(chunk, "/message/model"),
+                    out,
+                );
+                self.update_usage(
+                    chunk.pointer("/message/usage"),
+                    "input_tokens",
+                    "output_tokens",
+                    out,
+                );
+            }
+            Some("content_block_start") => {
+                let block = chunk.get("content_block").unwrap_or(&Value::Null);
+                let kind = match str_at(block, "/type") {
+                    Some("text") => BlockKind::Text,
+                    Some("thinking") => BlockKind::Thinking,
+                    Some("tool_use") => BlockKind::ToolUse {
+                        id: str_at(block, "/id").unwrap_or_default().to_string(),
+                        name: str_at(block, "/name").unwrap_or_default().to_string(),
+                    },
+                    _ => return,
+                };
+                let index = self.open(&key, kind, out);
+                if let Some(text) = str_at(block, "/text").filter(|t| !t.is_empty()) {
+                    out.push(StreamEvent::TextDelta {
+                        index,
+                        text: text.to_string(),
+                    });
+                }
+            }
+            Some("content_block_delta") => {
+                let Some(index) = self.open_index(&key) else {
+                    return;
+                };
+                match str_at(delta, "/type") {
+                    Some("text_delta") => Self::text(index, str_at(delta, "/text"), out),
+                    Some("thinking_delta") => {
+                        Self::thinking(index, str_at(delta, "/thinking"), out)
+                    }
+                    Some("input_json_delta") => {
+                        Self::tool_input(index, str_at(delta, "/partial_json"), out)
+                    }
+                    _ => {}
+                }
+            }
+            Some("content_block_stop") if self.open_index(&key).is_some() => self.close(out),
+            Some("message_delta") => {
+                self.update_stop_reason(delta.get("stop_reason"));
+                self.update_usage(chunk.get("usage"), "input_tokens", "output_tokens", out);
+            }
+            Some("message_stop") => self.stop(out),
+            _ => {}
+        }
+    }
+
+    fn read_openai(&mut self, chunk: &Value, out: &mut Vec<StreamEvent>) {
+        self.start(str_at(chunk, "/id"), str_at(chunk, "/model"), out);
+
+        let choices = chunk
+            .get("choices")
+            .and_then(|c| c.as_array())
+            .into_iter()
+            .flatten();
+        for choice in choices.filter(|c| c.get("index").and_then(|i| i.as_u64()).unwrap_or(0) == 0)
+        {
+            let delta = choice.get("delta").unwrap_or(&Value::Null);
+
+            for field in ["reasoning_content", "reasoning"] {
+                if let Some(thinking) =
+                    str_at(delta, &format!("/{field}")).filter(|t| !t.is_empty())
+                {
+                    let index = self.open("thinking", BlockKind::Thinking, out);
+                    Self::thinking(index, Some(thinking), out);
+                }
+            }
+            match delta.get("content") {
+                Some(Value::String(text)) if !text.is_empty() => self.read_content(text, out),
+                // Mistral streams thinking as content chunks
+                Some(Value::Array(items)) => {
+                    for item in items {
+                        match str_at(item, "/type") {
+                            Some("text") => {
+                                let index = self.open("text", BlockKind::Text, out);
+                                Self::text(index, str_at(item, "/text"), out);
+                            }
+                            Some("thinking") => {
+                                let index = self.open("thinking", BlockKind::Thinking, out);
+                                for piece in item
+                                    .get("thinking")
+                                    .and_then(|t| t.as_array())
+                                    .into_iter()
+                                    .flatten()
+                                {
+                                    Self::thinking(index, str_at(piece, "/text"), out);
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+            if let Some(refusal) = str_at(delta, "/refusal").filter(|r| !r.is_empty()) {
+                let index = self.open("refusal", BlockKind::Refusal, out);
+                Self::text(index, Some(refusal), out);
+            }
+
+            for (position, call) in delta
+                .get("tool_calls")
+                .and_then(|t| t.as_array())
+                .into_iter()
+                .flatten()
+                .enumerate()
+            {
+                let key = format!(
+                    "tool:{}",
+                    call.get("index")
+                        .and_then(|i| i.as_u64())
+                        .unwrap_or(position as u64)
+                );
+                let index = match self.open_index(&key) {
+                    Some(index) => index,
+                    None => {
+                        let kind = BlockKind::ToolUse {
+                            id: str_at(call, "/id").unwrap_or_default().to_string(),
+                            name: str_at(call, "/function/name")
+                                .unwrap_or_default()
+                                .to_string(),
+                        };
+                        self.open(&key, kind, out)
+                    }
+                };
+                match call.pointer("/function/arguments") {
+                    Some(Value::String(arguments)) => Self::tool_input(index, Some(arguments), out),
+                    Some(arguments) if arguments.is_object() => {
+                        Self::tool_input(index, Some(&arguments.to_string()), out)
+                    }
+                    _ => {}
+                }
+            }
+
+            self.update_stop_reason(choice.get("finish_reason"));
+        }
+
+        self.update_usage(
+            chunk.get("usage"),
+            "prompt_tokens",
+            "completion_tokens",
+            out,
+        );
+    }
+
+    /// Stream OpenAI-style string content, splitting off a leading `<think>` section
+    ///
+    /// Like the OpenAI parser, the thinking and the text after it are trimmed.
+    /// Whitespace, and what may be the start of a tag, is held back until the
+    /// following content shows whether it is kept. Content that only has a
+    /// closing tag is streamed as text, since that is not known until the tag
+    /// arrives.
+    fn read_content(&mut self, content: &str, out: &mut Vec<StreamEvent>) {
+        self.held_content.push_str(content);
+        loop {
+            match self.think_tags {
+                ThinkTags::Undecided => {
+                    let content = self.held_content.trim_start();
+                    if let Some(thinking) = content.strip_prefix(THINK_OPEN) {
+                        self.held_content = thinking.to_string();
+                        self.think_tags = ThinkTags::Thinking;
+                    } else if THINK_OPEN.starts_with(content) {
+                        return;
+                    } else {
+                        self.think_tags = ThinkTags::Absent;
+                    }
+                }
+                ThinkTags::Absent => {
+                    let text = std::mem::take(&mut self.held_content);
+                    if !text.is_empty() {
+                        let index = self.open("text", BlockKind::Text, out);
+                        Self::text(index, Some(&text), out);
+                    }
+                    return;
+                }
+                ThinkTags::Thinking => {
+                    let close = self.held_content.find(THINK_CLOSE);
+                    let end = close.unwrap_or_else(|| held_back(&self.held_content, THINK_CLOSE));
+                    let mut thinking = self.held_content[..end].trim_end();
+                    if self.open_index("thinking").is_none() {
+                        thinking = thinking.trim_start();
+                    }
+                    if !thinking.is_empty() {
+                        let thinking = thinking.to_string();
+                        let index = self.open("thinking", BlockKind::Thinking, out);
+                        Self::thinking(index, Some(&thinking), out);
+                    }
+                    match close {
+                        Some(end) => {
+                            self.held_content.drain(..end + THINK_CLOSE.len());
+                            self.think_tags = ThinkTags::Answer;
+                        }
+                        None => {
+                            self.held_content.drain(..end);
+                            return;
+                        }
+                    }
+                }
+                ThinkTags::Answer => {
+                    if self.open_index("text").is_none() {
+                        self.held_content = self.held_content.trim_start().to_string();
+                    }
+                    let end = self.held_content.trim_end().len();
+                    if end > 0 {
+                        let index = self.open("text", BlockKind::Text, out);
+                        let text: String = self.held_content.drain(..end).collect();
+                        Self::text(index, Some(&text), out);
+                    }
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Emit content still held back at the end of the stream
+    ///
+    /// Trailing whitespace after a `</think>` section is dropped, as the OpenAI
+    /// parser trims it.
+    fn flush_content(&mut self, out: &mut Vec<StreamEvent>) {
+        let content = std::mem::take(&mut self.held_content);
+        if content.is_empty() {
+            return;
+        }
+        match self.think_tags {
+            ThinkTags::Undecided | ThinkTags::Absent => {
+                let index = self.open("text", BlockKind::Text, out);
+                Self::text(index, Some(&content), out);
+            }
+            ThinkTags::Thinking => {
+                let index = self.open("thinking", BlockKind::Thinking, out);
+                Self::thinking(index, Some(&content), out);
+            }
+            ThinkTags::Answer => {}
+        }
+    }
+
+    fn read_openai_responses(&mut self, chunk: &Value, out: &mut Vec<StreamEvent>) {
+        let output = chunk
+            .get("output_index")
+            .and_then(|i| i.as_u64())
+            .unwrap_or(0);
+        let part = |field: &str| chunk.get(field).and_then(|i| i.as_u64()).unwrap_or(0);
+        let delta = str_at(chunk, "/delta");
+
+        match str_at(chunk, "/type") {
+            Some("response.created" | "response.in_progress") => {
+                self.start(
+                    str_at(chunk, "/response/id"),
+                    str_at(chunk, "/response/model"),
+                    out,
+                );
+            }
+            Some("response.output_item.added") => {
+                let item = chunk.get("item").unwrap_or(&Value::Null);
+                if str_at(item, "/type") == Some("function_call") {
+                    let kind = BlockKind::ToolUse {
+                        id: str_at(item, "/call_id").unwrap_or_default().to_string(),
+                        name: str_at(item, "/name").unwrap_or_default().to_string(),
+                    };
+                    self.open(&format!("{output}:"), kind, out);
+                }
+            }
+            Some("response.output_text.delta") => {
+                let index = self.open(
+                    &format!("{output}:text:{}", part("content_index")),
+                    BlockKind::Text,
+                    out,
+                );
+                Self::text(index, delta, out);
+            }
+            Some("response.refusal.delta") => {
+                let index = self.open(
+                    &format!("{output}:refusal:{}", part("content_index")),
+                    BlockKind::Refusal,
+                    out,
+                );
+                Self::text(index, delta, out);
+            }
+            Some("response.reasoning_summary_text.delta") => {
+                let index = self.open(
+                    &format!("{output}:summary:{}", part("summary_index")),
+                    BlockKind::Thinking,
+                    out,
+                );
+                Self::thinking(index, delta, out);
+            }
+            Some("response.reasoning_text.delta") => {
+                let index = self.open(
+                    &format!("{output}:reasoning:{}", part("content_index")),
+                    BlockKind::Thinking,
+                    out,
+                );
+                Self::thinking(index, delta, out);
+            }
+            Some("response.function_call_arguments.delta") => {
+                if let Some(index) = self.open_index(&format!("{output}:")) {
+                    Self::tool_input(index, delta, out);
+                }
+            }
+            Some("response.output_item.done")
+                if self
+                    .open
+                    .as_ref()
+                    .is_some_and(|(key, _)| key.starts_with(&format!("{output}:"))) =>
+            {
+                self.close(out)
+            }
+            Some("response.completed" | "response.incomplete" | "response.failed") => {
+                let response = chunk.get("response").unwrap_or(&Value::Null);
+                self.start(str_at(response, "/id"), str_at(response, "/model"), out);
+                self.update_usage(response.get("usage"), "input_tokens", "output_tokens", out);
+                // Incomplete responses say why in `incomplete_details.reason`, as in the parser
+                let reason = response
+                    .pointer("/incomplete_details/reason")
+                    .filter(|r| r.is_string());
+                self.update_stop_reason(reason.or_else(|| response.get("status")));
+                match response.pointer("/error/message").and_then(|m| m.as_str()) {
+                    Some(message) => self.fail(message.to_string(), out),
+                    None => self.stop(out),
+                }
+            }
+            Some("error") => {
+                let message = str_at(chunk, "/message").unwrap_or("Unknown error");
+                self.fail(message.to_string(), out);
+            }
+            _ => {}
+        }
+    }
+
+    fn read_gemini(&mut self, chunk: &Value, out: &mut Vec<StreamEvent>) {
+        self.start(
+            str_at(chunk, "/responseId"),
+            str_at(chunk, "/modelVersion"),
+            out,
+        );
+
+        if let Some(candidate) = chunk
+            .get("candidates")
+            .and_then(|c| c.as_array())
+            .and_then(|c| c.first())
+        {
+            for part in candidate
+                .pointer("/content/parts")
+                .and_then(|p| p.as_array())
+                .into_iter()
+                .flatten()
+            {
+                if let Some(text) = str_at(part, "/text") {
+                    if part.get("thought").and_then(|t| t.as_bool()) == Some(true) {
+                        let index = self.open("thinking", BlockKind::Thinking, out);
+                        Self::thinking(index, Some(text), out);
+                    } else {
+                        let index = self.open("text", BlockKind::Text, out);
+                        Self::text(index, Some(text), out);
+                    }
+                } else if let Some(call) = part.get("functionCall") {
+                    let id = match str_at(call, "/id") {
+                        Some(id) => id.to_string(),
+                        None => format!("call_{}", self.function_calls),
+                    };
+                    self.function_calls += 1;
+                    let name = str_at(call, "/name").unwrap_or_default().to_string();
+                    let index =
+                        self.open(&format!("call:{id}"), BlockKind::ToolUse { id, name }, out);
+                    let arguments = call
+                        .get("args")
+                        .map(|a| a.to_string())
+                        .unwrap_or_else(|| "{}".to_string());
+                    Self::tool_input(index, Some(&arguments), out);
+                    self.close(out);
+                } else {
+                    // Other parts, such as code execution, end the run of text
+                    self.close(out);
+                }
+            }
+            self.update_stop_reason(candidate.get("finishReason"));
+        }
+
+        if let Some(metadata) = chunk.get("usageMetadata") {
+            let thoughts = metadata
+                .get("thoughtsTokenCount")
+                .and_then(|t| t.as_u64())
+                .unwrap_or(0);
+            if let Some(mut usage) =
+                usage_from(Some(metadata), "promptTokenCount", "candidatesTokenCount")
+            {
+                usage.output_tokens = usage.output_tokens.saturating_add(thoughts);
+                self.usage = Some(usage);
+                out.push(StreamEvent::UsageUpdate { usage });
+            }
+        }
+    }
+
+    fn read_cohere(&mut self, chunk: &Value, out: &mut Vec<StreamEvent>) {
+        let key = format!(
+            "{}",
+            chunk.get("index").and_then(|i| i.as_u64()).unwrap_or(0)
+        );
+        let message = chunk.pointer("/delta/message").unwrap_or(&Value::Null);
+
+        match str_at(chunk, "/type") {
+            Some("message-start") => self.start(str_at(chunk, "/id"), None, out),
+            Some("content-start") => {
+                let kind = match str_at(message, "/content/type") {
+                    Some("thinking") => BlockKind::Thinking,
+                    _ => BlockKind::Text,
+                };
+                let index = self.open(&format!("content:{key}"), kind, out);
+                Self::text(index, str_at(message, "/content/text"), out);
+                Self::thinking(index, str_at(message, "/content/thinking"), out);
+            }
+            Some("content-delta") => {
+                if let Some(index) = self.open_index(&format!("content:{key}")) {
+                    Self::text(index, str_at(message, "/content/text"), out);
+                    Self::thinking(index, str_at(message, "/content/thinking"), out);
+                }
+            }
+            // The parser turns the tool plan into a thinking block
+            Some("tool-plan-delta") => {
+                let index = self.open("plan", BlockKind::Thinking, out);
+                Self::thinking(index, str_at(message, "/tool_plan"), out);
+            }
+            Some("tool-call-start") => {
+                let kind = BlockKind::ToolUse {
+                    id: str_at(message, "/tool_calls/id")
+                        .unwrap_or_default()
+                        .to_string(),
+                    name: str_at(message, "/tool_calls/function/name")
+                        .unwrap_or_default()
+                        .to_string(),
+                };
+                let index = self.open(&format!("tool:{key}"), kind, out);
+                Self::tool_input(
+                    index,
+                    str_at(message, "/tool_calls/function/arguments"),
+                    out,
+                );
+            }
+            Some("tool-call-delta") => {
+                if let Some(index) = self.open_index(&format!("tool:{key}")) {
+                    Self::tool_input(
+                        index,
+                        str_at(message, "/tool_calls/function/arguments"),
+                        out,
+                    );
+                }
+            }
+            Some("content-end" | "tool-call-end") => self.close(out),
+            Some("message-end") => {
+                let usage = chunk.pointer("/delta/usage");
+                let tokens = usage
+                    .and_then(|u| u.get("tokens"))
+                    .or_else(|| usage.and_then(|u| u.get("billed_units")));
+                self.update_usage(tokens, "input_tokens", "output_tokens", out);
+                self.update_stop_reason(chunk.pointer("/delta/finish_reason"));
+                self.stop(out);
+            }
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, id: Option<&str>, model: Option<&str>, out: &mut Vec<StreamEvent>) {
+        if !self.started {
+            self.started = true;
+            out.push(StreamEvent::MessageStart {
+                id: id.unwrap_or_default().to_string(),
+                model: model.unwrap_or_default().to_string(),
+            });
+        }
+    }
+
+    /// The index of the block with `key`, starting it (and closing the open block) if needed
+    fn open(&mut self, key: &str, kind: BlockKind, out: &mut Vec<StreamEvent>) -> usize {
+        if let Some(index) = self.open_index(key) {
+            return index;
+        }
+        self.start(None, None, out);
+        self.close(out);
+
+        let index = self.next_index;
+        self.next_index += 1;
+        self.open = Some((key.to_string(), index));
+        out.push(StreamEvent::BlockStart { index, kind });
+        index
+    }
+
+    /// The index of the open block, if its key is `key`
+    fn open_index(&self, key: &str) -> Option<usize> {
+        self.open
+            .as_ref()
+            .filter(|(open, _)| open == key)
+            .map(|(_, index)| *index)
+    }
+
+    fn close(&mut self, out: &mut Vec<StreamEvent>) {
+        if let Some((_, index)) = self.open.take() {
+            out.push(StreamEvent::BlockStop { index });
+        }
+    }
+
+    fn stop(&mut self, out: &mut Vec<StreamEvent>) {
+        if !self.stopped && !self.failed {
+            self.start(None, None, out);
+            self.flush_content(out);
+            self.close(out);
+            self.stopped = true;
+            out.push(StreamEvent::MessageStop {
+                stop_reason: self.stop_reason.clone(),
+            });
+        }
+    }
+
+    fn fail(&mut self, message: String, out: &mut Vec<StreamEvent>) {
+        if !self.failed {
+            self.close(out);
+            self.failed = true;
+            out.push(StreamEvent::Error { message });
+        }
+    }
+
+    fn update_stop_reason(&mut self, reason: Option<&Value>) {
+        if let Some(reason) = reason.and_then(|r| r.as_str()) {
+            self.stop_reason = Some(reason.to_string());
+        }
+    }
+
+    /// Merge the token counts present in `usage` into the totals so far
+    fn update_usage(
+        &mut self,
+        usage: Option<&Value>,
+        input: &str,
+        output: &str,
+        out: &mut Vec<StreamEvent>,
+    ) {
+        let Some(usage) = usage.filter(|u| u.is_object()) else {
+            return;
+        };
+        let Some(update) = usage_from(Some(usage), input, output) else {
+            return;
+        };
+        let mut merged = self.usage.unwrap_or_default();
+        if usage.get(input).is_some() {
+            merged.input_tokens = update.input_tokens;
+        }
+        if usage.get(output).is_some() {
+            merged.output_tokens = update.output_tokens;
+        }
+        self.usage = Some(merged);
+        out.push(StreamEvent::UsageUpdate { usage: merged });
+    }
+
+    fn text(index: usize, text: Option<&str>, out: &mut Vec<StreamEvent>) {
+        if let Some(text) = text.filter(|t| !t.is_empty()) {
+            out.push(StreamEvent::TextDelta {
+                index,
+                text: text.to_string(),
+            });
+        }
+    }
+
+    fn thinking(index: usize, thinking: Option<&str>, out: &mut Vec<StreamEvent>) {
+        if let Some(thinking) = thinking.filter(|t| !t.is_empty()) {
+            out.push(StreamEvent::ThinkingDelta {
+                index,
+                thinking: thinking.to_string(),
+            });
+        }
+    }
+
+    fn tool_input(index: usize, partial_json: Option<&str>, out: &mut Vec<StreamEvent>) {
+        if let Some(partial_json) = partial_json.filter(|p| !p.is_empty()) {
+            out.push(StreamEvent::ToolInputDelta {
+                index,
+                partial_json: partial_json.to_string(),
+            });
+        }
+    }
+}
+
+/// Length of `content` without trailing whitespace and a trailing start of `tag`
+fn held_back(content: &str, tag: &str) -> usize {
+    let partial = (1..tag.len())
+        .rev()
+        .find(|&len| content.ends_with(&tag[..len]))
+        .unwrap_or(0);
+    content[..content.len() - partial].trim_end().len()
+}
+
+fn str_at<'a>(value: &'a Value, pointer: &str) -> Option<&'a str> {
+    value.pointer(pointer).and_then(|v| v.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feed `capture` in chunks of `size` bytes, which split lines and UTF-8 sequences
+    fn stream(capture: &str, size: usize) -> (StreamParser, Vec<StreamEvent>) {
+        let mut parser = StreamParser::new();
+        let mut events = Vec::new();
+        for chunk in capture.as_bytes().chunks(size) {
+            events.extend(parser.push_bytes(chunk));
+        }
+        events.extend(parser.finish());
+        (parser, events)
+    }
+
+    /// Checks against the fixture corpus, which needs every provider with streaming
+    #[cfg(all(
+        feature = "anthropic",
+        feature = "openai",
+        feature = "gemini",
+        feature = "cohere",
+        feature = "mistral"
+    ))]
+    mod corpus {
+        use super::*;
+        use crate::normalized::ContentBlock;
+
+        /// Check the order of events and rebuild the streamed blocks from them
+        fn replay(events: &[StreamEvent]) -> Vec<ContentBlock> {
+            assert!(
+                matches!(events.first(), Some(StreamEvent::MessageStart { .. })),
+                "{events:?}"
+            );
+            assert!(
+                matches!(events.last(), Some(StreamEvent::MessageStop { .. })),
+                "{events:?}"
+            );
+
+            let mut blocks: Vec<(BlockKind, String)> = Vec::new();
+            let mut open = None;
+            for event in &events[1..events.len() - 1] {
+                match event {
+                    StreamEvent::BlockStart { index, kind } => {
+                        assert_eq!((open, *index), (None, blocks.len()));
+                        open = Some(*index);
+                        blocks.push((kind.clone(), String::new()));
+                    }
+                    StreamEvent::TextDelta { index, text: delta }
+                    | StreamEvent::ThinkingDelta {
+                        index,
+                        thinking: delta,
+                    }
+                    | StreamEvent::ToolInputDelta {
+                        index,
+                        partial_json: delta,
+                    } => {
+                        assert_eq!(open, Some(*index));
+                        blocks[*index].1.push_str(delta);
+                    }
+                    StreamEvent::BlockStop { index } => {
+                        assert_eq!(open.take(), Some(*index));
+                    }
+                    StreamEvent::UsageUpdate { .. } => {}
+                    other => panic!("unexpected {other:?}"),
+                }
+            }
+            assert_eq!(open, None);
+
+            blocks
+                .into_iter()
+                .map(|(kind, content)| match kind {
+                    BlockKind::Text => ContentBlock::text(content),
+                    BlockKind::Thinking => ContentBlock::Thinking {
+                        thinking: Some(content),
+                        signature: None,
+                    },
+                    BlockKind::Refusal => ContentBlock::Refusal { refusal: content },
+                    BlockKind::ToolUse { id, name } => ContentBlock::ToolUse {
+                        id,
+                        name,
+                        input: serde_json::from_str(&content)
+                            .unwrap_or_else(|_| serde_json::json!({})),
+                    },
+                })
+                .collect()
+        }
+
+        /// The blocks of a frame that are streamed as events, without what events leave out
+        fn streamed_blocks(frame: &ContentFrame) -> Vec<ContentBlock> {
+            frame
+                .blocks
+                .iter()
+                .filter_map(|block| match block {
+                    ContentBlock::Text { text, .. } => Some(ContentBlock::text(text.clone())),
+                    ContentBlock::Thinking {
+                        thinking: Some(thinking),
+                        ..
+                    } => Some(ContentBlock::Thinking {
+                        thinking: Some(thinking.clone()),
+                        signature: None,
+                    }),
+                    ContentBlock::Refusal { .. } | ContentBlock::ToolUse { .. } => {
+                        Some(block.clone())
+                    }
+                    _ => None,
+                })
+                .collect()
+        }
+
+        /// Events of every stream in the corpus agree with the frame of the assembled stream
+        #[test]
+        fn test_events_match_frames() {
+            let registry = ParserRegistry::with_defaults();
+            let fixtures = crate::testkit::FixtureSet::new(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/fixtures"
+            ))
+            .fixtures()
+            .unwrap();
+            let streams: Vec<_> = fixtures.iter().filter(|f| f.is_stream()).collect();
+            assert!(streams.len() >= 8);
+
+            for fixture in streams {
+                for size in [1, 7, 64, fixture.raw.len()] {
+                    let (parser, events) = stream(&fixture.raw, size);
+                    match fixture.parse_with_registry(&registry) {
+                        Ok(frame) => {
+                            let streamed = match &fixture.model {
+                                Some(model) => parser.frame_with_model(&registry, model),
+                                None => parser.frame(&registry),
+                            };
+                            assert_eq!(streamed.unwrap(), frame, "{}", fixture.name);
+                            assert_eq!(
+                                replay(&events),
+                                streamed_blocks(&frame),
+                                "{}",
+                                fixture.name
+                            );
+                            assert_eq!(
+                                events.last(),
+                                Some(&StreamEvent::MessageStop {
+                                    stop_reason: frame.stop_reason.clone()
+                                })
+                            );
+                            let usage = events.iter().rev().find_map(|e| match e {
+                                StreamEvent::UsageUpdate { usage } => Some(*usage),
+                                _ => None,
+                            });
+                            assert_eq!(usage, frame.usage, "{}", fixture.name);
+                        }
+                        Err(ParseError::ProviderError { .. }) => {
+                            assert!(
+                                matches!(events.last(), Some(StreamEvent::Error { .. })),
+                                "{}",
+                                fixture.name
+                            );
+                        }
+                        Err(_) => {
+                            assert!(parser.frame(&registry).is_err(), "{}", fixture.name);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_split_utf8() {
+        let capture = "data: {\"id\":\"1\",\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"日本語 🦀\"}}]}\n\n";
+        for size in 1..8 {
+            let (_, events) = stream(capture, size);
+            let text: String = events
+                .iter()
+                .filter_map(|e| match e {
+                    StreamEvent::TextDelta { text, .. } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(text, "日本語 🦀");
+        }
+
+        let mut parser = StreamParser::new();
+        parser.push_bytes(
+            b"data: {\"id\":\"1\",\"model\":\"m\",\"choices\":[{\"delta\":{\"content\":\"a\xff",
+        );
+        let events = parser.push_bytes(b"b\"}}]}\n\n");
+        assert!(events.contains(&StreamEvent::TextDelta {
+            index: 0,
+            text: "a\u{fffd}b".to_string()
+        }));
+    }
+
+    #[test]
+    fn test_think_tags() {
+        let chunk = |content: &str| {
+            format!(
+                "data: {{\"id\":\"1\",\"model\":\"qwen3-32b\",\"choices\":[{{\"index\":0,\"delta\":{{\"content\":{content:?}}}}}]}}\n\n"
+            )
+        };
+        let capture = format!(
+            "{}{}data: [DONE]\n\n",
+            chunk("<think>hmm</think>"),
+            chunk("Hi")
+        );
+
+        let (_, events) = stream(&capture, capture.len());
+        assert_eq!(
+            &events[1..events.len() - 1],
+            [
+                StreamEvent::BlockStart {
+                    index: 0,
+                    kind: BlockKind::Thinking
+                },
+                StreamEvent::ThinkingDelta {
+                    index: 0,
+                    thinking: "hmm".to_string()
+                },
+                StreamEvent::BlockStop { index: 0 },
+                StreamEvent::BlockStart {
+                    index: 1,
+                    kind: BlockKind::Text
+                },
+                StreamEvent::TextDelta {
+                    index: 1,
+                    text: "Hi".to_string()
+                },
+                StreamEvent::BlockStop { index: 1 },
+            ]
+        );
+
+        // Content that merely starts like a tag is text
+        let capture = format!("{}{}", chunk("<th"), chunk("e> tag"));
+        let (_, events) = stream(&capture, capture.len());
+        assert!(events.contains(&StreamEvent::TextDelta {
+            index: 0,
+            text: "<the> tag".to_string()
+        }));
+    }
+
+    #[test]
+    fn test_error_and_truncation() {
+        let capture = concat!(
+            "event: message_start\ndata: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\",\"model\":\"claude-sonnet-4-5\",\"usage\":{\"input_tokens\":3,\"output_tokens\":1}}}\n\n",
+            "event: content_block_start\ndata: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+            "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hi\"}}\n\n",
+        );
+        let error = "event: error\ndata: {\"type\":\"error\",\"error\":{\"type\":\"overloaded_error\",\"message\":\"Overloaded\"}}\n\n";
+
+        let (parser, events) = stream(&format!("{capture}{error}"), 16);
+        assert_eq!(
+            &events[events.len() - 2..],
+            [
+                StreamEvent::BlockStop { index: 0 },
+                StreamEvent::Error {
+                    message: "Provider error from anthropic (overloaded): Overloaded".to_string()
+                }
+            ]
+        );
+        assert!(matches!(
+            parser.frame(&ParserRegistry::new()),
+            Err(ParseError::ProviderError { .. })
+        ));
+
+        // A stream cut short still closes its block and message
+        let (_, events) = stream(capture, 16);
+        assert_eq!(
+            &events[events.len() - 2..],
+            [
+                StreamEvent::BlockStop { index: 0 },
+                StreamEvent::MessageStop { stop_reason: None }
+            ]
+        );
+
+        let (_, events) = stream("data: not json\n\n", 4);
+        assert!(events.is_empty());
+    }
+}
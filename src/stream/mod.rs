@@ -0,0 +1,1028 @@
+//! Streamed responses
+//!
+//! Providers stream responses as server-sent events (SSE), each carrying one
+//! chunk of the response in the provider's streaming format. [`SseDecoder`]
+//! splits a stream into events, and [`assemble`] rebuilds the regular
+//! (non-streaming) response body from the events of a complete stream, so the
+//! usual parsers can read it.
+//!
+//! To follow a response as it arrives, [`StreamParser`] turns the chunks of a
+//! stream into [`StreamEvent`]s, which are the same for every provider, and
+//! parses the complete response once the stream ends. With the `async`
+//! feature, `EventStream` does the same over an async response body.
+//!
+//! [`SseEncoder`] goes the other way, encoding events as the stream of
+//! OpenAI Chat Completions or Anthropic Messages, whichever provider they
+//! came from.
+//!
+//! The streaming formats of the following APIs are supported:
+//!
+//! - Anthropic Messages
+//! - OpenAI Chat Completions, and compatible APIs such as Mistral and DeepSeek
+//! - OpenAI Responses
+//! - Gemini `streamGenerateContent` with `alt=sse`
+//! - Cohere v2 Chat
+//!
+//! # Examples
+//!
+//! ```
+//! use adaptogen::stream::{assemble, SseDecoder};
+//!
+//! let capture = r#"data: {"id":"chatcmpl-1","object":"chat.completion.chunk","model":"gpt-4o","choices":[{"index":0,"delta":{"role":"assistant","content":"Hel"}}]}
+//!
+//! data: {"id":"chatcmpl-1","object":"chat.completion.chunk","model":"gpt-4o","choices":[{"index":0,"delta":{"content":"lo!"},"finish_reason":"stop"}]}
+//!
+//! data: [DONE]
+//!
+//! "#;
+//!
+//! let body = assemble(&SseDecoder::decode(capture)).unwrap();
+//! assert_eq!(body["choices"][0]["message"]["content"], "Hello!");
+//!
+//! # #[cfg(feature = "openai")]
+//! # {
+//! let frame = adaptogen::parse(&body.to_string()).unwrap();
+//! assert_eq!(frame.text(), "Hello!");
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+pub use crate::normalized::{BlockKind, StreamEvent};
+#[cfg(feature = "async")]
+pub use body::{EventStream, ReaderChunks, StreamError, StreamItem};
+pub use encode::{SseEncoder, StreamFormat};
+pub use events::StreamParser;
+
+use serde_json::{json, Value};
+
+use crate::parser::ParseError;
+use crate::providers::{decode_arguments, detect_provider_error};
+
+#[cfg(feature = "async")]
+mod body;
+mod encode;
+mod events;
+
+/// A server-sent event
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SseEvent {
+    /// The event type from the `event` field, if any
+    pub event: Option<String>,
+    /// The `data` lines of the event, joined with newlines
+    pub data: String,
+}
+
+impl fmt::Display for SseEvent {
+    /// The event in wire format, ending with the blank line that dispatches it
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(event) = &self.event {
+            writeln!(f, "event: {event}")?;
+        }
+        for line in self.data.split('\n') {
+            writeln!(f, "data: {line}")?;
+        }
+        writeln!(f)
+    }
+}
+
+/// Incremental decoder of server-sent events
+///
+/// Text can be pushed in chunks of any size; lines split across chunks are
+/// put back together. Comments and the `id` and `retry` fields are ignored.
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    buffer: String,
+    event: Option<String>,
+    data: Option<String>,
+}
+
+impl SseDecoder {
+    /// A decoder at the start of a stream
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode a complete stream
+    pub fn decode(capture: &str) -> Vec<SseEvent> {
+        let mut decoder = SseDecoder::new();
+        let mut events = decoder.push(capture);
+        events.extend(decoder.finish());
+        events
+    }
+
+    /// Feed the next chunk of the stream, returning the events it completes
+    pub fn push(&mut self, text: &str) -> Vec<SseEvent> {
+        self.buffer.push_str(text);
+        let mut events = Vec::new();
+        self.read_lines(false, &mut events);
+        events
+    }
+
+    /// End the stream, returning the last event if it was not terminated by a blank line
+    pub fn finish(&mut self) -> Option<SseEvent> {
+        let mut events = Vec::new();
+        self.read_lines(true, &mut events);
+        if !self.buffer.is_empty() {
+            let line = std::mem::take(&mut self.buffer);
+            self.read_line(&line, &mut events);
+        }
+        self.read_line("", &mut events);
+        events.pop()
+    }
+
+    /// Process every complete line in the buffer
+    ///
+    /// Lines end with `\n`, `\r\n` or `\r`. A trailing `\r` is only a line end
+    /// once the next chunk shows it is not followed by `\n`, or at the end of the stream.
+    fn read_lines(&mut self, at_end: bool, events: &mut Vec<SseEvent>) {
+        let buffer = std::mem::take(&mut self.buffer);
+        let bytes = buffer.as_bytes();
+        let mut start = 0;
+
+        while let Some(offset) = bytes[start..]
+            .iter()
+            .position(|&b| b == b'\n' || b == b'\r')
+        {
+            let end = start + offset;
+            let terminator = match (bytes[end], bytes.get(end + 1)) {
+                (b'\r', Some(b'\n')) => 2,
+                (b'\r', None) if !at_end => break,
+                _ => 1,
+            };
+            self.read_line(&buffer[start..end], events);
+            start = end + terminator;
+        }
+
+        self.buffer = buffer[start..].to_string();
+    }
+
+    fn read_line(&mut self, line: &str, events: &mut Vec<SseEvent>) {
+        if line.is_empty() {
+            let event = self.event.take();
+            if let Some(data) = self.data.take() {
+                events.push(SseEvent { event, data });
+            }
+            return;
+        }
+        if line.starts_with(':') {
+            return;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => match &mut self.data {
+                Some(data) => {
+                    data.push('\n');
+                    data.push_str(value);
+                }
+                None => self.data = Some(value.to_string()),
+            },
+            _ => {}
+        }
+    }
+}
+
+/// Rebuild the regular response body from the events of a complete stream
+///
+/// The result has the shape of the provider's non-streaming response, so it
+/// can be passed to [`ParserRegistry::parse`](crate::registry::ParserRegistry::parse)
+/// or a provider's parser. If the stream reports an error, the error body is
+/// returned instead, which the registry reports as a
+/// [`ParseError::ProviderError`].
+///
+/// # Errors
+///
+/// Returns a `ParseError` if an event does not hold JSON, the stream is
+/// empty or in an unknown format, or (for the OpenAI Responses API) the
+/// stream ended before the response was complete.
+pub fn assemble(events: &[SseEvent]) -> Result<Value, ParseError> {
+    let chunks = events
+        .iter()
+        .map(|event| event.data.trim())
+        .filter(|data| !data.is_empty() && *data != "[DONE]")
+        .map(serde_json::from_str)
+        .collect::<Result<Vec<Value>, _>>()?;
+
+    if let Some(error) = chunks
+        .iter()
+        .find(|chunk| detect_provider_error(chunk).is_some())
+    {
+        return Ok(error.clone());
+    }
+
+    match chunks.iter().find_map(Format::of) {
+        Some(Format::Anthropic) => Ok(assemble_anthropic(&chunks)),
+        Some(Format::OpenAi) => Ok(assemble_openai(&chunks)),
+        Some(Format::OpenAiResponses) => assemble_openai_responses(&chunks),
+        Some(Format::Gemini) => Ok(assemble_gemini(&chunks)),
+        Some(Format::Cohere) => Ok(assemble_cohere(&chunks)),
+        None if chunks.is_empty() => Err(ParseError::Other("Empty stream".to_string())),
+        None => Err(ParseError::Other("Unrecognized stream format".to_string())),
+    }
+}
+
+/// A provider's streaming format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Anthropic,
+    OpenAi,
+    OpenAiResponses,
+    Gemini,
+    Cohere,
+}
+
+impl Format {
+    /// The format a chunk belongs to, if the chunk tells
+    fn of(chunk: &Value) -> Option<Format> {
+        let has = |field: &str| chunk.get(field).is_some();
+        match chunk.get("type").and_then(|t| t.as_str()) {
+            Some(t) if t.starts_with("message_") || t.starts_with("content_block_") => {
+                Some(Format::Anthropic)
+            }
+            // Errors of the Responses API carry their fields at the top level
+            Some(t) if t.starts_with("response.") || (t == "error" && !has("error")) => {
+                Some(Format::OpenAiResponses)
+            }
+            Some(t)
+                if ["message-", "content-", "tool-", "citation-"]
+                    .iter()
+                    .any(|prefix| t.starts_with(prefix)) =>
+            {
+                Some(Format::Cohere)
+            }
+            _ if has("choices") => Some(Format::OpenAi),
+            _ if has("candidates") || has("promptFeedback") || has("usageMetadata") => {
+                Some(Format::Gemini)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Anthropic: `message_start`, then `content_block_start`, `content_block_delta`
+/// and `content_block_stop` per block, then `message_delta` and `message_stop`
+fn assemble_anthropic(chunks: &[Value]) -> Value {
+    let mut message = json!({});
+    let mut blocks: BTreeMap<u64, Value> = BTreeMap::new();
+    let mut inputs: BTreeMap<u64, String> = BTreeMap::new();
+
+    for chunk in chunks {
+        let index = chunk.get("index").and_then(|i| i.as_u64()).unwrap_or(0);
+        let delta = chunk.get("delta").unwrap_or(&Value::Null);
+
+        match chunk.get("type").and_then(|t| t.as_str()) {
+            Some("message_start") => {
+                if let Some(start) = chunk.get("message").filter(|m| m.is_object()) {
+                    message = start.clone();
+                }
+            }
+            Some("content_block_start") => {
+                if let Some(block) = chunk.get("content_block").filter(|b| b.is_object()) {
+                    blocks.insert(index, block.clone());
+                }
+            }
+            Some("content_block_delta") => {
+                let Some(block) = blocks.get_mut(&index) else {
+                    continue;
+                };
+                let delta_str =
+                    |name: &str| delta.get(name).and_then(|v| v.as_str()).unwrap_or_default();
+                match delta.get("type").and_then(|t| t.as_str()) {
+                    Some("text_delta") => append_str(block, "text", delta_str("text")),
+                    Some("thinking_delta") => append_str(block, "thinking", delta_str("thinking")),
+                    Some("signature_delta") => {
+                        append_str(block, "signature", delta_str("signature"))
+                    }
+                    Some("input_json_delta") => inputs
+                        .entry(index)
+                        .or_default()
+                        .push_str(delta_str("partial_json")),
+                    Some("citations_delta") => {
+                        if let Some(citation) = delta.get("citation") {
+                            push(block, "citations", citation.clone());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Some("message_delta") => {
+                merge(&mut message, delta);
+                if let Some(usage) = chunk.get("usage") {
+                    if let Some(message) = message.as_object_mut() {
+                        merge(message.entry("usage").or_insert_with(|| json!({})), usage);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Tool inputs are streamed as pieces of JSON text
+    for (index, input) in inputs {
+        if let Some(block) = blocks.get_mut(&index) {
+            set(
+                block,
+                "input",
+                decode_arguments(Some(&Value::String(input))),
+            );
+        }
+    }
+
+    set(
+        &mut message,
+        "content",
+        Value::Array(blocks.into_values().collect()),
+    );
+    message
+}
+
+/// OpenAI Chat Completions: `chat.completion.chunk` objects whose choices carry a `delta`
+fn assemble_openai(chunks: &[Value]) -> Value {
+    let mut response = json!({"object": "chat.completion"});
+    let mut choices: BTreeMap<u64, Value> = BTreeMap::new();
+    let mut tool_calls: BTreeMap<(u64, u64), Value> = BTreeMap::new();
+
+    for chunk in chunks {
+        for field in [
+            "id",
+            "model",
+            "created",
+            "system_fingerprint",
+            "service_tier",
+            "usage",
+        ] {
+            if let Some(value) = chunk.get(field).filter(|v| !v.is_null()) {
+                set(&mut response, field, value.clone());
+            }
+        }
+
+        for choice in chunk
+            .get("choices")
+            .and_then(|c| c.as_array())
+            .into_iter()
+            .flatten()
+        {
+            let index = choice.get("index").and_then(|i| i.as_u64()).unwrap_or(0);
+            let entry = choices.entry(index).or_insert_with(
+                || json!({"index": index, "message": {"role": "assistant"}, "finish_reason": null}),
+            );
+            let delta = choice.get("delta").unwrap_or(&Value::Null);
+
+            if let Some(message) = entry.get_mut("message") {
+                for field in ["content", "reasoning_content", "reasoning", "refusal"] {
+                    match delta.get(field) {
+                        Some(Value::String(text))
+                            if message.get(field).is_some_and(Value::is_array) =>
+                        {
+                            append_items(message, field, &[json!({"type": "text", "text": text})])
+                        }
+                        Some(Value::String(text)) => append_str(message, field, text),
+                        Some(Value::Array(items)) => append_items(message, field, items),
+                        _ => {}
+                    }
+                }
+            }
+
+            for (position, call) in delta
+                .get("tool_calls")
+                .and_then(|t| t.as_array())
+                .into_iter()
+                .flatten()
+                .enumerate()
+            {
+                let call_index = call
+                    .get("index")
+                    .and_then(|i| i.as_u64())
+                    .unwrap_or(position as u64);
+                let tool_call = tool_calls
+                    .entry((index, call_index))
+                    .or_insert_with(|| json!({"type": "function", "function": {"arguments": ""}}));
+                for field in ["id", "type"] {
+                    if let Some(value) = call.get(field).filter(|v| v.is_string()) {
+                        set(tool_call, field, value.clone());
+                    }
+                }
+                if let (Some(function), Some(delta)) =
+                    (tool_call.get_mut("function"), call.get("function"))
+                {
+                    if let Some(name) = delta.get("name").filter(|n| n.is_string()) {
+                        set(function, "name", name.clone());
+                    }
+                    match delta.get("arguments") {
+                        Some(Value::String(arguments)) => {
+                            append_str(function, "arguments", arguments)
+                        }
+                        Some(arguments) if arguments.is_object() => {
+                            set(function, "arguments", arguments.clone())
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            if let Some(reason) = choice.get("finish_reason").filter(|r| !r.is_null()) {
+                set(entry, "finish_reason", reason.clone());
+            }
+            if let Some(filter) = choice.get("content_filter_results") {
+                set(entry, "content_filter_results", filter.clone());
+            }
+            for token in choice
+                .pointer("/logprobs/content")
+                .and_then(|c| c.as_array())
+                .into_iter()
+                .flatten()
+            {
+                if let Some(entry) = entry.as_object_mut() {
+                    push(
+                        entry.entry("logprobs").or_insert_with(|| json!({})),
+                        "content",
+                        token.clone(),
+                    );
+                }
+            }
+        }
+    }
+
+    for ((choice, _), tool_call) in tool_calls {
+        if let Some(message) = choices.get_mut(&choice).and_then(|c| c.get_mut("message")) {
+            push(message, "tool_calls", tool_call);
+        }
+    }
+
+    set(
+        &mut response,
+        "choices",
+        Value::Array(choices.into_values().collect()),
+    );
+    response
+}
+
+/// OpenAI Responses: lifecycle events ending with the complete response
+fn assemble_openai_responses(chunks: &[Value]) -> Result<Value, ParseError> {
+    for chunk in chunks.iter().rev() {
+        match chunk.get("type").and_then(|t| t.as_str()) {
+            Some("response.completed" | "response.incomplete" | "response.failed") => {
+                if let Some(response) = chunk.get("response") {
+                    return Ok(response.clone());
+                }
+            }
+            // Errors outside a response, such as rate limits, carry the error
+            // fields at the top level
+            Some("error") => {
+                let mut error = chunk.clone();
+                if let Some(error) = error.as_object_mut() {
+                    error.remove("type");
+                    error.remove("sequence_number");
+                }
+                return Ok(json!({ "error": error }));
+            }
+            _ => {}
+        }
+    }
+    Err(ParseError::Other(
+        "Stream ended before the response was complete".to_string(),
+    ))
+}
+
+/// Gemini: complete `GenerateContentResponse` objects, each with the next parts
+fn assemble_gemini(chunks: &[Value]) -> Value {
+    let mut response = json!({});
+    let mut candidates: BTreeMap<u64, Value> = BTreeMap::new();
+
+    for chunk in chunks {
+        for field in [
+            "responseId",
+            "modelVersion",
+            "createTime",
+            "usageMetadata",
+            "promptFeedback",
+        ] {
+            if let Some(value) = chunk.get(field) {
+                set(&mut response, field, value.clone());
+            }
+        }
+
+        for candidate in chunk
+            .get("candidates")
+            .and_then(|c| c.as_array())
+            .into_iter()
+            .flatten()
+        {
+            let index = candidate.get("index").and_then(|i| i.as_u64()).unwrap_or(0);
+            let entry = candidates.entry(index).or_insert_with(
+                || json!({"index": index, "content": {"role": "model", "parts": []}}),
+            );
+
+            for (field, value) in candidate.as_object().into_iter().flatten() {
+                if field != "content" {
+                    set(entry, field, value.clone());
+                }
+            }
+            let parts = candidate
+                .pointer("/content/parts")
+                .and_then(|p| p.as_array());
+            if let Some(entry_parts) = entry
+                .pointer_mut("/content/parts")
+                .and_then(|p| p.as_array_mut())
+            {
+                for part in parts.into_iter().flatten() {
+                    push_gemini_part(entry_parts, part);
+                }
+            }
+        }
+    }
+
+    set(
+        &mut response,
+        "candidates",
+        Value::Array(candidates.into_values().collect()),
+    );
+    response
+}
+
+/// Append a streamed part, joining consecutive text of the same kind into one part
+fn push_gemini_part(parts: &mut Vec<Value>, part: &Value) {
+    let is_text = |part: &Value| {
+        part.as_object().is_some_and(|p| {
+            p.get("text").is_some_and(Value::is_string)
+                && p.keys()
+                    .all(|k| matches!(k.as_str(), "text" | "thought" | "thoughtSignature"))
+        })
+    };
+
+    if let Some(last) = parts.last_mut() {
+        if is_text(last) && is_text(part) && last.get("thought") == part.get("thought") {
+            append_str(last, "text", part["text"].as_str().unwrap_or_default());
+            if let Some(signature) = part.get("thoughtSignature") {
+                set(last, "thoughtSignature", signature.clone());
+            }
+            return;
+        }
+    }
+    parts.push(part.clone());
+}
+
+/// Cohere v2: `message-start`, content, tool plan, tool call and citation
+/// events, then `message-end`
+fn assemble_cohere(chunks: &[Value]) -> Value {
+    let mut response = json!({});
+    let mut content: BTreeMap<u64, Value> = BTreeMap::new();
+    let mut tool_calls: BTreeMap<u64, Value> = BTreeMap::new();
+    let mut citations = Vec::new();
+    let mut tool_plan = String::new();
+
+    for chunk in chunks {
+        let index = chunk.get("index").and_then(|i| i.as_u64()).unwrap_or(0);
+        let message = chunk.pointer("/delta/message").unwrap_or(&Value::Null);
+
+        match chunk.get("type").and_then(|t| t.as_str()) {
+            Some("message-start") => {
+                if let Some(id) = chunk.get("id") {
+                    set(&mut response, "id", id.clone());
+                }
+            }
+            Some("content-start") => {
+                if let Some(item) = message.get("content").filter(|c| c.is_object()) {
+                    content.insert(index, item.clone());
+                }
+            }
+            Some("content-delta") => {
+                let item = content
+                    .entry(index)
+                    .or_insert_with(|| json!({"type": "text"}));
+                for field in ["text", "thinking"] {
+                    if let Some(text) = message
+                        .get("content")
+                        .and_then(|c| c.get(field))
+                        .and_then(|t| t.as_str())
+                    {
+                        append_str(item, field, text);
+                    }
+                }
+            }
+            Some("tool-plan-delta") => {
+                tool_plan.push_str(
+                    message
+                        .get("tool_plan")
+                        .and_then(|p| p.as_str())
+                        .unwrap_or_default(),
+                );
+            }
+            Some("tool-call-start") => {
+                if let Some(call) = message.get("tool_calls").filter(|c| c.is_object()) {
+                    tool_calls.insert(index, call.clone());
+                }
+            }
+            Some("tool-call-delta") => {
+                let arguments = message
+                    .pointer("/tool_calls/function/arguments")
+                    .and_then(|a| a.as_str());
+                if let (Some(function), Some(arguments)) = (
+                    tool_calls
+                        .get_mut(&index)
+                        .and_then(|c| c.get_mut("function")),
+                    arguments,
+                ) {
+                    append_str(function, "arguments", arguments);
+                }
+            }
+            Some("citation-start") => {
+                if let Some(citation) = message.get("citations") {
+                    citations.push(citation.clone());
+                }
+            }
+            Some("message-end") => {
+                for field in ["finish_reason", "usage"] {
+                    if let Some(value) = chunk.pointer(&format!("/delta/{field}")) {
+                        set(&mut response, field, value.clone());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut message = json!({
+        "role": "assistant",
+        "content": content.into_values().collect::<Vec<_>>(),
+        "citations": citations,
+    });
+    if !tool_plan.is_empty() {
+        set(&mut message, "tool_plan", Value::String(tool_plan));
+    }
+    if !tool_calls.is_empty() {
+        set(
+            &mut message,
+            "tool_calls",
+            Value::Array(tool_calls.into_values().collect()),
+        );
+    }
+    set(&mut response, "message", message);
+    response
+}
+
+/// Set `field` of `target`, if `target` is an object
+fn set(target: &mut Value, field: &str, value: Value) {
+    if let Some(object) = target.as_object_mut() {
+        object.insert(field.to_string(), value);
+    }
+}
+
+/// Append `text` to the string `field` of `target`, if `target` is an object
+fn append_str(target: &mut Value, field: &str, text: &str) {
+    if let Some(object) = target.as_object_mut() {
+        match object.get_mut(field) {
+            Some(Value::String(existing)) => existing.push_str(text),
+            _ => {
+                object.insert(field.to_string(), Value::String(text.to_string()));
+            }
+        }
+    }
+}
+
+/// Append `item` to the array `field` of `target`, if `target` is an object
+fn push(target: &mut Value, field: &str, item: Value) {
+    if let Some(object) = target.as_object_mut() {
+        match object.get_mut(field) {
+            Some(Value::Array(items)) => items.push(item),
+            _ => {
+                object.insert(field.to_string(), Value::Array(vec![item]));
+            }
+        }
+    }
+}
+
+/// Merge the fields of `source` into `target`, if both are objects
+fn merge(target: &mut Value, source: &Value) {
+    if let (Some(target), Some(source)) = (target.as_object_mut(), source.as_object()) {
+        for (field, value) in source {
+            target.insert(field.clone(), value.clone());
+        }
+    }
+}
+
+/// Append content chunks (as streamed by Mistral) to `field`, joining
+/// consecutive text or thinking chunks
+fn append_items(target: &mut Value, field: &str, items: &[Value]) {
+    let Some(object) = target.as_object_mut() else {
+        return;
+    };
+    let existing = match object.remove(field) {
+        Some(Value::Array(existing)) => existing,
+        Some(Value::String(text)) if !text.is_empty() => {
+            vec![json!({"type": "text", "text": text})]
+        }
+        _ => Vec::new(),
+    };
+
+    let mut merged = existing;
+    for item in items {
+        let kind = item.get("type").and_then(|t| t.as_str());
+        let last_kind = merged
+            .last()
+            .and_then(|l| l.get("type"))
+            .and_then(|t| t.as_str())
+            .map(str::to_string);
+        match (kind, last_kind.as_deref(), merged.last_mut()) {
+            (Some("text"), Some("text"), Some(last)) => {
+                append_str(
+                    last,
+                    "text",
+                    item.get("text")
+                        .and_then(|t| t.as_str())
+                        .unwrap_or_default(),
+                );
+            }
+            (Some("thinking"), Some("thinking"), Some(last)) => {
+                let pieces = item
+                    .get("thinking")
+                    .and_then(|t| t.as_array())
+                    .into_iter()
+                    .flatten();
+                for piece in pieces {
+                    let text = piece
+                        .get("text")
+                        .and_then(|t| t.as_str())
+                        .unwrap_or_default();
+                    match last
+                        .get_mut("thinking")
+                        .and_then(|t| t.as_array_mut())
+                        .and_then(|t| t.last_mut())
+                    {
+                        Some(previous) => append_str(previous, "text", text),
+                        None => push(last, "thinking", piece.clone()),
+                    }
+                }
+            }
+            _ => merged.push(item.clone()),
+        }
+    }
+    object.insert(field.to_string(), Value::Array(merged));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data_events(chunks: &[Value]) -> String {
+        chunks
+            .iter()
+            .map(|chunk| format!("data: {chunk}\n\n"))
+            .collect()
+    }
+
+    #[test]
+    fn test_decoder_handles_split_lines_and_line_endings() {
+        let capture = ": keep-alive\r\nevent: ping\r\ndata: {\"a\":\r\ndata: 1}\r\n\r\nid: 7\ndata: second\n\rdata: third";
+        let expected = vec![
+            SseEvent {
+                event: Some("ping".to_string()),
+                data: "{\"a\":\n1}".to_string(),
+            },
+            SseEvent {
+                event: None,
+                data: "second".to_string(),
+            },
+            SseEvent {
+                event: None,
+                data: "third".to_string(),
+            },
+        ];
+        assert_eq!(SseDecoder::decode(capture), expected);
+
+        // Every split point gives the same events, including between \r and \n
+        for split in 0..=capture.len() {
+            let mut decoder = SseDecoder::new();
+            let mut events = decoder.push(&capture[..split]);
+            events.extend(decoder.push(&capture[split..]));
+            events.extend(decoder.finish());
+            assert_eq!(events, expected, "split at {split}");
+        }
+    }
+
+    #[test]
+    fn test_event_display() {
+        let events = vec![
+            SseEvent {
+                event: Some("ping".to_string()),
+                data: "{\"a\":\n1}".to_string(),
+            },
+            SseEvent {
+                event: None,
+                data: "[DONE]".to_string(),
+            },
+        ];
+        let capture: String = events.iter().map(SseEvent::to_string).collect();
+        assert_eq!(
+            capture,
+            "event: ping\ndata: {\"a\":\ndata: 1}\n\ndata: [DONE]\n\n"
+        );
+        assert_eq!(SseDecoder::decode(&capture), events);
+    }
+
+    #[test]
+    fn test_assemble_anthropic() {
+        let capture = r#"event: message_start
+data: {"type":"message_start","message":{"id":"msg_1","type":"message","role":"assistant","model":"claude-sonnet-4-5","content":[],"stop_reason":null,"usage":{"input_tokens":25,"output_tokens":1}}}
+
+event: content_block_start
+data: {"type":"content_block_start","index":0,"content_block":{"type":"thinking","thinking":"","signature":""}}
+
+event: content_block_delta
+data: {"type":"content_block_delta","index":0,"delta":{"type":"thinking_delta","thinking":"Need the "}}
+
+event: content_block_delta
+data: {"type":"content_block_delta","index":0,"delta":{"type":"thinking_delta","thinking":"weather."}}
+
+event: content_block_delta
+data: {"type":"content_block_delta","index":0,"delta":{"type":"signature_delta","signature":"sig"}}
+
+event: content_block_stop
+data: {"type":"content_block_stop","index":0}
+
+event: ping
+data: {"type": "ping"}
+
+event: content_block_start
+data: {"type":"content_block_start","index":1,"content_block":{"type":"tool_use","id":"toolu_1","name":"get_weather","input":{}}}
+
+event: content_block_delta
+data: {"type":"content_block_delta","index":1,"delta":{"type":"input_json_delta","partial_json":"{\"city\": \"Pa"}}
+
+event: content_block_delta
+data: {"type":"content_block_delta","index":1,"delta":{"type":"input_json_delta","partial_json":"ris\"}"}}
+
+event: content_block_stop
+data: {"type":"content_block_stop","index":1}
+
+event: message_delta
+data: {"type":"message_delta","delta":{"stop_reason":"tool_use","stop_sequence":null},"usage":{"output_tokens":42}}
+
+event: message_stop
+data: {"type":"message_stop"}
+"#;
+
+        let body = assemble(&SseDecoder::decode(capture)).unwrap();
+        assert_eq!(body["id"], "msg_1");
+        assert_eq!(body["stop_reason"], "tool_use");
+        assert_eq!(
+            body["usage"],
+            json!({"input_tokens": 25, "output_tokens": 42})
+        );
+        assert_eq!(
+            body["content"],
+            json!([
+                {"type": "thinking", "thinking": "Need the weather.", "signature": "sig"},
+                {"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {"city": "Paris"}}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_assemble_openai() {
+        let capture = data_events(&[
+            json!({"id": "chatcmpl-1", "object": "chat.completion.chunk", "model": "gpt-4o", "choices": [{"index": 0, "delta": {"role": "assistant", "content": ""}, "finish_reason": null}]}),
+            json!({"id": "chatcmpl-1", "object": "chat.completion.chunk", "model": "gpt-4o", "choices": [{"index": 0, "delta": {"content": "Let me check."}, "finish_reason": null}]}),
+            json!({"id": "chatcmpl-1", "object": "chat.completion.chunk", "model": "gpt-4o", "choices": [{"index": 0, "delta": {"tool_calls": [{"index": 0, "id": "call_1", "type": "function", "function": {"name": "get_weather", "arguments": ""}}]}, "finish_reason": null}]}),
+            json!({"id": "chatcmpl-1", "object": "chat.completion.chunk", "model": "gpt-4o", "choices": [{"index": 0, "delta": {"tool_calls": [{"index": 0, "function": {"arguments": "{\"city\":"}}]}, "finish_reason": null}]}),
+            json!({"id": "chatcmpl-1", "object": "chat.completion.chunk", "model": "gpt-4o", "choices": [{"index": 0, "delta": {"tool_calls": [{"index": 0, "function": {"arguments": "\"Paris\"}"}}]}, "finish_reason": null}]}),
+            json!({"id": "chatcmpl-1", "object": "chat.completion.chunk", "model": "gpt-4o", "choices": [{"index": 0, "delta": {}, "finish_reason": "tool_calls"}]}),
+            json!({"id": "chatcmpl-1", "object": "chat.completion.chunk", "model": "gpt-4o", "choices": [], "usage": {"prompt_tokens": 10, "completion_tokens": 20, "total_tokens": 30}}),
+        ]) + "data: [DONE]\n\n";
+
+        let body = assemble(&SseDecoder::decode(&capture)).unwrap();
+        assert_eq!(body["usage"]["completion_tokens"], 20);
+        assert_eq!(
+            body["choices"],
+            json!([{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": "Let me check.",
+                    "tool_calls": [{"id": "call_1", "type": "function", "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}}]
+                },
+                "finish_reason": "tool_calls"
+            }])
+        );
+    }
+
+    #[test]
+    fn test_assemble_mistral_content_chunks() {
+        let chunk = |content: Value| json!({"id": "1", "model": "magistral-medium-2509", "choices": [{"index": 0, "delta": {"content": content}}]});
+        let capture = data_events(&[
+            chunk(json!([{"type": "thinking", "thinking": [{"type": "text", "text": "Count "}]}])),
+            chunk(
+                json!([{"type": "thinking", "thinking": [{"type": "text", "text": "letters."}]}]),
+            ),
+            chunk(json!("There are ")),
+            chunk(json!("3.")),
+        ]);
+
+        let body = assemble(&SseDecoder::decode(&capture)).unwrap();
+        assert_eq!(
+            body["choices"][0]["message"]["content"],
+            json!([
+                {"type": "thinking", "thinking": [{"type": "text", "text": "Count letters."}]},
+                {"type": "text", "text": "There are 3."}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_assemble_openai_responses() {
+        let capture = data_events(&[
+            json!({"type": "response.created", "sequence_number": 0, "response": {"id": "resp_1", "status": "in_progress", "output": []}}),
+            json!({"type": "response.output_text.delta", "sequence_number": 1, "item_id": "msg_1", "output_index": 0, "content_index": 0, "delta": "Hi"}),
+            json!({"type": "response.completed", "sequence_number": 2, "response": {"id": "resp_1", "status": "completed", "model": "gpt-4.1", "output": [{"type": "message", "role": "assistant", "content": [{"type": "output_text", "text": "Hi", "annotations": []}]}]}}),
+        ]);
+        let body = assemble(&SseDecoder::decode(&capture)).unwrap();
+        assert_eq!(body["status"], "completed");
+        assert_eq!(body["output"][0]["content"][0]["text"], "Hi");
+
+        let truncated = data_events(&[
+            json!({"type": "response.created", "response": {"id": "resp_1", "status": "in_progress"}}),
+        ]);
+        assert!(matches!(
+            assemble(&SseDecoder::decode(&truncated)),
+            Err(ParseError::Other(_))
+        ));
+
+        let error = data_events(&[
+            json!({"type": "error", "code": "rate_limit_exceeded", "message": "Slow down", "sequence_number": 1}),
+        ]);
+        let body = assemble(&SseDecoder::decode(&error)).unwrap();
+        assert_eq!(
+            body,
+            json!({"error": {"code": "rate_limit_exceeded", "message": "Slow down"}})
+        );
+    }
+
+    #[test]
+    fn test_assemble_gemini() {
+        let capture = data_events(&[
+            json!({"candidates": [{"content": {"role": "model", "parts": [{"text": "Thinking", "thought": true}]}, "index": 0}], "modelVersion": "gemini-2.5-flash", "responseId": "r1"}),
+            json!({"candidates": [{"content": {"role": "model", "parts": [{"text": "The answer"}]}, "index": 0}], "modelVersion": "gemini-2.5-flash", "responseId": "r1"}),
+            json!({"candidates": [{"content": {"role": "model", "parts": [{"text": " is 42.", "thoughtSignature": "sig"}]}, "finishReason": "STOP", "index": 0}], "usageMetadata": {"promptTokenCount": 5, "candidatesTokenCount": 7}, "modelVersion": "gemini-2.5-flash", "responseId": "r1"}),
+        ]);
+
+        let body = assemble(&SseDecoder::decode(&capture)).unwrap();
+        assert_eq!(body["usageMetadata"]["candidatesTokenCount"], 7);
+        assert_eq!(body["candidates"][0]["finishReason"], "STOP");
+        assert_eq!(
+            body["candidates"][0]["content"]["parts"],
+            json!([{"text": "Thinking", "thought": true}, {"text": "The answer is 42.", "thoughtSignature": "sig"}])
+        );
+    }
+
+    #[test]
+    fn test_assemble_cohere() {
+        let capture = data_events(&[
+            json!({"type": "message-start", "id": "c1", "delta": {"message": {"role": "assistant"}}}),
+            json!({"type": "tool-plan-delta", "delta": {"message": {"tool_plan": "I will "}}}),
+            json!({"type": "tool-plan-delta", "delta": {"message": {"tool_plan": "search."}}}),
+            json!({"type": "tool-call-start", "index": 0, "delta": {"message": {"tool_calls": {"id": "search_1", "type": "function", "function": {"name": "search", "arguments": ""}}}}}),
+            json!({"type": "tool-call-delta", "index": 0, "delta": {"message": {"tool_calls": {"function": {"arguments": "{\"q\": \"x\"}"}}}}}),
+            json!({"type": "tool-call-end", "index": 0}),
+            json!({"type": "content-start", "index": 0, "delta": {"message": {"content": {"type": "text", "text": ""}}}}),
+            json!({"type": "content-delta", "index": 0, "delta": {"message": {"content": {"text": "Done"}}}}),
+            json!({"type": "content-end", "index": 0}),
+            json!({"type": "message-end", "delta": {"finish_reason": "COMPLETE", "usage": {"tokens": {"input_tokens": 3, "output_tokens": 4}}}}),
+        ]);
+
+        let body = assemble(&SseDecoder::decode(&capture)).unwrap();
+        assert_eq!(body["id"], "c1");
+        assert_eq!(body["finish_reason"], "COMPLETE");
+        assert_eq!(body["message"]["tool_plan"], "I will search.");
+        assert_eq!(
+            body["message"]["tool_calls"][0]["function"]["arguments"],
+            "{\"q\": \"x\"}"
+        );
+        assert_eq!(
+            body["message"]["content"],
+            json!([{"type": "text", "text": "Done"}])
+        );
+    }
+
+    #[test]
+    fn test_assemble_errors() {
+        let error = "event: error\ndata: {\"type\": \"error\", \"error\": {\"type\": \"overloaded_error\", \"message\": \"Overloaded\"}}\n\n";
+        let body = assemble(&SseDecoder::decode(error)).unwrap();
+        assert!(detect_provider_error(&body).is_some());
+
+        assert!(matches!(assemble(&[]), Err(ParseError::Other(_))));
+        assert!(matches!(
+            assemble(&SseDecoder::decode("data: {\"a\": 1}\n\n")),
+            Err(ParseError::Other(_))
+        ));
+        assert!(matches!(
+            assemble(&SseDecoder::decode("data: {\n\n")),
+            Err(ParseError::InvalidJson(_))
+        ));
+    }
+}
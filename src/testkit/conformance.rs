@@ -0,0 +1,543 @@
+//! Conformance checks for parser implementations
+//!
+//! [`Conformance`] runs a parser through the behavior the built-in parsers
+//! share, starting from one valid sample response:
+//!
+//! - the sample parses, and the frame's id is the response's id
+//! - parsing is deterministic
+//! - a response without its id is rejected with [`ParseError::MissingField`]
+//! - content blocks keep the order of the response (optional, see
+//!   [`blocks_at`](Conformance::blocks_at))
+//! - arbitrary and malformed input never panics (see [`Generator`])
+//! - `can_handle` accepts every model in `supported_models`, and rejects
+//!   unrelated model names
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "anthropic")]
+//! # {
+//! use adaptogen::providers::AnthropicParser;
+//! use adaptogen::testkit::Conformance;
+//!
+//! let sample = r#"{
+//!     "id": "msg_1",
+//!     "model": "claude-sonnet-4-5",
+//!     "content": [
+//!         {"type": "text", "text": "Checking the weather."},
+//!         {"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {"city": "Paris"}}
+//!     ]
+//! }"#;
+//!
+//! Conformance::new(&AnthropicParser, sample).blocks_at("/content").assert();
+//! # }
+//! ```
+
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+use serde_json::Value;
+
+use super::generate::{remove, Generator};
+use crate::normalized::ContentFrame;
+use crate::parser::{ModelResponseParser, ParseError};
+
+/// Model name no parser should claim
+const UNRELATED_MODEL: &str = "adaptogen-conformance-unrelated-model";
+
+/// The behavior checked by [`Conformance`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Check {
+    /// The sample response parses, with the expected id
+    Sample,
+    /// Parsing the same response twice gives the same frame
+    Deterministic,
+    /// A response without its id is rejected
+    MissingId,
+    /// Content blocks keep the order of the response
+    BlockOrder,
+    /// Arbitrary input never panics
+    NoPanic,
+    /// `can_handle` agrees with `supported_models`
+    CanHandle,
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Check::Sample => "sample",
+            Check::Deterministic => "deterministic",
+            Check::MissingId => "missing id",
+            Check::BlockOrder => "block order",
+            Check::NoPanic => "no panic",
+            Check::CanHandle => "can_handle",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A failed conformance check
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{check}: {message}")]
+pub struct ConformanceFailure {
+    /// The check that failed
+    pub check: Check,
+    /// What went wrong, including the offending input where there is one
+    pub message: String,
+}
+
+/// Conformance checks for a [`ModelResponseParser`]
+///
+/// Built with a parser and a valid sample response of its format. The
+/// sample's id is expected at `/id` unless configured with
+/// [`id_at`](Self::id_at).
+pub struct Conformance<'a> {
+    parser: &'a dyn ModelResponseParser,
+    sample: String,
+    model: Option<String>,
+    id_pointer: Option<String>,
+    blocks_pointer: Option<String>,
+    fuzz_cases: usize,
+    seed: u64,
+}
+
+impl<'a> Conformance<'a> {
+    /// Checks for `parser`, starting from a valid `sample` response
+    pub fn new(parser: &'a dyn ModelResponseParser, sample: impl Into<String>) -> Self {
+        Conformance {
+            parser,
+            sample: sample.into(),
+            model: None,
+            id_pointer: Some("/id".to_string()),
+            blocks_pointer: None,
+            fuzz_cases: 256,
+            seed: 0,
+        }
+    }
+
+    /// Parse with [`parse_with_model`](ModelResponseParser::parse_with_model) for `model`
+    ///
+    /// For formats that do not include the model in the response body.
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// JSON pointer to the response id, or `None` for formats without one
+    pub fn id_at(mut self, pointer: Option<&str>) -> Self {
+        self.id_pointer = pointer.map(str::to_string);
+        self
+    }
+
+    /// JSON pointer to the array of content items, enabling the block order check
+    ///
+    /// The check reverses the array, which must hold at least two items that
+    /// each become one block, and expects the blocks in reverse order. Items
+    /// should not rely on generated ids, which may depend on their position.
+    pub fn blocks_at(mut self, pointer: &str) -> Self {
+        self.blocks_pointer = Some(pointer.to_string());
+        self
+    }
+
+    /// Number of generated inputs for the no-panic check (256 by default)
+    pub fn fuzz_cases(mut self, cases: usize) -> Self {
+        self.fuzz_cases = cases;
+        self
+    }
+
+    /// Seed of the [`Generator`] for the no-panic check
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Run every check, returning the failures
+    pub fn run(&self) -> Vec<ConformanceFailure> {
+        let mut failures = Vec::new();
+        let sample: Value = match serde_json::from_str(&self.sample) {
+            Ok(sample) => sample,
+            Err(error) => {
+                failures.push(failure(
+                    Check::Sample,
+                    format!("sample is not valid JSON: {error}"),
+                ));
+                return failures;
+            }
+        };
+
+        let checks = [
+            self.check_sample(&sample),
+            self.check_missing_id(&sample),
+            self.check_block_order(&sample),
+            self.check_no_panic(),
+            self.check_can_handle(),
+        ];
+        failures.extend(checks.into_iter().flatten());
+        failures
+    }
+
+    /// Run every check, panicking with all failures
+    pub fn assert(&self) {
+        let failures = self.run();
+        if !failures.is_empty() {
+            let failures: Vec<String> = failures.iter().map(|f| format!("- {f}")).collect();
+            panic!(
+                "{} conformance check(s) failed:\n{}",
+                failures.len(),
+                failures.join("\n")
+            );
+        }
+    }
+
+    fn parse(&self, raw: &str) -> Result<ContentFrame, ParseError> {
+        match &self.model {
+            Some(model) => self.parser.parse_with_model(raw, model),
+            None => self.parser.parse(raw),
+        }
+    }
+
+    fn check_sample(&self, sample: &Value) -> Option<ConformanceFailure> {
+        let frame = match self.parse(&self.sample) {
+            Ok(frame) => frame,
+            Err(error) => {
+                return Some(failure(
+                    Check::Sample,
+                    format!("sample failed to parse: {error}"),
+                ))
+            }
+        };
+
+        if let Some(pointer) = &self.id_pointer {
+            let id = sample.pointer(pointer).and_then(|id| id.as_str());
+            if id != Some(frame.id.as_str()) {
+                return Some(failure(
+                    Check::Sample,
+                    format!(
+                        "frame id is {:?}, but the sample has {id:?} at {pointer}",
+                        frame.id
+                    ),
+                ));
+            }
+        }
+
+        match self.parse(&self.sample) {
+            Ok(again) if again == frame => None,
+            _ => Some(failure(
+                Check::Deterministic,
+                "parsing the sample twice gave different results".to_string(),
+            )),
+        }
+    }
+
+    fn check_missing_id(&self, sample: &Value) -> Option<ConformanceFailure> {
+        let pointer = self.id_pointer.as_ref()?;
+        if sample.pointer(pointer).is_none() {
+            return Some(failure(
+                Check::Sample,
+                format!("sample has no id at {pointer}"),
+            ));
+        }
+
+        let mut without_id = sample.clone();
+        remove(&mut without_id, pointer);
+        match self.parse(&without_id.to_string()) {
+            Err(ParseError::MissingField(_)) => None,
+            Err(error) => Some(failure(
+                Check::MissingId,
+                format!("expected a missing field error without {pointer}, got: {error}"),
+            )),
+            Ok(_) => Some(failure(
+                Check::MissingId,
+                format!("parsed a response without {pointer}"),
+            )),
+        }
+    }
+
+    fn check_block_order(&self, sample: &Value) -> Option<ConformanceFailure> {
+        let pointer = self.blocks_pointer.as_ref()?;
+        let items = match sample.pointer(pointer).and_then(|items| items.as_array()) {
+            Some(items) if items.len() >= 2 => items,
+            _ => {
+                return Some(failure(
+                    Check::Sample,
+                    format!("sample needs at least two items at {pointer}"),
+                ))
+            }
+        };
+
+        let original = self.parse(&self.sample).ok()?;
+        if original.blocks.len() != items.len() {
+            return Some(failure(
+                Check::Sample,
+                format!(
+                    "{} items at {pointer} became {} blocks",
+                    items.len(),
+                    original.blocks.len()
+                ),
+            ));
+        }
+
+        let mut reversed = sample.clone();
+        if let Some(Value::Array(items)) = reversed.pointer_mut(pointer) {
+            items.reverse();
+        }
+        let expected: Vec<_> = original.blocks.iter().rev().cloned().collect();
+        match self.parse(&reversed.to_string()) {
+            Ok(frame) if frame.blocks == expected => None,
+            Ok(_) => Some(failure(
+                Check::BlockOrder,
+                format!("reversing the items at {pointer} did not reverse the blocks"),
+            )),
+            Err(error) => Some(failure(
+                Check::BlockOrder,
+                format!("reversing the items at {pointer} failed to parse: {error}"),
+            )),
+        }
+    }
+
+    fn check_no_panic(&self) -> Option<ConformanceFailure> {
+        let mut generator = Generator::new(self.seed);
+        for case in 0..self.fuzz_cases {
+            let input = generator.input(&self.sample);
+            if panic::catch_unwind(AssertUnwindSafe(|| self.parse(&input))).is_err() {
+                return Some(failure(
+                    Check::NoPanic,
+                    format!(
+                        "panicked on generated input {case} (seed {}): {input}",
+                        self.seed
+                    ),
+                ));
+            }
+        }
+        None
+    }
+
+    fn check_can_handle(&self) -> Option<ConformanceFailure> {
+        let models = self.parser.supported_models();
+        if let Some(model) = models.iter().find(|model| !self.parser.can_handle(model)) {
+            return Some(failure(
+                Check::CanHandle,
+                format!("supported model {model:?} is not handled"),
+            ));
+        }
+        for model in ["", UNRELATED_MODEL] {
+            if self.parser.can_handle(model) {
+                return Some(failure(
+                    Check::CanHandle,
+                    format!("unrelated model {model:?} is handled"),
+                ));
+            }
+        }
+        None
+    }
+}
+
+fn failure(check: Check, message: String) -> ConformanceFailure {
+    ConformanceFailure { check, message }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::normalized::ContentBlock;
+
+    /// Parser with every defect the checks look for
+    struct SloppyParser;
+
+    impl ModelResponseParser for SloppyParser {
+        fn supported_models(&self) -> Vec<String> {
+            vec!["sloppy-1".to_string()]
+        }
+
+        fn parse(&self, raw_response: &str) -> Result<ContentFrame, ParseError> {
+            let json: Value = serde_json::from_str(raw_response)?;
+            let id = json["id"].as_str().unwrap_or_default();
+            let mut blocks: Vec<ContentBlock> = json["content"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|item| ContentBlock::text(item["text"].as_str().unwrap()))
+                .collect();
+            blocks.sort_by_key(|block| format!("{block:?}"));
+            Ok(ContentFrame::new(id, "sloppy-1", blocks))
+        }
+
+        fn can_handle(&self, model: &str) -> bool {
+            model.is_empty()
+        }
+    }
+
+    #[test]
+    fn test_reports_every_defect() {
+        let sample = r#"{"id": "1", "content": [{"text": "a"}, {"text": "b"}]}"#;
+        let failures = Conformance::new(&SloppyParser, sample)
+            .blocks_at("/content")
+            .run();
+        let checks: Vec<Check> = failures.iter().map(|f| f.check).collect();
+        assert_eq!(
+            checks,
+            [
+                Check::MissingId,
+                Check::BlockOrder,
+                Check::NoPanic,
+                Check::CanHandle
+            ]
+        );
+        assert!(failures[2].message.contains("seed 0"));
+    }
+
+    #[test]
+    fn test_invalid_sample() {
+        let failures = Conformance::new(&SloppyParser, "{").run();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].check, Check::Sample);
+
+        let failures = Conformance::new(&SloppyParser, r#"{"id": "1"}"#)
+            .id_at(Some("/uuid"))
+            .run();
+        assert!(failures.iter().any(|f| f.check == Check::Sample));
+    }
+
+    #[cfg(feature = "anthropic")]
+    #[test]
+    fn test_anthropic_conforms() {
+        let sample = r#"{
+            "id": "msg_1",
+            "model": "claude-sonnet-4-5",
+            "content": [
+                {"type": "thinking", "thinking": "Weather lookup", "signature": "sig"},
+                {"type": "text", "text": "Checking the weather."},
+                {"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {"city": "Paris"}}
+            ],
+            "stop_reason": "tool_use",
+            "usage": {"input_tokens": 10, "output_tokens": 5}
+        }"#;
+        Conformance::new(&crate::providers::AnthropicParser, sample)
+            .blocks_at("/content")
+            .assert();
+    }
+
+    #[cfg(feature = "openai")]
+    #[test]
+    fn test_openai_conforms() {
+        let sample = r#"{
+            "id": "chatcmpl-1",
+            "model": "gpt-4o",
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": "<think>Hmm</think>Hi", "tool_calls": [
+                {"id": "call_1", "type": "function", "function": {"name": "a", "arguments": "{\"x\": 1}"}},
+                {"id": "call_2", "type": "function", "function": {"name": "b", "arguments": "{}"}}
+            ]}, "logprobs": {"content": [{"token": "Hi", "logprob": -0.5, "bytes": [72, 105], "top_logprobs": []}]}, "finish_reason": "tool_calls"}],
+            "usage": {"prompt_tokens": 3, "completion_tokens": 4}
+        }"#;
+        Conformance::new(&crate::providers::OpenAiParser, sample).assert();
+
+        let sample = r#"{
+            "id": "resp_1",
+            "model": "gpt-4.1",
+            "status": "completed",
+            "output": [
+                {"type": "reasoning", "id": "rs_1", "summary": [{"type": "summary_text", "text": "Hmm"}]},
+                {"type": "message", "role": "assistant", "content": [{"type": "output_text", "text": "Hi", "annotations": [{"type": "url_citation", "start_index": 0, "end_index": 2, "url": "https://example.com"}]}]},
+                {"type": "web_search_call", "id": "ws_1", "status": "completed", "action": {"query": "hi"}},
+                {"type": "function_call", "call_id": "call_1", "name": "f", "arguments": "{}"}
+            ]
+        }"#;
+        Conformance::new(&crate::providers::OpenAiResponsesParser, sample)
+            .blocks_at("/output")
+            .assert();
+    }
+
+    #[cfg(feature = "gemini")]
+    #[test]
+    fn test_gemini_conforms() {
+        let sample = r#"{
+            "responseId": "resp_1",
+            "modelVersion": "gemini-2.5-flash",
+            "candidates": [{"content": {"role": "model", "parts": [
+                {"text": "Thinking", "thought": true},
+                {"text": "Hi"},
+                {"functionCall": {"id": "fc_1", "name": "f", "args": {"x": 1}}},
+                {"executableCode": {"language": "PYTHON", "code": "print(1)"}}
+            ]}, "finishReason": "STOP", "safetyRatings": [{"category": "HARM_CATEGORY_HARASSMENT", "probability": "NEGLIGIBLE"}]}],
+            "usageMetadata": {"promptTokenCount": 1, "candidatesTokenCount": 2}
+        }"#;
+        Conformance::new(&crate::providers::GeminiParser, sample)
+            .id_at(Some("/responseId"))
+            .blocks_at("/candidates/0/content/parts")
+            .assert();
+    }
+
+    #[cfg(feature = "bedrock")]
+    #[test]
+    fn test_bedrock_conforms() {
+        let sample = r#"{
+            "ResponseMetadata": {"RequestId": "req_1"},
+            "output": {"message": {"role": "assistant", "content": [
+                {"reasoningContent": {"reasoningText": {"text": "Hmm", "signature": "sig"}}},
+                {"text": "Hi"},
+                {"toolUse": {"toolUseId": "tooluse_1", "name": "f", "input": {}}}
+            ]}},
+            "stopReason": "tool_use",
+            "usage": {"inputTokens": 1, "outputTokens": 2}
+        }"#;
+        // The Converse body has no id, so the request id is optional
+        Conformance::new(&crate::providers::BedrockConverseParser, sample)
+            .model("anthropic.claude-3-5-sonnet-20240620-v1:0")
+            .id_at(None)
+            .blocks_at("/output/message/content")
+            .assert();
+
+        let sample = r#"{"id": "msg_1", "type": "message", "role": "assistant", "model": "claude-3-5-sonnet-20240620", "content": [{"type": "text", "text": "Hi"}]}"#;
+        Conformance::new(&crate::providers::BedrockInvokeParser, sample)
+            .model("anthropic.claude-3-5-sonnet-20240620-v1:0")
+            .assert();
+    }
+
+    #[cfg(feature = "cohere")]
+    #[test]
+    fn test_cohere_conforms() {
+        let sample = r#"{
+            "id": "resp_1",
+            "finish_reason": "COMPLETE",
+            "message": {"role": "assistant", "tool_plan": "Plan", "content": [
+                {"type": "thinking", "thinking": "Hmm"},
+                {"type": "text", "text": "Hello"}
+            ], "citations": [{"start": 0, "end": 5, "text": "Hello", "sources": [{"type": "document", "id": "doc:0"}]}]},
+            "usage": {"tokens": {"input_tokens": 1, "output_tokens": 2}}
+        }"#;
+        Conformance::new(&crate::providers::CohereParser, sample)
+            .model("command-a-03-2025")
+            .assert();
+    }
+
+    #[cfg(feature = "mistral")]
+    #[test]
+    fn test_mistral_conforms() {
+        let sample = r#"{
+            "id": "cmpl-1",
+            "model": "magistral-medium-2509",
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": [
+                {"type": "thinking", "thinking": [{"type": "text", "text": "Hmm"}]},
+                {"type": "text", "text": "Hi [TOOL_CALLS][{\"name\": \"f\", \"arguments\": {}}]"}
+            ]}, "finish_reason": "stop"}]
+        }"#;
+        Conformance::new(&crate::providers::MistralParser, sample).assert();
+    }
+
+    #[cfg(feature = "ollama")]
+    #[test]
+    fn test_ollama_conforms() {
+        let sample = r#"{
+            "model": "llama3.2",
+            "message": {"role": "assistant", "content": "Hi", "thinking": "Hmm", "tool_calls": [{"function": {"name": "f", "arguments": {}}}]},
+            "done": true,
+            "done_reason": "stop",
+            "prompt_eval_count": 1,
+            "eval_count": 2
+        }"#;
+        // Ollama responses have no id
+        Conformance::new(&crate::providers::OllamaParser, sample)
+            .id_at(None)
+            .assert();
+    }
+}
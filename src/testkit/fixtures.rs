@@ -0,0 +1,378 @@
+//! Snapshot tests of captured provider responses
+//!
+//! A fixture directory holds raw provider responses, one per `.json` file,
+//! exactly as returned by the provider's API. Next to each fixture, a
+//! `.snap.json` file records what it normalizes to: either
+//! `{"frame": <ContentFrame>}` or `{"error": "<message>"}`. For formats that do
+//! not include the model in the response body, an optional `.model` file holds
+//! the model the request was sent to.
+//!
+//! Streamed responses are captured as `.sse` files holding the server-sent
+//! events as received. They are reassembled with [`crate::stream::assemble`]
+//! before parsing, and snapshotted the same way. A stream and a regular
+//! response in the same directory need different names.
+//!
+//! ```text
+//! tests/fixtures/acme/
+//!     chat.json
+//!     chat.snap.json
+//!     chat_stream.sse
+//!     chat_stream.snap.json
+//!     rate_limit_error.json
+//!     rate_limit_error.snap.json
+//! ```
+//!
+//! [`FixtureSet::assert`] checks every fixture against its snapshot, so a
+//! change in a provider's format shows up as soon as a new capture is added.
+//! After adding fixtures, or changing a parser on purpose, run the tests with
+//! the `ADAPTOGEN_UPDATE_FIXTURES` environment variable set to write the
+//! snapshots, and review the changes.
+//!
+//! The crate's own corpus lives in `tests/fixtures`.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # #[cfg(feature = "anthropic")]
+//! # {
+//! use adaptogen::providers::AnthropicParser;
+//! use adaptogen::testkit::FixtureSet;
+//!
+//! FixtureSet::new("tests/fixtures/anthropic").assert(|fixture| fixture.parse_with(&AnthropicParser));
+//! # }
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use crate::normalized::ContentFrame;
+use crate::parser::{ModelResponseParser, ParseError};
+use crate::registry::ParserRegistry;
+use crate::stream::{self, SseDecoder};
+
+/// Environment variable that makes [`FixtureSet`] write snapshots instead of checking them
+pub const UPDATE_ENV: &str = "ADAPTOGEN_UPDATE_FIXTURES";
+
+/// A captured provider response
+#[derive(Debug, Clone)]
+pub struct Fixture {
+    /// Path of the fixture relative to the fixture directory, without extension
+    pub name: String,
+    /// Path of the response file
+    pub path: PathBuf,
+    /// The raw response body, or the captured events of a stream
+    pub raw: String,
+    /// The model the request was sent to, from the fixture's `.model` file
+    pub model: Option<String>,
+}
+
+impl Fixture {
+    /// Whether the fixture is a captured stream of server-sent events
+    pub fn is_stream(&self) -> bool {
+        self.path.extension().is_some_and(|extension| extension == "sse")
+    }
+
+    /// The response body to parse, reassembled from its events for a stream
+    pub fn body(&self) -> Result<String, ParseError> {
+        if self.is_stream() {
+            Ok(stream::assemble(&SseDecoder::decode(&self.raw))?.to_string())
+        } else {
+            Ok(self.raw.clone())
+        }
+    }
+
+    /// Parse the fixture with `parser`, passing on the model if the fixture has one
+    pub fn parse_with(&self, parser: &dyn ModelResponseParser) -> Result<ContentFrame, ParseError> {
+        let body = self.body()?;
+        match &self.model {
+            Some(model) => parser.parse_with_model(&body, model),
+            None => parser.parse(&body),
+        }
+    }
+
+    /// Parse the fixture with `registry`, passing on the model if the fixture has one
+    pub fn parse_with_registry(&self, registry: &ParserRegistry) -> Result<ContentFrame, ParseError> {
+        let body = self.body()?;
+        match &self.model {
+            Some(model) => registry.parse_with_model(&body, model),
+            None => registry.parse(&body),
+        }
+    }
+
+    fn snapshot_path(&self) -> PathBuf {
+        self.path.with_extension("snap.json")
+    }
+}
+
+/// Error checking a fixture against its snapshot
+#[derive(Debug, thiserror::Error)]
+pub enum FixtureError {
+    /// A fixture or snapshot could not be read or written
+    #[error("{}: {source}", path.display())]
+    Io {
+        /// The file that could not be accessed
+        path: PathBuf,
+        /// The underlying error
+        source: std::io::Error,
+    },
+
+    /// A fixture has no snapshot yet
+    #[error("{}: no snapshot, run with ADAPTOGEN_UPDATE_FIXTURES=1 to write it", path.display())]
+    MissingSnapshot {
+        /// The fixture without a snapshot
+        path: PathBuf,
+    },
+
+    /// A snapshot is not valid JSON
+    #[error("{}: invalid snapshot: {source}", path.display())]
+    InvalidSnapshot {
+        /// The snapshot file
+        path: PathBuf,
+        /// The JSON error
+        source: serde_json::Error,
+    },
+
+    /// A fixture does not normalize to its snapshot
+    #[error("{}: output differs from snapshot\n--- expected\n{expected}\n--- actual\n{actual}", path.display())]
+    Mismatch {
+        /// The fixture
+        path: PathBuf,
+        /// The snapshot contents
+        expected: String,
+        /// The snapshot the fixture normalizes to now
+        actual: String,
+    },
+}
+
+/// The fixtures in a directory and its subdirectories
+///
+/// Snapshots are checked, or written when the `ADAPTOGEN_UPDATE_FIXTURES`
+/// environment variable is set (see [`update`](Self::update)).
+#[derive(Debug, Clone)]
+pub struct FixtureSet {
+    dir: PathBuf,
+    update: bool,
+}
+
+impl FixtureSet {
+    /// Fixtures in `dir`, relative to the current directory
+    ///
+    /// Cargo runs tests from the package root, so paths such as
+    /// `tests/fixtures/acme` work in tests.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        FixtureSet {
+            dir: dir.into(),
+            update: std::env::var_os(UPDATE_ENV).is_some(),
+        }
+    }
+
+    /// Whether to write snapshots instead of checking them
+    pub fn update(mut self, update: bool) -> Self {
+        self.update = update;
+        self
+    }
+
+    /// Read every fixture, sorted by name
+    pub fn fixtures(&self) -> Result<Vec<Fixture>, FixtureError> {
+        let mut paths = Vec::new();
+        collect_fixtures(&self.dir, &mut paths)?;
+        paths.sort();
+
+        paths
+            .into_iter()
+            .map(|path| {
+                let name = path
+                    .strip_prefix(&self.dir)
+                    .unwrap_or(&path)
+                    .with_extension("")
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                let model_path = path.with_extension("model");
+                let model = match fs::read_to_string(&model_path) {
+                    Ok(model) => Some(model.trim().to_string()),
+                    Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+                    Err(source) => return Err(FixtureError::Io { path: model_path, source }),
+                };
+                Ok(Fixture {
+                    name,
+                    raw: read(&path)?,
+                    path,
+                    model,
+                })
+            })
+            .collect()
+    }
+
+    /// Check every fixture against its snapshot
+    ///
+    /// `parse` normalizes a fixture, usually with [`Fixture::parse_with`] or
+    /// [`Fixture::parse_with_registry`]. Returns the number of fixtures
+    /// checked, or every failure.
+    pub fn check<F>(&self, parse: F) -> Result<usize, Vec<FixtureError>>
+    where
+        F: Fn(&Fixture) -> Result<ContentFrame, ParseError>,
+    {
+        let fixtures = self.fixtures().map_err(|error| vec![error])?;
+        let failures: Vec<FixtureError> = fixtures
+            .iter()
+            .filter_map(|fixture| self.check_fixture(fixture, &parse).err())
+            .collect();
+
+        if failures.is_empty() {
+            Ok(fixtures.len())
+        } else {
+            Err(failures)
+        }
+    }
+
+    /// Check every fixture against its snapshot, panicking with all failures
+    ///
+    /// Also panics if the directory holds no fixtures, which usually means
+    /// the path is wrong.
+    pub fn assert<F>(&self, parse: F)
+    where
+        F: Fn(&Fixture) -> Result<ContentFrame, ParseError>,
+    {
+        match self.check(parse) {
+            Ok(0) => panic!("no fixtures found in {}", self.dir.display()),
+            Ok(_) => {}
+            Err(failures) => {
+                let failures: Vec<String> = failures.iter().map(|f| f.to_string()).collect();
+                panic!("{} fixture(s) failed:\n\n{}", failures.len(), failures.join("\n\n"));
+            }
+        }
+    }
+
+    fn check_fixture<F>(&self, fixture: &Fixture, parse: &F) -> Result<(), FixtureError>
+    where
+        F: Fn(&Fixture) -> Result<ContentFrame, ParseError>,
+    {
+        let snapshot = match parse(fixture) {
+            Ok(frame) => json!({ "frame": frame }),
+            Err(error) => json!({ "error": error.to_string() }),
+        };
+        let actual = serde_json::to_string_pretty(&snapshot).expect("snapshots always serialize") + "\n";
+
+        let path = fixture.snapshot_path();
+        if self.update {
+            return fs::write(&path, actual).map_err(|source| FixtureError::Io { path, source });
+        }
+        if !path.exists() {
+            return Err(FixtureError::MissingSnapshot { path: fixture.path.clone() });
+        }
+
+        let expected = read(&path)?;
+        // Compare parsed JSON so formatting of the snapshot does not matter, and
+        // numbers go through the same conversion on both sides
+        let expected_json: Value =
+            serde_json::from_str(&expected).map_err(|source| FixtureError::InvalidSnapshot { path, source })?;
+        let actual_json: Value = serde_json::from_str(&actual).expect("snapshots are valid JSON");
+
+        if expected_json == actual_json {
+            Ok(())
+        } else {
+            Err(FixtureError::Mismatch {
+                path: fixture.path.clone(),
+                expected,
+                actual,
+            })
+        }
+    }
+}
+
+/// Collect `.json` files that are not snapshots and `.sse` files, recursively
+fn collect_fixtures(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<(), FixtureError> {
+    let entries = fs::read_dir(dir).map_err(|source| FixtureError::Io {
+        path: dir.to_path_buf(),
+        source,
+    })?;
+
+    for entry in entries {
+        let path = entry
+            .map_err(|source| FixtureError::Io {
+                path: dir.to_path_buf(),
+                source,
+            })?
+            .path();
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        if path.is_dir() {
+            collect_fixtures(&path, paths)?;
+        } else if (name.ends_with(".json") && !name.ends_with(".snap.json")) || name.ends_with(".sse") {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn read(path: &Path) -> Result<String, FixtureError> {
+    fs::read_to_string(path).map_err(|source| FixtureError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run the crate's own corpus through the default registry
+    #[cfg(all(
+        feature = "anthropic",
+        feature = "openai",
+        feature = "gemini",
+        feature = "bedrock",
+        feature = "cohere",
+        feature = "mistral",
+        feature = "ollama"
+    ))]
+    #[test]
+    fn test_corpus() {
+        let registry = ParserRegistry::with_defaults();
+        FixtureSet::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures"))
+            .assert(|fixture| fixture.parse_with_registry(&registry));
+    }
+
+    #[test]
+    fn test_snapshot_lifecycle() {
+        let dir = std::env::temp_dir().join(format!("adaptogen-fixtures-{}", std::process::id()));
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("nested/hello.json"), r#"{"id": "1", "text": "Hello"}"#).unwrap();
+        fs::write(dir.join("nested/hello.model"), "acme-1\n").unwrap();
+        fs::write(dir.join("broken.json"), "{").unwrap();
+
+        let parse = |fixture: &Fixture| -> Result<ContentFrame, ParseError> {
+            let json: Value = serde_json::from_str(&fixture.raw)?;
+            let text = json["text"].as_str().unwrap_or_default();
+            let model = fixture.model.clone().unwrap_or_default();
+            Ok(ContentFrame::new(json["id"].as_str().unwrap(), model, vec![crate::normalized::ContentBlock::text(text)]))
+        };
+
+        let fixtures = FixtureSet::new(&dir).fixtures().unwrap();
+        let names: Vec<&str> = fixtures.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["broken", "nested/hello"]);
+        assert_eq!(fixtures[1].model.as_deref(), Some("acme-1"));
+
+        let failures = FixtureSet::new(&dir).update(false).check(parse).unwrap_err();
+        assert_eq!(failures.len(), 2);
+        assert!(matches!(failures[0], FixtureError::MissingSnapshot { .. }));
+
+        assert_eq!(FixtureSet::new(&dir).update(true).check(parse).unwrap(), 2);
+        assert_eq!(FixtureSet::new(&dir).update(false).check(parse).unwrap(), 2);
+        let snapshot = fs::read_to_string(dir.join("broken.snap.json")).unwrap();
+        assert!(snapshot.contains("Invalid JSON"));
+
+        fs::write(dir.join("nested/hello.json"), r#"{"id": "1", "text": "Goodbye"}"#).unwrap();
+        let failures = FixtureSet::new(&dir).update(false).check(parse).unwrap_err();
+        match &failures[..] {
+            [FixtureError::Mismatch { expected, actual, .. }] => {
+                assert!(expected.contains("Hello"));
+                assert!(actual.contains("Goodbye"));
+            }
+            _ => panic!("Expected a single mismatch, got {failures:?}"),
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,259 @@
+//! Fuzz-style generators of parser inputs
+//!
+//! [`Generator`] produces malformed and unexpected responses: arbitrary JSON,
+//! mutations of a valid sample response, and truncated response bodies. A
+//! parser should reject these with a [`ParseError`](crate::parser::ParseError)
+//! or parse what it can, but never panic. The generator is seeded and
+//! deterministic, so failures can be reproduced.
+//!
+//! # Examples
+//!
+//! ```
+//! use adaptogen::testkit::Generator;
+//!
+//! let sample = r#"{"id": "msg_1", "model": "acme-1", "content": [{"type": "text", "text": "Hi"}]}"#;
+//! let mut generator = Generator::new(42);
+//! for _ in 0..100 {
+//!     let input = generator.input(sample);
+//!     // Parse `input` with your parser; any outcome but a panic is fine
+//!     # let _ = input;
+//! }
+//! ```
+
+use serde_json::{Map, Value};
+
+/// Object keys used by common provider formats, so generated objects reach parser code paths
+const KEYS: &[&str] = &[
+    "id", "model", "type", "role", "content", "text", "message", "choices", "delta", "tool_calls",
+    "function", "name", "arguments", "input", "candidates", "parts", "output", "usage", "error",
+    "thinking", "signature", "citations", "start", "end", "index", "finish_reason", "stop_reason",
+];
+
+/// Strings likely to trip up parsers: empty, markup, escapes, multi-byte characters
+const STRINGS: &[&str] = &[
+    "", " ", "a", "null", "{", "[]", "<think>", "</think>", "[TOOL_CALLS]", "\u{0}", "\n\n",
+    "é", "🦀", "日本語", "\u{feff}", "\\u0000", "{\"a\": 1}", "-1",
+];
+
+/// Deterministic generator of fuzz-style parser inputs
+#[derive(Debug, Clone)]
+pub struct Generator {
+    state: u64,
+}
+
+impl Generator {
+    /// A generator whose outputs are determined by `seed`
+    pub fn new(seed: u64) -> Self {
+        Generator { state: seed }
+    }
+
+    /// A random input derived from a valid `sample` response
+    ///
+    /// Picks between a mutation of the sample, a truncation of it, and
+    /// arbitrary JSON. `sample` need not be valid JSON, in which case only
+    /// truncations and arbitrary JSON are produced.
+    pub fn input(&mut self, sample: &str) -> String {
+        let parsed = serde_json::from_str::<Value>(sample).ok();
+        match (self.below(4), parsed) {
+            (0 | 1, Some(sample)) => self.mutate(&sample).to_string(),
+            (2, _) => self.truncate(sample),
+            _ => self.json().to_string(),
+        }
+    }
+
+    /// Arbitrary JSON, nested up to four levels
+    pub fn json(&mut self) -> Value {
+        self.json_at_depth(4)
+    }
+
+    /// `sample` with one to three random changes
+    ///
+    /// Each change removes an object field or array element, replaces a value
+    /// with arbitrary JSON or `null`, or empties an array or object.
+    pub fn mutate(&mut self, sample: &Value) -> Value {
+        let mut value = sample.clone();
+        for _ in 0..=self.below(3) {
+            let mut pointers = Vec::new();
+            collect_pointers(&value, String::new(), &mut pointers);
+            let pointer = pointers[self.below(pointers.len())].clone();
+
+            match self.below(4) {
+                0 => remove(&mut value, &pointer),
+                1 => {
+                    let replacement = self.json_at_depth(2);
+                    if let Some(target) = value.pointer_mut(&pointer) {
+                        *target = replacement;
+                    }
+                }
+                2 => {
+                    if let Some(target) = value.pointer_mut(&pointer) {
+                        *target = Value::Null;
+                    }
+                }
+                _ => match value.pointer_mut(&pointer) {
+                    Some(Value::Array(items)) => items.clear(),
+                    Some(Value::Object(fields)) => fields.clear(),
+                    _ => {}
+                },
+            }
+        }
+        value
+    }
+
+    /// `raw` cut off at a random character boundary
+    pub fn truncate(&mut self, raw: &str) -> String {
+        let boundaries: Vec<usize> = raw.char_indices().map(|(i, _)| i).collect();
+        match boundaries.len() {
+            0 => String::new(),
+            n => raw[..boundaries[self.below(n)]].to_string(),
+        }
+    }
+
+    fn json_at_depth(&mut self, depth: usize) -> Value {
+        let kinds = if depth == 0 { 4 } else { 6 };
+        match self.below(kinds) {
+            0 => Value::Null,
+            1 => Value::Bool(self.below(2) == 1),
+            2 => self.number(),
+            3 => Value::String(self.string()),
+            4 => Value::Array((0..self.below(4)).map(|_| self.json_at_depth(depth - 1)).collect()),
+            _ => {
+                let mut object = Map::new();
+                for _ in 0..self.below(5) {
+                    let key = if self.below(4) == 0 {
+                        self.string()
+                    } else {
+                        KEYS[self.below(KEYS.len())].to_string()
+                    };
+                    object.insert(key, self.json_at_depth(depth - 1));
+                }
+                Value::Object(object)
+            }
+        }
+    }
+
+    fn number(&mut self) -> Value {
+        match self.below(5) {
+            0 => Value::from(0),
+            1 => Value::from(-1),
+            2 => Value::from(u64::MAX),
+            3 => Value::from(i64::MIN),
+            _ => serde_json::Number::from_f64(self.next_u64() as f64 / 1e6 - 9e12)
+                .map(Value::Number)
+                .unwrap_or(Value::Null),
+        }
+    }
+
+    fn string(&mut self) -> String {
+        match self.below(3) {
+            0 => STRINGS[self.below(STRINGS.len())].to_string(),
+            1 => STRINGS[self.below(STRINGS.len())].repeat(1 + self.below(64)),
+            _ => (0..self.below(12))
+                .map(|_| char::from_u32(self.below(0x3000) as u32).unwrap_or('?'))
+                .collect(),
+        }
+    }
+
+    /// A number in `0..n`, or 0 when `n` is 0
+    fn below(&mut self, n: usize) -> usize {
+        if n == 0 {
+            0
+        } else {
+            (self.next_u64() % n as u64) as usize
+        }
+    }
+
+    /// SplitMix64
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// JSON pointers to every value in `value`, including the root
+fn collect_pointers(value: &Value, pointer: String, pointers: &mut Vec<String>) {
+    match value {
+        Value::Object(fields) => {
+            for (key, field) in fields {
+                let escaped = key.replace('~', "~0").replace('/', "~1");
+                collect_pointers(field, format!("{pointer}/{escaped}"), pointers);
+            }
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                collect_pointers(item, format!("{pointer}/{index}"), pointers);
+            }
+        }
+        _ => {}
+    }
+    pointers.push(pointer);
+}
+
+/// Remove the value at `pointer` from its parent; the root is replaced by `null`
+pub(crate) fn remove(value: &mut Value, pointer: &str) {
+    let Some((parent, key)) = pointer.rsplit_once('/') else {
+        *value = Value::Null;
+        return;
+    };
+    let key = key.replace("~1", "/").replace("~0", "~");
+    match value.pointer_mut(parent) {
+        Some(Value::Object(fields)) => {
+            fields.remove(&key);
+        }
+        Some(Value::Array(items)) => {
+            if let Some(index) = key.parse::<usize>().ok().filter(|&i| i < items.len()) {
+                items.remove(index);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_deterministic() {
+        let sample = r#"{"id": "1", "content": [{"type": "text", "text": "Hi"}]}"#;
+        let mut first = Generator::new(7);
+        let mut second = Generator::new(7);
+        for _ in 0..50 {
+            assert_eq!(first.input(sample), second.input(sample));
+        }
+        assert_ne!(Generator::new(1).json(), Generator::new(2).json());
+    }
+
+    #[test]
+    fn test_truncate_keeps_char_boundaries() {
+        let mut generator = Generator::new(3);
+        for _ in 0..50 {
+            let cut = generator.truncate("日本語 🦀");
+            assert!("日本語 🦀".starts_with(&cut));
+            assert!(cut.len() < "日本語 🦀".len());
+        }
+        assert_eq!(generator.truncate(""), "");
+    }
+
+    #[test]
+    fn test_mutate_changes_sample() {
+        let sample = json!({"id": "1", "a/b": {"c": [1, 2]}});
+        let mut generator = Generator::new(11);
+        let changed = (0..20).filter(|_| generator.mutate(&sample) != sample).count();
+        assert!(changed > 10);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut value = json!({"id": "1", "a/b": {"c": [1, 2]}});
+        remove(&mut value, "/a~1b/c/0");
+        remove(&mut value, "/id");
+        assert_eq!(value, json!({"a/b": {"c": [2]}}));
+        remove(&mut value, "");
+        assert_eq!(value, Value::Null);
+    }
+}
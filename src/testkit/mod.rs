@@ -0,0 +1,25 @@
+//! Test helpers for parser implementations
+//!
+//! The same helpers that test the built-in parsers are available to authors of
+//! custom [`ModelResponseParser`](crate::parser::ModelResponseParser)
+//! implementations:
+//!
+//! - [`fixtures`]: snapshot tests of captured provider responses
+//! - [`conformance`]: checks that a parser behaves like the built-in ones
+//! - [`generate`]: fuzz-style generators of malformed and unexpected input
+//!
+//! Requires the `testkit` feature, which is meant to be enabled for
+//! dev-dependencies only:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! adaptogen = { version = "0.1", features = ["testkit"] }
+//! ```
+
+pub mod conformance;
+pub mod fixtures;
+pub mod generate;
+
+pub use conformance::{Check, Conformance, ConformanceFailure};
+pub use fixtures::{Fixture, FixtureError, FixtureSet};
+pub use generate::Generator;
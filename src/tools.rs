@@ -0,0 +1,547 @@
+//! Typed tool definitions and decoding of tool calls
+//!
+//! A [`Tool`] is a Rust type describing one tool: its name, description and
+//! the JSON Schema of its input, which the type deserializes from. The
+//! [`tool_set!`](crate::tool_set) macro declares an enum of all the tools an
+//! application knows about, so a [`ContentBlock::ToolUse`] can be decoded
+//! straight into a typed value, whichever provider it came from.
+//!
+//! Tools are described to providers with [`ToolDefinition`]s, which the
+//! `render_*` functions turn into each provider's request format.
+//!
+//! # Examples
+//!
+//! ```
+//! use adaptogen::normalized::{ContentBlock, ContentFrame};
+//! use adaptogen::tool_set;
+//! use adaptogen::tools::Tool;
+//! use serde::Deserialize;
+//! use serde_json::{json, Value};
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct GetWeather {
+//!     city: String,
+//! }
+//!
+//! impl Tool for GetWeather {
+//!     const NAME: &'static str = "get_weather";
+//!     const DESCRIPTION: &'static str = "Get the current weather in a city";
+//!
+//!     fn input_schema() -> Value {
+//!         json!({"type": "object", "properties": {"city": {"type": "string"}}, "required": ["city"]})
+//!     }
+//! }
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct GetTime {}
+//!
+//! impl Tool for GetTime {
+//!     const NAME: &'static str = "get_time";
+//!     const DESCRIPTION: &'static str = "Get the current time";
+//!
+//!     fn input_schema() -> Value {
+//!         json!({"type": "object", "properties": {}})
+//!     }
+//! }
+//!
+//! tool_set! {
+//!     #[derive(Debug)]
+//!     enum AgentTool {
+//!         Weather(GetWeather),
+//!         Time(GetTime),
+//!     }
+//! }
+//!
+//! let frame = ContentFrame::new("msg_1", "my-model", vec![ContentBlock::ToolUse {
+//!     id: "call_1".to_string(),
+//!     name: "get_weather".to_string(),
+//!     input: json!({"city": "Paris"}),
+//! }]);
+//!
+//! for tool_use in frame.tool_uses() {
+//!     match tool_use.decode::<AgentTool>().unwrap() {
+//!         AgentTool::Weather(weather) => assert_eq!(weather.city, "Paris"),
+//!         AgentTool::Time(_) => unreachable!(),
+//!     }
+//! }
+//! ```
+
+use serde::de::DeserializeOwned;
+use serde_json::{json, Map, Value};
+use thiserror::Error;
+
+use crate::normalized::{ToolDefinition, ToolUse};
+
+/// Schema keywords Gemini accepts, a subset of OpenAPI 3.0
+const GEMINI_SCHEMA_KEYWORDS: &[&str] = &[
+    "type",
+    "format",
+    "title",
+    "description",
+    "nullable",
+    "enum",
+    "properties",
+    "required",
+    "propertyOrdering",
+    "items",
+    "minItems",
+    "maxItems",
+    "minimum",
+    "maximum",
+    "minLength",
+    "maxLength",
+    "pattern",
+    "minProperties",
+    "maxProperties",
+    "anyOf",
+];
+
+/// How deeply `$ref`s are followed before giving up on recursive schemas
+const MAX_REF_DEPTH: usize = 16;
+
+/// Errors that can occur when decoding a tool call
+#[derive(Error, Debug)]
+pub enum ToolError {
+    /// The call is for a tool that is not part of the tool set
+    #[error("Unknown tool: {name}")]
+    UnknownTool {
+        /// Name of the tool the model called
+        name: String,
+    },
+
+    /// The call's input does not match the tool's input type
+    #[error("Invalid input for tool {name}: {source}")]
+    InvalidInput {
+        /// Name of the tool the model called
+        name: String,
+        /// Why the input could not be deserialized
+        source: serde_json::Error,
+    },
+}
+
+/// A tool the model can call, described by a Rust type
+///
+/// The implementing type is the tool's input: calls are decoded by
+/// deserializing their input into it, so its serde representation should
+/// match [`input_schema`](Tool::input_schema).
+pub trait Tool: DeserializeOwned {
+    /// Name the model uses to call the tool
+    const NAME: &'static str;
+
+    /// Description of what the tool does, shown to the model
+    const DESCRIPTION: &'static str;
+
+    /// JSON Schema of the tool's input
+    ///
+    /// With the `schema` feature, a type deriving `schemars::JsonSchema` can
+    /// return `input_schema_for::<Self>()` from this module. There is no
+    /// default implementation, as it would require every tool to implement
+    /// `JsonSchema`.
+    fn input_schema() -> Value;
+
+    /// The provider-independent definition of this tool
+    fn definition() -> ToolDefinition {
+        ToolDefinition::new(Self::NAME, Self::DESCRIPTION, Self::input_schema())
+    }
+
+    /// Decode a call to this tool
+    ///
+    /// Fails with [`ToolError::UnknownTool`] if the call is for another tool.
+    fn from_tool_use(tool_use: ToolUse<'_>) -> Result<Self, ToolError> {
+        if tool_use.name != Self::NAME {
+            return Err(ToolError::UnknownTool {
+                name: tool_use.name.to_string(),
+            });
+        }
+        tool_use.input_as().map_err(|source| ToolError::InvalidInput {
+            name: Self::NAME.to_string(),
+            source,
+        })
+    }
+}
+
+/// JSON Schema (draft 7) of `T`, generated from its `schemars::JsonSchema` implementation
+///
+/// Meant for [`Tool::input_schema`]: deriving both `Deserialize` and
+/// `JsonSchema` on a tool type keeps its schema in sync with the input it
+/// decodes. Nested types are placed under `definitions` and referenced with
+/// `$ref`, which [`render_gemini`] inlines.
+///
+/// Requires the `schema` feature.
+#[cfg(feature = "schema")]
+pub fn input_schema_for<T: schemars::JsonSchema>() -> Value {
+    let schema = schemars::gen::SchemaSettings::draft07()
+        .into_generator()
+        .into_root_schema_for::<T>();
+    serde_json::to_value(schema).expect("schemas always serialize")
+}
+
+/// A closed set of tools, usually an enum declared with [`tool_set!`](crate::tool_set)
+pub trait ToolSet: Sized {
+    /// Names of the tools in the set
+    fn names() -> Vec<&'static str>;
+
+    /// Definitions of the tools in the set, to be rendered into a request
+    fn definitions() -> Vec<ToolDefinition>;
+
+    /// Decode a call to any tool in the set
+    fn decode(tool_use: ToolUse<'_>) -> Result<Self, ToolError>;
+}
+
+/// Declare an enum of [`Tool`]s implementing [`ToolSet`]
+///
+/// Each variant wraps one tool type. Calls are dispatched on the tool's
+/// [`NAME`](Tool::NAME); see the [module documentation](crate::tools) for an
+/// example.
+#[macro_export]
+macro_rules! tool_set {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $($variant:ident($tool:ty)),+ $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis enum $name {
+            $($variant($tool)),+
+        }
+
+        impl $crate::tools::ToolSet for $name {
+            fn names() -> ::std::vec::Vec<&'static str> {
+                ::std::vec![$(<$tool as $crate::tools::Tool>::NAME),+]
+            }
+
+            fn definitions() -> ::std::vec::Vec<$crate::normalized::ToolDefinition> {
+                ::std::vec![$(<$tool as $crate::tools::Tool>::definition()),+]
+            }
+
+            fn decode(
+                tool_use: $crate::normalized::ToolUse<'_>,
+            ) -> ::std::result::Result<Self, $crate::tools::ToolError> {
+                $(
+                    if tool_use.name == <$tool as $crate::tools::Tool>::NAME {
+                        return <$tool as $crate::tools::Tool>::from_tool_use(tool_use).map($name::$variant);
+                    }
+                )+
+                ::std::result::Result::Err($crate::tools::ToolError::UnknownTool {
+                    name: tool_use.name.to_string(),
+                })
+            }
+        }
+    };
+}
+
+/// Render tools as the Anthropic Messages API `tools` array
+pub fn render_anthropic(tools: &[ToolDefinition]) -> Value {
+    tools
+        .iter()
+        .map(|tool| {
+            json!({
+                "name": tool.name,
+                "description": tool.description,
+                "input_schema": tool.input_schema,
+            })
+        })
+        .collect()
+}
+
+/// Render tools as the OpenAI Chat Completions `tools` array
+pub fn render_openai(tools: &[ToolDefinition]) -> Value {
+    tools
+        .iter()
+        .map(|tool| {
+            json!({
+                "type": "function",
+                "function": {
+                    "name": tool.name,
+                    "description": tool.description,
+                    "parameters": tool.input_schema,
+                },
+            })
+        })
+        .collect()
+}
+
+/// Render tools as the Gemini `tools` array, holding one `functionDeclarations` entry
+///
+/// Input schemas are converted with [`gemini_schema`].
+pub fn render_gemini(tools: &[ToolDefinition]) -> Value {
+    let declarations: Vec<Value> = tools
+        .iter()
+        .map(|tool| {
+            json!({
+                "name": tool.name,
+                "description": tool.description,
+                "parameters": gemini_schema(&tool.input_schema),
+            })
+        })
+        .collect();
+    json!([{ "functionDeclarations": declarations }])
+}
+
+/// Render tools as text, for models prompted to call tools without native support
+pub fn render_prompt(tools: &[ToolDefinition]) -> String {
+    let mut prompt = String::from("You have access to the following tools:\n");
+    for tool in tools {
+        prompt.push_str(&format!("\n## {}\n", tool.name));
+        if !tool.description.is_empty() {
+            prompt.push_str(&format!("{}\n", tool.description));
+        }
+        prompt.push_str(&format!("Input schema: {}\n", tool.input_schema));
+    }
+    prompt
+}
+
+/// Convert a JSON Schema into the subset Gemini accepts
+///
+/// Local `$ref`s are inlined, `oneOf` becomes `anyOf`, `const` becomes a
+/// single-value `enum`, a `null` member of a `type` array becomes
+/// `nullable: true`, and keywords Gemini rejects (such as
+/// `additionalProperties`, `$schema` or `default`) are dropped.
+pub fn gemini_schema(schema: &Value) -> Value {
+    convert_gemini_schema(schema, schema, 0)
+}
+
+fn convert_gemini_schema(schema: &Value, root: &Value, depth: usize) -> Value {
+    let Some(object) = schema.as_object() else {
+        return schema.clone();
+    };
+
+    if let Some(reference) = object.get("$ref").and_then(|r| r.as_str()) {
+        let target = reference.strip_prefix('#').and_then(|pointer| root.pointer(pointer));
+        return match target {
+            Some(target) if depth < MAX_REF_DEPTH => convert_gemini_schema(target, root, depth + 1),
+            _ => json!({"type": "object"}),
+        };
+    }
+
+    let mut converted = Map::new();
+    for (keyword, value) in object {
+        match keyword.as_str() {
+            "type" => match value {
+                Value::Array(types) => {
+                    let nullable = types.iter().any(|t| t.as_str() == Some("null"));
+                    if let Some(first) = types.iter().find(|t| t.as_str() != Some("null")) {
+                        converted.insert("type".to_string(), first.clone());
+                    }
+                    if nullable {
+                        converted.insert("nullable".to_string(), Value::Bool(true));
+                    }
+                }
+                _ => {
+                    converted.insert("type".to_string(), value.clone());
+                }
+            },
+            "const" => {
+                converted.insert("enum".to_string(), json!([value]));
+            }
+            "properties" => {
+                let properties = value
+                    .as_object()
+                    .map(|properties| {
+                        properties
+                            .iter()
+                            .map(|(name, property)| (name.clone(), convert_gemini_schema(property, root, depth)))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                converted.insert("properties".to_string(), Value::Object(properties));
+            }
+            "items" => {
+                converted.insert("items".to_string(), convert_gemini_schema(value, root, depth));
+            }
+            "anyOf" | "oneOf" => {
+                let variants: Vec<Value> = value
+                    .as_array()
+                    .map(Vec::as_slice)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|variant| convert_gemini_schema(variant, root, depth))
+                    .collect();
+                converted.insert("anyOf".to_string(), Value::Array(variants));
+            }
+            keyword if GEMINI_SCHEMA_KEYWORDS.contains(&keyword) => {
+                converted.insert(keyword.to_string(), value.clone());
+            }
+            _ => {}
+        }
+    }
+    Value::Object(converted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Search {
+        query: String,
+        #[serde(default)]
+        limit: Option<u32>,
+    }
+
+    impl Tool for Search {
+        const NAME: &'static str = "search";
+        const DESCRIPTION: &'static str = "Search the web";
+
+        fn input_schema() -> Value {
+            json!({"type": "object", "properties": {"query": {"type": "string"}, "limit": {"type": "integer"}}, "required": ["query"]})
+        }
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(deny_unknown_fields)]
+    struct Stop {}
+
+    impl Tool for Stop {
+        const NAME: &'static str = "stop";
+        const DESCRIPTION: &'static str = "End the session";
+
+        fn input_schema() -> Value {
+            json!({"type": "object", "properties": {}})
+        }
+    }
+
+    tool_set! {
+        #[derive(Debug, PartialEq)]
+        enum TestTool {
+            Search(Search),
+            Stop(Stop),
+        }
+    }
+
+    fn tool_use<'a>(name: &'a str, input: &'a Value) -> ToolUse<'a> {
+        ToolUse { id: "call_1", name, input }
+    }
+
+    #[test]
+    fn test_decode_tool_set() {
+        assert_eq!(TestTool::names(), ["search", "stop"]);
+
+        let input = json!({"query": "rust"});
+        assert_eq!(
+            tool_use("search", &input).decode::<TestTool>().unwrap(),
+            TestTool::Search(Search { query: "rust".to_string(), limit: None })
+        );
+
+        let input = json!({});
+        assert_eq!(TestTool::decode(tool_use("stop", &input)).unwrap(), TestTool::Stop(Stop {}));
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let input = json!({"query": 42});
+        match TestTool::decode(tool_use("search", &input)) {
+            Err(ToolError::InvalidInput { name, .. }) => assert_eq!(name, "search"),
+            other => panic!("Expected InvalidInput, got {other:?}"),
+        }
+
+        let input = json!({});
+        match TestTool::decode(tool_use("browse", &input)) {
+            Err(ToolError::UnknownTool { name }) => assert_eq!(name, "browse"),
+            other => panic!("Expected UnknownTool, got {other:?}"),
+        }
+
+        // A single tool rejects calls to other tools
+        assert!(matches!(
+            Search::from_tool_use(tool_use("stop", &input)),
+            Err(ToolError::UnknownTool { .. })
+        ));
+    }
+
+    #[test]
+    fn test_render_tools() {
+        let tools = TestTool::definitions();
+        assert_eq!(tools[0].name, "search");
+
+        let anthropic = render_anthropic(&tools);
+        assert_eq!(anthropic[0]["input_schema"]["required"][0], "query");
+        assert_eq!(anthropic[1]["description"], "End the session");
+
+        let openai = render_openai(&tools);
+        assert_eq!(openai[0]["type"], "function");
+        assert_eq!(openai[0]["function"]["parameters"]["properties"]["limit"]["type"], "integer");
+
+        let gemini = render_gemini(&tools);
+        assert_eq!(gemini[0]["functionDeclarations"][1]["name"], "stop");
+
+        let prompt = render_prompt(&tools);
+        assert!(prompt.contains("## search\nSearch the web\nInput schema: {"));
+    }
+
+    #[cfg(feature = "schema")]
+    #[test]
+    fn test_input_schema_for() {
+        #[derive(Deserialize, schemars::JsonSchema)]
+        struct Place {
+            city: String,
+        }
+
+        /// Book a table
+        #[derive(Deserialize, schemars::JsonSchema)]
+        struct BookTable {
+            place: Place,
+            guests: Option<u32>,
+        }
+
+        impl Tool for BookTable {
+            const NAME: &'static str = "book_table";
+            const DESCRIPTION: &'static str = "Book a table at a restaurant";
+
+            fn input_schema() -> Value {
+                input_schema_for::<Self>()
+            }
+        }
+
+        let schema = BookTable::input_schema();
+        assert_eq!(schema["type"], "object");
+        assert_eq!(schema["required"], json!(["place"]));
+        assert_eq!(schema["properties"]["place"]["$ref"], "#/definitions/Place");
+
+        let gemini = render_gemini(&[BookTable::definition()]);
+        let parameters = &gemini[0]["functionDeclarations"][0]["parameters"];
+        assert_eq!(parameters["properties"]["place"]["properties"]["city"]["type"], "string");
+        assert_eq!(parameters["properties"]["guests"]["nullable"], true);
+
+        let input = json!({"place": {"city": "Paris"}});
+        let booking = BookTable::from_tool_use(tool_use("book_table", &input)).unwrap();
+        assert_eq!((booking.place.city.as_str(), booking.guests), ("Paris", None));
+    }
+
+    #[test]
+    fn test_gemini_schema() {
+        let schema = json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {
+                "unit": {"type": ["string", "null"], "default": "celsius"},
+                "mode": {"const": "fast"},
+                "place": {"$ref": "#/$defs/Place"},
+                "tags": {"type": "array", "items": {"oneOf": [{"type": "string"}, {"type": "integer"}]}}
+            },
+            "required": ["place"],
+            "$defs": {"Place": {"type": "object", "properties": {"city": {"type": "string"}}}}
+        });
+
+        assert_eq!(
+            gemini_schema(&schema),
+            json!({
+                "type": "object",
+                "properties": {
+                    "unit": {"type": "string", "nullable": true},
+                    "mode": {"enum": ["fast"]},
+                    "place": {"type": "object", "properties": {"city": {"type": "string"}}},
+                    "tags": {"type": "array", "items": {"anyOf": [{"type": "string"}, {"type": "integer"}]}}
+                },
+                "required": ["place"]
+            })
+        );
+
+        // Recursive references stop expanding instead of looping forever
+        let recursive = json!({"$defs": {"Node": {"type": "object", "properties": {"next": {"$ref": "#/$defs/Node"}}}}, "$ref": "#/$defs/Node"});
+        assert_eq!(gemini_schema(&recursive)["type"], "object");
+    }
+}
@@ -0,0 +1,101 @@
+#![cfg(all(feature = "cli", feature = "anthropic", feature = "openai", feature = "bedrock"))]
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+use serde_json::Value;
+
+fn fixture(path: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(path)
+}
+
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_adaptogen"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn frames(output: &Output) -> Vec<Value> {
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+fn snapshot_frame(path: &str) -> Value {
+    let snapshot: Value = serde_json::from_str(&std::fs::read_to_string(fixture(path)).unwrap()).unwrap();
+    snapshot["frame"].clone()
+}
+
+#[test]
+fn test_files_of_each_format() {
+    let json = fixture("anthropic/tool_use.json");
+    let sse = fixture("openai/tool_calls_stream.sse");
+    let output = run(&[json.to_str().unwrap(), sse.to_str().unwrap()], "");
+
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+    assert_eq!(
+        frames(&output),
+        [snapshot_frame("anthropic/tool_use.snap.json"), snapshot_frame("openai/tool_calls_stream.snap.json")]
+    );
+}
+
+#[test]
+fn test_stdin_jsonl_with_failures() {
+    let text = std::fs::read_to_string(fixture("anthropic/text.json")).unwrap();
+    let text: Value = serde_json::from_str(&text).unwrap();
+    let input = format!("{text}\n\n{{\"id\": \"x\", \"model\": \"unknown-model\"}}\nnot json\n{text}\n");
+
+    let output = run(&[], &input);
+    assert!(output.status.success());
+    assert_eq!(frames(&output).len(), 2);
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("2 parsed, 2 failed"), "{stderr}");
+    assert!(stderr.contains("<stdin>:3: Unsupported model: unknown-model"), "{stderr}");
+    assert!(stderr.contains("<stdin>:4: Invalid JSON"), "{stderr}");
+
+    let output = run(&["--strict", "-"], &input);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(frames(&output).len(), 1);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("1 parsed, 1 failed"));
+}
+
+#[test]
+fn test_provider_and_model_hints() {
+    let converse = std::fs::read_to_string(fixture("bedrock_converse/text.json")).unwrap();
+    let model = std::fs::read_to_string(fixture("bedrock_converse/text.model")).unwrap();
+
+    let output = run(&["--format", "json", "--model", model.trim()], &converse);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(frames(&output), [snapshot_frame("bedrock_converse/text.snap.json")]);
+
+    let output = run(&["-p", "bedrock-converse", "-m", model.trim()], &converse);
+    assert_eq!(frames(&output), [snapshot_frame("bedrock_converse/text.snap.json")]);
+
+    // Provider errors are reported with a fixed parser too
+    let error = std::fs::read_to_string(fixture("anthropic/overloaded_error.json")).unwrap();
+    let output = run(&["-p", "anthropic"], &error);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Provider error from anthropic"));
+}
+
+#[test]
+fn test_json_output_to_file() {
+    let path = std::env::temp_dir().join(format!("adaptogen-cli-{}.json", std::process::id()));
+    let input = fixture("anthropic/thinking.json");
+    let output = run(&[input.to_str().unwrap(), "--output-format", "json", "-o", path.to_str().unwrap()], "");
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    let written: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(written, Value::Array(vec![snapshot_frame("anthropic/thinking.snap.json")]));
+    std::fs::remove_file(&path).unwrap();
+}
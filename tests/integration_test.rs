@@ -2,8 +2,8 @@ use adaptogen::normalized::{ContentBlock, ContentFrame};
 use adaptogen::parser::{ModelResponseParser, ParseError};
 use adaptogen::registry::ParserRegistry;
 
-use std::sync::Arc;
 use serde_json::Value;
+use std::sync::Arc;
 
 // Custom parser for testing
 struct TestClaudeParser;
@@ -13,6 +13,7 @@ impl ModelResponseParser for TestClaudeParser {
         vec!["claude".to_string()]
     }
 
+    #[allow(clippy::collapsible_match, clippy::single_match)]
     fn parse(&self, raw_response: &str) -> Result<ContentFrame, ParseError> {
         let json: Value = serde_json::from_str(raw_response)?;
 
@@ -37,9 +38,7 @@ impl ModelResponseParser for TestClaudeParser {
                         match block_type {
                             "text" => {
                                 if let Some(text) = block.get("text").and_then(|t| t.as_str()) {
-                                    blocks.push(ContentBlock::Text {
-                                        text: text.to_string(),
-                                    });
+                                    blocks.push(ContentBlock::text(text));
                                 }
                             }
                             _ => {} // Ignore other types
@@ -49,7 +48,7 @@ impl ModelResponseParser for TestClaudeParser {
             }
         }
 
-        Ok(ContentFrame { id, model, blocks })
+        Ok(ContentFrame::new(id, model, blocks))
     }
 }
 
@@ -84,22 +83,20 @@ impl ModelResponseParser for TestQwenParser {
             if let Some(first_choice) = choices.first() {
                 if let Some(message) = first_choice.get("message") {
                     if let Some(content) = message.get("content").and_then(|c| c.as_str()) {
-                        blocks.push(ContentBlock::Text {
-                            text: content.to_string(),
-                        });
+                        blocks.push(ContentBlock::text(content));
                     }
                 }
             }
         }
 
-        Ok(ContentFrame { id, model, blocks })
+        Ok(ContentFrame::new(id, model, blocks))
     }
 }
 
 #[test]
 fn test_registry_with_multiple_parsers() {
     // Create registry with both parsers
-    let mut registry = ParserRegistry::new();
+    let registry = ParserRegistry::new();
     registry.register_parser(Arc::new(TestClaudeParser));
     registry.register_parser(Arc::new(TestQwenParser));
     
@@ -121,7 +118,7 @@ fn test_registry_with_multiple_parsers() {
     assert_eq!(frame.model, "claude");
     assert_eq!(frame.blocks.len(), 1);
     
-    if let ContentBlock::Text { text } = &frame.blocks[0] {
+    if let ContentBlock::Text { text, .. } = &frame.blocks[0] {
         assert_eq!(text, "Hello from Claude!");
     } else {
         panic!("Expected Text block");
@@ -149,7 +146,7 @@ fn test_registry_with_multiple_parsers() {
     assert_eq!(frame.model, "qwen");
     assert_eq!(frame.blocks.len(), 1);
     
-    if let ContentBlock::Text { text } = &frame.blocks[0] {
+    if let ContentBlock::Text { text, .. } = &frame.blocks[0] {
         assert_eq!(text, "Hello from Qwen!");
     } else {
         panic!("Expected Text block");
@@ -158,7 +155,7 @@ fn test_registry_with_multiple_parsers() {
 
 #[test]
 fn test_unsupported_model() {
-    let mut registry = ParserRegistry::new();
+    let registry = ParserRegistry::new();
     registry.register_parser(Arc::new(TestClaudeParser));
     registry.register_parser(Arc::new(TestQwenParser));
     
@@ -175,4 +172,101 @@ fn test_unsupported_model() {
         ParseError::UnsupportedModel(model) => assert_eq!(model, "unknown-model"),
         _ => panic!("Expected UnsupportedModel error"),
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(all(feature = "anthropic", feature = "openai", feature = "gemini"))]
+#[test]
+fn test_default_parse() {
+    let responses = [
+        (
+            r#"{"id": "msg_1", "model": "claude-3-5-sonnet-20241022", "content": [{"type": "text", "text": "Hi"}]}"#,
+            "claude-3-5-sonnet-20241022",
+        ),
+        (
+            r#"{"id": "chatcmpl-1", "model": "gpt-4o", "choices": [{"message": {"content": "Hi"}}]}"#,
+            "gpt-4o",
+        ),
+        (
+            r#"{"responseId": "r1", "modelVersion": "gemini-2.0-flash", "candidates": [{"content": {"parts": [{"text": "Hi"}]}}]}"#,
+            "gemini-2.0-flash",
+        ),
+    ];
+
+    for (response, model) in responses {
+        let frame = adaptogen::parse(response).unwrap();
+        assert_eq!(frame.model, model);
+        assert_eq!(frame.blocks.len(), 1);
+    }
+}
+
+#[test]
+fn test_custom_parser_overrides_defaults() {
+    let registry = ParserRegistry::with_defaults();
+    registry.register_parser_with_priority(Arc::new(TestClaudeParser), 1);
+
+    let response = r#"{"id": "claude-1", "model": "claude", "content": [{"type": "thinking", "thinking": "hmm"}]}"#;
+
+    // TestClaudeParser ignores thinking blocks, the built-in parser does not
+    let frame = registry.parse(response).unwrap();
+    assert!(frame.blocks.is_empty());
+}
+
+#[cfg(feature = "bedrock")]
+#[test]
+fn test_bedrock_routing() {
+    let converse = r#"{
+        "output": {"message": {"role": "assistant", "content": [{"text": "Hi"}]}},
+        "stopReason": "end_turn",
+        "usage": {"inputTokens": 3, "outputTokens": 1, "totalTokens": 4}
+    }"#;
+
+    // Without a model hint the response is routed by its shape
+    let frame = adaptogen::parse(converse).unwrap();
+    assert_eq!(frame.model, "");
+    assert_eq!(frame.blocks.len(), 1);
+
+    let registry = ParserRegistry::with_defaults();
+    let frame = registry
+        .parse_with_model(converse, "meta.llama3-1-70b-instruct-v1:0")
+        .unwrap();
+    assert_eq!(frame.model, "meta.llama3-1-70b-instruct-v1:0");
+
+    // InvokeModel bodies for the same model id go to the InvokeModel parser
+    let invoke = r#"{"generation": "Hi", "prompt_token_count": 3, "generation_token_count": 1, "stop_reason": "stop"}"#;
+    let frame = registry
+        .parse_with_model(invoke, "meta.llama3-1-70b-instruct-v1:0")
+        .unwrap();
+    assert_eq!(frame.stop_reason.as_deref(), Some("stop"));
+}
+
+#[test]
+#[cfg(all(feature = "openai", feature = "ollama", feature = "cohere"))]
+fn test_native_format_routing() {
+    // "qwen3" matches the OpenAI-compatible parser by name, but the body is
+    // Ollama's native format
+    let ollama = r#"{
+        "model": "qwen3:8b",
+        "message": {"role": "assistant", "content": "Hi", "thinking": "Say hi"},
+        "done": true,
+        "done_reason": "stop",
+        "prompt_eval_count": 5,
+        "eval_count": 2
+    }"#;
+
+    let frame = adaptogen::parse(ollama).unwrap();
+    assert_eq!(frame.model, "qwen3:8b");
+    assert_eq!(frame.blocks.len(), 2);
+    assert_eq!(frame.usage.unwrap().output_tokens, 2);
+
+    let cohere = r#"{
+        "id": "resp_1",
+        "finish_reason": "COMPLETE",
+        "message": {"role": "assistant", "content": [{"type": "text", "text": "Hi"}]}
+    }"#;
+
+    let frame = ParserRegistry::with_defaults()
+        .parse_with_model(cohere, "command-a-03-2025")
+        .unwrap();
+    assert_eq!(frame.id, "resp_1");
+    assert_eq!(frame.model, "command-a-03-2025");
+}
@@ -0,0 +1,1094 @@
+//! Round-trip tests: normalized frame -> provider response -> normalized frame
+//!
+//! For each built-in provider, `render` writes a generated `ContentFrame` as
+//! that provider's response body, and the provider's parser reads it back.
+//! The result must equal `expected`, which applies the losses of the provider
+//! format. Each provider module lists its losses; everything not listed there
+//! survives the round trip unchanged.
+
+use adaptogen::normalized::{
+    Citation, CitationSource, ContentBlock, ContentFrame, ContentResultBlock, TokenLogprob, Usage,
+};
+use proptest::prelude::*;
+use serde_json::{json, Value};
+
+/// Stop reasons without provider-specific meaning, such as refusals or content filtering
+const STOP_REASONS: &[&str] = &["end_turn", "stop", "tool_use", "tool_calls", "max_tokens", "length"];
+
+/// Names of server tools, some of which only exist for some providers
+const SERVER_TOOLS: &[&str] = &["web_search", "code_execution", "file_search"];
+
+fn word() -> impl Strategy<Value = String> {
+    "[a-z]{1,8}"
+}
+
+/// Non-empty text without leading or trailing whitespace or markup
+fn text() -> impl Strategy<Value = String> {
+    prop::collection::vec(word(), 1..4).prop_map(|words| words.join(" "))
+}
+
+fn id() -> impl Strategy<Value = String> {
+    "[a-z][a-z0-9_]{0,11}"
+}
+
+/// A JSON object as used for tool inputs
+fn object() -> impl Strategy<Value = Value> {
+    let value = prop_oneof![
+        text().prop_map(Value::from),
+        (0u32..1000).prop_map(Value::from),
+        any::<bool>().prop_map(Value::from),
+    ];
+    prop::collection::btree_map("k_[a-z]{1,5}", value, 0..3).prop_map(|map| Value::Object(map.into_iter().collect()))
+}
+
+fn citation() -> impl Strategy<Value = Citation> {
+    let source = (
+        proptest::option::of(id()),
+        proptest::option::of(text()),
+        proptest::option::of("https://[a-z]{1,8}\\.com"),
+    )
+        .prop_map(|(id, title, url)| CitationSource { id, title, url });
+
+    (
+        proptest::option::of(text()),
+        proptest::option::of(0usize..20),
+        proptest::option::of(0usize..40),
+        prop::collection::vec(source, 0..3),
+    )
+        .prop_map(|(cited_text, start, end, sources)| Citation {
+            cited_text,
+            start,
+            end,
+            sources,
+        })
+}
+
+/// Logprobs are multiples of 1/1024, which serde_json parses back exactly
+fn logprob() -> impl Strategy<Value = TokenLogprob> {
+    (word(), -10_240i32..0, 0usize..50).prop_map(|(token, logprob, offset)| TokenLogprob {
+        token,
+        logprob: f64::from(logprob) / 1024.0,
+        offset,
+        bytes: None,
+        top_logprobs: Vec::new(),
+    })
+}
+
+/// One or two blocks of any variant; server tool calls are sometimes followed by their result
+fn blocks() -> impl Strategy<Value = Vec<ContentBlock>> {
+    let text_block = (text(), prop::collection::vec(citation(), 0..3), prop::collection::vec(logprob(), 0..3))
+        .prop_map(|(text, citations, logprobs)| vec![ContentBlock::Text { text, citations, logprobs }]);
+    let tool_use = (id(), "[a-z_]{1,10}", object()).prop_map(|(id, name, input)| vec![ContentBlock::ToolUse { id, name, input }]);
+    let tool_result = (id(), prop::collection::vec(text(), 0..3), any::<bool>()).prop_map(|(tool_use_id, content, is_error)| {
+        vec![ContentBlock::ToolResult {
+            tool_use_id,
+            content: content.into_iter().map(|content| ContentResultBlock { content }).collect(),
+            is_error,
+        }]
+    });
+    let server_tool = (
+        id(),
+        prop::sample::select(SERVER_TOOLS),
+        object(),
+        proptest::option::of((prop::collection::vec(object(), 0..3), any::<bool>())),
+    )
+        .prop_map(|(id, name, input, result)| {
+            let mut blocks = vec![ContentBlock::ServerToolUse {
+                id: id.clone(),
+                name: name.to_string(),
+                input,
+            }];
+            if let Some((content, is_error)) = result {
+                blocks.push(ContentBlock::ServerToolResult {
+                    tool_use_id: id,
+                    content: Value::Array(content),
+                    is_error,
+                });
+            }
+            blocks
+        });
+    let server_tool_result = (id(), prop::collection::vec(object(), 0..3), any::<bool>()).prop_map(|(tool_use_id, content, is_error)| {
+        vec![ContentBlock::ServerToolResult {
+            tool_use_id,
+            content: Value::Array(content),
+            is_error,
+        }]
+    });
+    let refusal = text().prop_map(|refusal| vec![ContentBlock::Refusal { refusal }]);
+    let thinking = (proptest::option::of(text()), proptest::option::of("[A-Za-z0-9]{4,12}"))
+        .prop_map(|(thinking, signature)| vec![ContentBlock::Thinking { thinking, signature }]);
+    let unknown = text().prop_map(|data| vec![ContentBlock::Unknown { raw: json!({"type": "x_custom", "data": data}) }]);
+
+    prop::collection::vec(
+        prop_oneof![text_block, tool_use, tool_result, server_tool, server_tool_result, refusal, thinking, unknown],
+        0..6,
+    )
+    .prop_map(|groups| groups.into_iter().flatten().collect())
+}
+
+fn frame() -> impl Strategy<Value = ContentFrame> {
+    (
+        "msg_[a-z0-9]{1,8}",
+        "[a-z][a-z0-9-]{0,11}",
+        blocks(),
+        proptest::option::of(prop::sample::select(STOP_REASONS)),
+        proptest::option::of((0u64..10_000, 0u64..10_000)),
+    )
+        .prop_map(|(id, model, blocks, stop_reason, usage)| ContentFrame {
+            stop_reason: stop_reason.map(str::to_string),
+            usage: usage.map(|(input_tokens, output_tokens)| Usage {
+                input_tokens,
+                output_tokens,
+            }),
+            ..ContentFrame::new(id, model, blocks)
+        })
+}
+
+/// The frame with only `blocks` kept, as a parser would return it
+#[cfg(any(
+    feature = "anthropic",
+    feature = "cohere",
+    feature = "gemini",
+    feature = "mistral",
+    feature = "ollama",
+    feature = "openai"
+))]
+fn with_blocks(frame: &ContentFrame, blocks: Vec<ContentBlock>) -> ContentFrame {
+    ContentFrame {
+        blocks,
+        ..frame.clone()
+    }
+}
+
+#[cfg(any(feature = "mistral", feature = "ollama", feature = "openai"))]
+fn thinking_texts(frame: &ContentFrame) -> Vec<&str> {
+    frame
+        .blocks
+        .iter()
+        .filter_map(|block| match block {
+            ContentBlock::Thinking { thinking, .. } => thinking.as_deref(),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(any(feature = "cohere", feature = "mistral", feature = "ollama", feature = "openai"))]
+fn tool_uses(frame: &ContentFrame) -> Vec<ContentBlock> {
+    frame
+        .blocks
+        .iter()
+        .filter(|block| matches!(block, ContentBlock::ToolUse { .. }))
+        .cloned()
+        .collect()
+}
+
+#[cfg(any(feature = "anthropic", feature = "mistral", feature = "openai"))]
+fn usage_json(frame: &ContentFrame, input: &str, output: &str) -> Value {
+    match frame.usage {
+        Some(usage) => json!({ input: usage.input_tokens, output: usage.output_tokens }),
+        None => Value::Null,
+    }
+}
+
+/// `tokens` with each offset set to the length of the tokens before it, as
+/// parsers do for formats that carry no offsets
+#[cfg(any(feature = "gemini", feature = "openai"))]
+fn reoffset<'a>(tokens: impl IntoIterator<Item = &'a TokenLogprob>) -> Vec<TokenLogprob> {
+    let mut offset = 0;
+    tokens
+        .into_iter()
+        .map(|token| {
+            let token = TokenLogprob { offset, ..token.clone() };
+            offset += token.token.len();
+            token
+        })
+        .collect()
+}
+
+/// A token as an OpenAI logprobs entry
+#[cfg(feature = "openai")]
+fn logprob_json(token: &TokenLogprob) -> Value {
+    json!({"token": token.token, "logprob": token.logprob, "top_logprobs": []})
+}
+
+/// The logprobs of all text blocks as one track over the joined text
+#[cfg(any(feature = "gemini", feature = "openai"))]
+fn logprob_track(frame: &ContentFrame) -> Vec<TokenLogprob> {
+    reoffset(frame.blocks.iter().flat_map(|block| match block {
+        ContentBlock::Text { logprobs, .. } => logprobs.as_slice(),
+        _ => &[],
+    }))
+}
+
+/// The tokens of `track` overlapping `range` of the joined text, with offsets relative to its start
+#[cfg(any(feature = "gemini", feature = "openai"))]
+fn track_in(track: &[TokenLogprob], range: std::ops::Range<usize>) -> Vec<TokenLogprob> {
+    track
+        .iter()
+        .filter(|token| {
+            let end = token.offset + token.token.len();
+            token.offset < range.end && (end > range.start || token.offset >= range.start)
+        })
+        .map(|token| TokenLogprob {
+            offset: token.offset.saturating_sub(range.start),
+            ..token.clone()
+        })
+        .collect()
+}
+
+proptest! {
+    /// The normalized JSON format itself is lossless
+    #[test]
+    fn test_serde_round_trip(frame in frame()) {
+        let json = serde_json::to_string(&frame).unwrap();
+        prop_assert_eq!(ContentFrame::from_json(&json).unwrap(), frame);
+    }
+}
+
+/// Anthropic Messages API
+///
+/// Losses:
+/// - refusal blocks are dropped; Anthropic reports refusals through the stop reason
+/// - unknown blocks are dropped
+/// - text logprobs are dropped
+/// - citation offsets are dropped, and only the first source of each citation is kept
+#[cfg(feature = "anthropic")]
+mod anthropic {
+    use super::*;
+    use adaptogen::parser::ModelResponseParser;
+    use adaptogen::providers::AnthropicParser;
+
+    pub fn render(frame: &ContentFrame) -> Value {
+        let content: Vec<Value> = frame
+            .blocks
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Text { text, citations, .. } => {
+                    let citations: Vec<Value> = citations
+                        .iter()
+                        .map(|citation| {
+                            let source = citation.sources.first().cloned().unwrap_or_default();
+                            json!({
+                                "type": "char_location",
+                                "cited_text": citation.cited_text,
+                                "source": source.id,
+                                "title": source.title,
+                                "url": source.url,
+                            })
+                        })
+                        .collect();
+                    Some(json!({"type": "text", "text": text, "citations": citations}))
+                }
+                ContentBlock::ToolUse { id, name, input } => {
+                    Some(json!({"type": "tool_use", "id": id, "name": name, "input": input}))
+                }
+                ContentBlock::ToolResult { tool_use_id, content, is_error } => {
+                    let content: Vec<Value> = content.iter().map(|c| json!({"type": "text", "text": c.content})).collect();
+                    Some(json!({"type": "tool_result", "tool_use_id": tool_use_id, "content": content, "is_error": is_error}))
+                }
+                ContentBlock::ServerToolUse { id, name, input } => {
+                    Some(json!({"type": "server_tool_use", "id": id, "name": name, "input": input}))
+                }
+                ContentBlock::ServerToolResult { tool_use_id, content, is_error } => Some(
+                    json!({"type": "web_search_tool_result", "tool_use_id": tool_use_id, "content": content, "is_error": is_error}),
+                ),
+                ContentBlock::Thinking { thinking: Some(thinking), signature } => {
+                    Some(json!({"type": "thinking", "thinking": thinking, "signature": signature}))
+                }
+                ContentBlock::Thinking { thinking: None, signature } => {
+                    Some(json!({"type": "redacted_thinking", "data": signature}))
+                }
+                ContentBlock::Refusal { .. } => None,
+                ContentBlock::Unknown { raw } => Some(raw.clone()),
+            })
+            .collect();
+
+        json!({
+            "id": frame.id,
+            "type": "message",
+            "role": "assistant",
+            "model": frame.model,
+            "content": content,
+            "stop_reason": frame.stop_reason,
+            "usage": usage_json(frame, "input_tokens", "output_tokens"),
+        })
+    }
+
+    pub fn expected(frame: &ContentFrame) -> ContentFrame {
+        let blocks = frame
+            .blocks
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Text { text, citations, .. } => Some(ContentBlock::Text {
+                    text: text.clone(),
+                    citations: citations
+                        .iter()
+                        .map(|citation| Citation {
+                            cited_text: citation.cited_text.clone(),
+                            start: None,
+                            end: None,
+                            sources: vec![citation.sources.first().cloned().unwrap_or_default()],
+                        })
+                        .collect(),
+                    logprobs: Vec::new(),
+                }),
+                ContentBlock::Refusal { .. } | ContentBlock::Unknown { .. } => None,
+                block => Some(block.clone()),
+            })
+            .collect();
+        with_blocks(frame, blocks)
+    }
+
+    proptest! {
+        #[test]
+        fn test_anthropic_round_trip(frame in frame()) {
+            let parsed = AnthropicParser.parse(&render(&frame).to_string()).unwrap();
+            prop_assert_eq!(parsed, expected(&frame));
+        }
+    }
+}
+
+/// AWS Bedrock InvokeModel with an Anthropic body, with the same losses as [`anthropic`]
+#[cfg(feature = "bedrock")]
+mod bedrock_invoke {
+    use super::*;
+    use adaptogen::parser::ModelResponseParser;
+    use adaptogen::providers::BedrockInvokeParser;
+
+    proptest! {
+        #[test]
+        fn test_bedrock_invoke_round_trip(frame in frame()) {
+            let raw = anthropic::render(&frame).to_string();
+            let parsed = BedrockInvokeParser.parse_with_model(&raw, &frame.model).unwrap();
+            prop_assert_eq!(parsed, anthropic::expected(&frame));
+        }
+    }
+}
+
+/// AWS Bedrock Converse API, read with the model id the request was sent to
+///
+/// Losses:
+/// - server tool, refusal and unknown blocks are dropped
+/// - text citations and logprobs are dropped
+#[cfg(feature = "bedrock")]
+mod bedrock_converse {
+    use super::*;
+    use adaptogen::parser::ModelResponseParser;
+    use adaptogen::providers::BedrockConverseParser;
+
+    fn render(frame: &ContentFrame) -> Value {
+        let content: Vec<Value> = frame
+            .blocks
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Text { text, .. } => Some(json!({"text": text})),
+                ContentBlock::ToolUse { id, name, input } => {
+                    Some(json!({"toolUse": {"toolUseId": id, "name": name, "input": input}}))
+                }
+                ContentBlock::ToolResult { tool_use_id, content, is_error } => {
+                    let content: Vec<Value> = content.iter().map(|c| json!({"text": c.content})).collect();
+                    let status = if *is_error { "error" } else { "success" };
+                    Some(json!({"toolResult": {"toolUseId": tool_use_id, "content": content, "status": status}}))
+                }
+                ContentBlock::Thinking { thinking: Some(thinking), signature } => {
+                    Some(json!({"reasoningContent": {"reasoningText": {"text": thinking, "signature": signature}}}))
+                }
+                ContentBlock::Thinking { thinking: None, signature } => {
+                    Some(json!({"reasoningContent": {"redactedContent": signature}}))
+                }
+                _ => None,
+            })
+            .collect();
+
+        json!({
+            "output": {"message": {"role": "assistant", "content": content}},
+            "stopReason": frame.stop_reason,
+            "usage": usage_json(frame, "inputTokens", "outputTokens"),
+            "ResponseMetadata": {"RequestId": frame.id},
+        })
+    }
+
+    fn expected(frame: &ContentFrame) -> ContentFrame {
+        let blocks = frame
+            .blocks
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Text { text, .. } => Some(ContentBlock::text(text)),
+                ContentBlock::ToolUse { .. } | ContentBlock::ToolResult { .. } | ContentBlock::Thinking { .. } => {
+                    Some(block.clone())
+                }
+                _ => None,
+            })
+            .collect();
+        with_blocks(frame, blocks)
+    }
+
+    proptest! {
+        #[test]
+        fn test_bedrock_converse_round_trip(frame in frame()) {
+            let parsed = BedrockConverseParser.parse_with_model(&render(&frame).to_string(), &frame.model).unwrap();
+            prop_assert_eq!(parsed, expected(&frame));
+        }
+    }
+}
+
+/// Cohere v2 Chat API, read with the model id the request was sent to
+///
+/// Losses:
+/// - tool calls move after the text and thinking blocks
+/// - tool result, server tool, refusal, unknown and redacted thinking blocks are dropped
+/// - thinking signatures and text logprobs are dropped
+/// - citations must start inside their text block; others are dropped
+#[cfg(feature = "cohere")]
+mod cohere {
+    use super::*;
+    use adaptogen::parser::ModelResponseParser;
+    use adaptogen::providers::CohereParser;
+
+    fn in_text(citation: &Citation, text: &str) -> bool {
+        citation.start.is_some_and(|start| start < text.chars().count())
+    }
+
+    fn render(frame: &ContentFrame) -> Value {
+        let mut content = Vec::new();
+        let mut citations = Vec::new();
+        let mut offset = 0;
+
+        for block in &frame.blocks {
+            match block {
+                ContentBlock::Text { text, citations: block_citations, .. } => {
+                    // Cohere citation offsets refer to the concatenated text
+                    for citation in block_citations.iter().filter(|c| in_text(c, text)) {
+                        let sources: Vec<Value> = citation
+                            .sources
+                            .iter()
+                            .map(|s| json!({"type": "document", "id": s.id, "document": {"title": s.title, "url": s.url}}))
+                            .collect();
+                        citations.push(json!({
+                            "start": citation.start.map(|s| s + offset),
+                            "end": citation.end.map(|e| e + offset),
+                            "text": citation.cited_text,
+                            "sources": sources,
+                        }));
+                    }
+                    offset += text.chars().count();
+                    content.push(json!({"type": "text", "text": text}));
+                }
+                ContentBlock::Thinking { thinking: Some(thinking), .. } => {
+                    content.push(json!({"type": "thinking", "thinking": thinking}));
+                }
+                _ => {}
+            }
+        }
+
+        let tool_calls: Vec<Value> = tool_uses(frame)
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::ToolUse { id, name, input } => Some(json!({
+                    "id": id,
+                    "type": "function",
+                    "function": {"name": name, "arguments": input.to_string()},
+                })),
+                _ => None,
+            })
+            .collect();
+
+        let mut response = json!({
+            "id": frame.id,
+            "finish_reason": frame.stop_reason,
+            "message": {"role": "assistant", "content": content, "tool_calls": tool_calls, "citations": citations},
+        });
+        if let Some(usage) = frame.usage {
+            response["usage"] = json!({"tokens": {"input_tokens": usage.input_tokens, "output_tokens": usage.output_tokens}});
+        }
+        response
+    }
+
+    fn expected(frame: &ContentFrame) -> ContentFrame {
+        let mut blocks: Vec<ContentBlock> = frame
+            .blocks
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Text { text, citations, .. } => Some(ContentBlock::Text {
+                    text: text.clone(),
+                    citations: citations.iter().filter(|c| in_text(c, text)).cloned().collect(),
+                    logprobs: Vec::new(),
+                }),
+                ContentBlock::Thinking { thinking: Some(thinking), .. } => Some(ContentBlock::Thinking {
+                    thinking: Some(thinking.clone()),
+                    signature: None,
+                }),
+                _ => None,
+            })
+            .collect();
+        blocks.extend(tool_uses(frame));
+        with_blocks(frame, blocks)
+    }
+
+    proptest! {
+        #[test]
+        fn test_cohere_round_trip(frame in frame()) {
+            let parsed = CohereParser.parse_with_model(&render(&frame).to_string(), &frame.model).unwrap();
+            prop_assert_eq!(parsed, expected(&frame));
+        }
+    }
+}
+
+/// Gemini `generateContent` API
+///
+/// Losses:
+/// - tool result, refusal, unknown and redacted thinking blocks are dropped
+/// - text citations are dropped
+/// - text logprobs form one token track over the joined text, so their offsets
+///   are recomputed from token lengths and each text block gets the tokens
+///   overlapping it
+/// - the only server tool is code execution: its calls get generated ids, and
+///   its results must directly follow their call and are wrapped with their outcome
+#[cfg(feature = "gemini")]
+mod gemini {
+    use super::*;
+    use adaptogen::parser::ModelResponseParser;
+    use adaptogen::providers::GeminiParser;
+
+    fn outcome(is_error: bool) -> &'static str {
+        if is_error {
+            "OUTCOME_FAILED"
+        } else {
+            "OUTCOME_OK"
+        }
+    }
+
+    /// Whether the block at `index` is a result directly following its code execution call
+    fn is_code_result(blocks: &[ContentBlock], index: usize) -> bool {
+        let (Some(ContentBlock::ServerToolResult { tool_use_id, .. }), Some(ContentBlock::ServerToolUse { id, name, .. })) =
+            (blocks.get(index), index.checked_sub(1).and_then(|i| blocks.get(i)))
+        else {
+            return false;
+        };
+        name == "code_execution" && id == tool_use_id
+    }
+
+    fn render(frame: &ContentFrame) -> Value {
+        let parts: Vec<Value> = frame
+            .blocks
+            .iter()
+            .enumerate()
+            .filter_map(|(index, block)| match block {
+                ContentBlock::Text { text, .. } => Some(json!({"text": text})),
+                ContentBlock::Thinking { thinking: Some(thinking), signature } => {
+                    Some(json!({"text": thinking, "thought": true, "thoughtSignature": signature}))
+                }
+                ContentBlock::ToolUse { id, name, input } => {
+                    Some(json!({"functionCall": {"id": id, "name": name, "args": input}}))
+                }
+                ContentBlock::ServerToolUse { name, input, .. } if name == "code_execution" => {
+                    Some(json!({"executableCode": input}))
+                }
+                ContentBlock::ServerToolResult { content, is_error, .. } if is_code_result(&frame.blocks, index) => {
+                    Some(json!({"codeExecutionResult": {"outcome": outcome(*is_error), "output": content}}))
+                }
+                _ => None,
+            })
+            .collect();
+
+        let mut response = json!({
+            "responseId": frame.id,
+            "modelVersion": frame.model,
+            "candidates": [{"content": {"role": "model", "parts": parts}, "finishReason": frame.stop_reason}],
+        });
+        let track = logprob_track(frame);
+        if !track.is_empty() {
+            let chosen: Vec<Value> = track
+                .iter()
+                .map(|token| json!({"token": token.token, "logProbability": token.logprob}))
+                .collect();
+            response["candidates"][0]["logprobsResult"] = json!({"chosenCandidates": chosen});
+        }
+        if let Some(usage) = frame.usage {
+            response["usageMetadata"] = json!({"promptTokenCount": usage.input_tokens, "candidatesTokenCount": usage.output_tokens});
+        }
+        response
+    }
+
+    fn expected(frame: &ContentFrame) -> ContentFrame {
+        let track = logprob_track(frame);
+        let mut text_offset = 0;
+        let mut code_executions = 0;
+        let blocks = frame
+            .blocks
+            .iter()
+            .enumerate()
+            .filter_map(|(index, block)| match block {
+                ContentBlock::Text { text, .. } => {
+                    let range = text_offset..text_offset + text.len();
+                    text_offset = range.end;
+                    Some(ContentBlock::Text {
+                        text: text.clone(),
+                        citations: Vec::new(),
+                        logprobs: track_in(&track, range),
+                    })
+                }
+                ContentBlock::Thinking { thinking: Some(_), .. } | ContentBlock::ToolUse { .. } => Some(block.clone()),
+                ContentBlock::ServerToolUse { name, input, .. } if name == "code_execution" => {
+                    code_executions += 1;
+                    Some(ContentBlock::ServerToolUse {
+                        id: format!("code_execution_{}", code_executions - 1),
+                        name: name.clone(),
+                        input: input.clone(),
+                    })
+                }
+                ContentBlock::ServerToolResult { content, is_error, .. } if is_code_result(&frame.blocks, index) => {
+                    Some(ContentBlock::ServerToolResult {
+                        tool_use_id: format!("code_execution_{}", code_executions - 1),
+                        content: json!({"outcome": outcome(*is_error), "output": content}),
+                        is_error: *is_error,
+                    })
+                }
+                _ => None,
+            })
+            .collect();
+        with_blocks(frame, blocks)
+    }
+
+    proptest! {
+        #[test]
+        fn test_gemini_round_trip(frame in frame()) {
+            let parsed = GeminiParser.parse(&render(&frame).to_string()).unwrap();
+            prop_assert_eq!(parsed, expected(&frame));
+        }
+    }
+}
+
+/// Mistral Chat Completions API
+///
+/// Losses:
+/// - thinking blocks come first, followed by a single text block joining all
+///   text, followed by the tool calls
+/// - tool result, server tool, refusal, unknown and redacted thinking blocks are dropped
+/// - thinking signatures, text citations and logprobs are dropped
+#[cfg(feature = "mistral")]
+mod mistral {
+    use super::*;
+    use adaptogen::parser::ModelResponseParser;
+    use adaptogen::providers::MistralParser;
+
+    fn render(frame: &ContentFrame) -> Value {
+        let content: Vec<Value> = frame
+            .blocks
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Text { text, .. } => Some(json!({"type": "text", "text": text})),
+                ContentBlock::Thinking { thinking: Some(thinking), .. } => {
+                    Some(json!({"type": "thinking", "thinking": [{"type": "text", "text": thinking}]}))
+                }
+                _ => None,
+            })
+            .collect();
+        let tool_calls: Vec<Value> = frame
+            .blocks
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::ToolUse { id, name, input } => Some(json!({
+                    "id": id,
+                    "type": "function",
+                    "function": {"name": name, "arguments": input.to_string()},
+                })),
+                _ => None,
+            })
+            .collect();
+
+        json!({
+            "id": frame.id,
+            "object": "chat.completion",
+            "model": frame.model,
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": content, "tool_calls": tool_calls},
+                "finish_reason": frame.stop_reason,
+            }],
+            "usage": usage_json(frame, "prompt_tokens", "completion_tokens"),
+        })
+    }
+
+    fn expected(frame: &ContentFrame) -> ContentFrame {
+        let mut blocks: Vec<ContentBlock> = thinking_texts(frame)
+            .into_iter()
+            .map(|thinking| ContentBlock::Thinking {
+                thinking: Some(thinking.to_string()),
+                signature: None,
+            })
+            .collect();
+        if !frame.text().is_empty() {
+            blocks.push(ContentBlock::text(frame.text()));
+        }
+        blocks.extend(tool_uses(frame));
+        with_blocks(frame, blocks)
+    }
+
+    proptest! {
+        #[test]
+        fn test_mistral_round_trip(frame in frame()) {
+            let parsed = MistralParser.parse(&render(&frame).to_string()).unwrap();
+            prop_assert_eq!(parsed, expected(&frame));
+        }
+    }
+}
+
+/// Ollama `/api/chat` API
+///
+/// Losses:
+/// - the frame id, which Ollama responses do not have
+/// - one thinking block joining all thinking, followed by one text block
+///   joining all text, followed by the tool calls
+/// - tool result, server tool, refusal, unknown and redacted thinking blocks are dropped
+/// - thinking signatures, text citations and logprobs are dropped
+#[cfg(feature = "ollama")]
+mod ollama {
+    use super::*;
+    use adaptogen::parser::ModelResponseParser;
+    use adaptogen::providers::OllamaParser;
+
+    fn render(frame: &ContentFrame) -> Value {
+        let tool_calls: Vec<Value> = frame
+            .blocks
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::ToolUse { id, name, input } => {
+                    Some(json!({"id": id, "function": {"name": name, "arguments": input}}))
+                }
+                _ => None,
+            })
+            .collect();
+
+        let mut response = json!({
+            "model": frame.model,
+            "created_at": "2025-06-01T12:00:00Z",
+            "message": {
+                "role": "assistant",
+                "content": frame.text(),
+                "thinking": thinking_texts(frame).join("\n\n"),
+                "tool_calls": tool_calls,
+            },
+            "done": true,
+            "done_reason": frame.stop_reason,
+        });
+        if let Some(usage) = frame.usage {
+            response["prompt_eval_count"] = json!(usage.input_tokens);
+            response["eval_count"] = json!(usage.output_tokens);
+        }
+        response
+    }
+
+    fn expected(frame: &ContentFrame) -> ContentFrame {
+        let mut blocks = Vec::new();
+        let thinking = thinking_texts(frame);
+        if !thinking.is_empty() {
+            blocks.push(ContentBlock::Thinking {
+                thinking: Some(thinking.join("\n\n")),
+                signature: None,
+            });
+        }
+        if !frame.text().is_empty() {
+            blocks.push(ContentBlock::text(frame.text()));
+        }
+        blocks.extend(tool_uses(frame));
+        ContentFrame {
+            id: String::new(),
+            ..with_blocks(frame, blocks)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_ollama_round_trip(frame in frame()) {
+            let parsed = OllamaParser.parse(&render(&frame).to_string()).unwrap();
+            prop_assert_eq!(parsed, expected(&frame));
+        }
+    }
+}
+
+/// OpenAI Chat Completions API
+///
+/// Losses:
+/// - one thinking block (as `reasoning_content`) joining all thinking, followed
+///   by one refusal block joining all refusals, one text block joining all
+///   text, and the tool calls
+/// - tool result, server tool, unknown and redacted thinking blocks are dropped
+/// - thinking signatures and text citations are dropped
+/// - text logprobs form one token track over the joined text, so their offsets
+///   are recomputed from token lengths and tokens starting past its end are dropped
+/// - a refusal adds a safety report
+#[cfg(feature = "openai")]
+mod openai {
+    use super::*;
+    use adaptogen::normalized::SafetyReport;
+    use adaptogen::parser::ModelResponseParser;
+    use adaptogen::providers::OpenAiParser;
+    use serde_json::Map;
+
+    fn refusals(frame: &ContentFrame) -> Vec<&str> {
+        frame
+            .blocks
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Refusal { refusal } => Some(refusal.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn render(frame: &ContentFrame) -> Value {
+        let mut message = Map::new();
+        message.insert("role".to_string(), json!("assistant"));
+        let text = frame.text();
+        message.insert("content".to_string(), if text.is_empty() { Value::Null } else { json!(text) });
+        let thinking = thinking_texts(frame);
+        if !thinking.is_empty() {
+            message.insert("reasoning_content".to_string(), json!(thinking.join("\n\n")));
+        }
+        let refusals = refusals(frame);
+        if !refusals.is_empty() {
+            message.insert("refusal".to_string(), json!(refusals.join("\n")));
+        }
+        let tool_calls: Vec<Value> = frame
+            .blocks
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::ToolUse { id, name, input } => Some(json!({
+                    "id": id,
+                    "type": "function",
+                    "function": {"name": name, "arguments": input.to_string()},
+                })),
+                _ => None,
+            })
+            .collect();
+        if !tool_calls.is_empty() {
+            message.insert("tool_calls".to_string(), Value::Array(tool_calls));
+        }
+
+        let track = logprob_track(frame);
+        let logprobs = if track.is_empty() {
+            Value::Null
+        } else {
+            json!({"content": track.iter().map(logprob_json).collect::<Vec<_>>()})
+        };
+
+        json!({
+            "id": frame.id,
+            "object": "chat.completion",
+            "model": frame.model,
+            "choices": [{"index": 0, "message": message, "logprobs": logprobs, "finish_reason": frame.stop_reason}],
+            "usage": usage_json(frame, "prompt_tokens", "completion_tokens"),
+        })
+    }
+
+    fn expected(frame: &ContentFrame) -> ContentFrame {
+        let mut blocks = Vec::new();
+        let thinking = thinking_texts(frame);
+        if !thinking.is_empty() {
+            blocks.push(ContentBlock::Thinking {
+                thinking: Some(thinking.join("\n\n")),
+                signature: None,
+            });
+        }
+        let refusals = refusals(frame);
+        if !refusals.is_empty() {
+            blocks.push(ContentBlock::Refusal {
+                refusal: refusals.join("\n"),
+            });
+        }
+        let text = frame.text();
+        if !text.is_empty() {
+            blocks.push(ContentBlock::Text {
+                citations: Vec::new(),
+                logprobs: track_in(&logprob_track(frame), 0..text.len()),
+                text,
+            });
+        }
+        blocks.extend(tool_uses(frame));
+
+        ContentFrame {
+            safety: (!refusals.is_empty()).then(|| SafetyReport {
+                blocked: true,
+                reason: Some("refusal".to_string()),
+                ratings: Vec::new(),
+            }),
+            ..with_blocks(frame, blocks)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_openai_round_trip(frame in frame()) {
+            let parsed = OpenAiParser.parse(&render(&frame).to_string()).unwrap();
+            prop_assert_eq!(parsed, expected(&frame));
+        }
+    }
+}
+
+/// OpenAI Responses API
+///
+/// Losses:
+/// - tool result and unknown blocks are dropped
+/// - server tools other than web search and file search are dropped, and their
+///   results must directly follow their call
+/// - citation quotes and source ids are dropped, and only the first source of
+///   each citation is kept
+/// - text logprob offsets are recomputed from token lengths
+/// - a refusal adds a safety report
+#[cfg(feature = "openai")]
+mod openai_responses {
+    use super::*;
+    use adaptogen::normalized::SafetyReport;
+    use adaptogen::parser::ModelResponseParser;
+    use adaptogen::providers::OpenAiResponsesParser;
+
+    /// Server tools with a Responses API counterpart
+    const TOOLS: &[&str] = &["web_search", "file_search"];
+
+    /// The result directly following a supported server tool call, if any
+    fn server_result(blocks: &[ContentBlock], index: usize) -> Option<(&Value, bool)> {
+        let ContentBlock::ServerToolUse { id, .. } = &blocks[index] else {
+            return None;
+        };
+        match blocks.get(index + 1) {
+            Some(ContentBlock::ServerToolResult { tool_use_id, content, is_error }) if tool_use_id == id => {
+                Some((content, *is_error))
+            }
+            _ => None,
+        }
+    }
+
+    fn render(frame: &ContentFrame) -> Value {
+        let output: Vec<Value> = frame
+            .blocks
+            .iter()
+            .enumerate()
+            .filter_map(|(index, block)| match block {
+                ContentBlock::Text { text, citations, logprobs } => {
+                    let annotations: Vec<Value> = citations
+                        .iter()
+                        .map(|citation| {
+                            let source = citation.sources.first().cloned().unwrap_or_default();
+                            json!({
+                                "type": "url_citation",
+                                "start_index": citation.start,
+                                "end_index": citation.end,
+                                "title": source.title,
+                                "url": source.url,
+                            })
+                        })
+                        .collect();
+                    Some(json!({
+                        "type": "message",
+                        "role": "assistant",
+                        "content": [{
+                            "type": "output_text",
+                            "text": text,
+                            "annotations": annotations,
+                            "logprobs": logprobs.iter().map(logprob_json).collect::<Vec<_>>(),
+                        }],
+                    }))
+                }
+                ContentBlock::Refusal { refusal } => Some(json!({
+                    "type": "message",
+                    "role": "assistant",
+                    "content": [{"type": "refusal", "refusal": refusal}],
+                })),
+                ContentBlock::Thinking { thinking, signature } => {
+                    let summary: Vec<Value> = thinking.iter().map(|t| json!({"type": "summary_text", "text": t})).collect();
+                    Some(json!({"type": "reasoning", "summary": summary, "encrypted_content": signature}))
+                }
+                ContentBlock::ToolUse { id, name, input } => Some(json!({
+                    "type": "function_call",
+                    "id": format!("fc_{index}"),
+                    "call_id": id,
+                    "name": name,
+                    "arguments": input.to_string(),
+                })),
+                ContentBlock::ServerToolUse { id, name, input } if TOOLS.contains(&name.as_str()) => {
+                    let mut item = input.as_object().cloned().unwrap_or_default();
+                    item.insert("type".to_string(), json!(format!("{name}_call")));
+                    item.insert("id".to_string(), json!(id));
+                    item.insert("status".to_string(), json!("completed"));
+                    if let Some((content, is_error)) = server_result(&frame.blocks, index) {
+                        item.insert("results".to_string(), content.clone());
+                        if is_error {
+                            item.insert("status".to_string(), json!("failed"));
+                        }
+                    }
+                    Some(Value::Object(item))
+                }
+                _ => None,
+            })
+            .collect();
+
+        json!({
+            "id": frame.id,
+            "object": "response",
+            "model": frame.model,
+            "status": frame.stop_reason,
+            "output": output,
+            "usage": usage_json(frame, "input_tokens", "output_tokens"),
+        })
+    }
+
+    fn expected(frame: &ContentFrame) -> ContentFrame {
+        let mut blocks = Vec::new();
+        for (index, block) in frame.blocks.iter().enumerate() {
+            match block {
+                ContentBlock::Text { text, citations, logprobs } => blocks.push(ContentBlock::Text {
+                    text: text.clone(),
+                    citations: citations
+                        .iter()
+                        .map(|citation| {
+                            let source = citation.sources.first().cloned().unwrap_or_default();
+                            Citation {
+                                cited_text: None,
+                                start: citation.start,
+                                end: citation.end,
+                                sources: vec![CitationSource {
+                                    id: None,
+                                    ..source
+                                }],
+                            }
+                        })
+                        .collect(),
+                    logprobs: reoffset(logprobs),
+                }),
+                ContentBlock::Refusal { .. } | ContentBlock::Thinking { .. } | ContentBlock::ToolUse { .. } => {
+                    blocks.push(block.clone())
+                }
+                ContentBlock::ServerToolUse { id, name, .. } if TOOLS.contains(&name.as_str()) => {
+                    blocks.push(block.clone());
+                    if let Some((content, is_error)) = server_result(&frame.blocks, index) {
+                        blocks.push(ContentBlock::ServerToolResult {
+                            tool_use_id: id.clone(),
+                            content: content.clone(),
+                            is_error,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let refused = blocks.iter().any(|block| matches!(block, ContentBlock::Refusal { .. }));
+        ContentFrame {
+            safety: refused.then(|| SafetyReport {
+                blocked: true,
+                reason: Some("refusal".to_string()),
+                ratings: Vec::new(),
+            }),
+            ..with_blocks(frame, blocks)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_openai_responses_round_trip(frame in frame()) {
+            let parsed = OpenAiResponsesParser.parse(&render(&frame).to_string()).unwrap();
+            prop_assert_eq!(parsed, expected(&frame));
+        }
+    }
+}